@@ -64,10 +64,10 @@ impl Example {
                 }
             }
             Message::FocusAdjacent(direction) => {
-                if let Some(pane) = self.focus {
-                    if let Some(adjacent) = self.panes.adjacent(pane, direction) {
-                        self.focus = Some(adjacent);
-                    }
+                if let Some(pane) = self.focus
+                    && let Some(adjacent) = self.panes.adjacent(pane, direction)
+                {
+                    self.focus = Some(adjacent);
                 }
             }
             Message::Clicked(pane) => {
@@ -95,14 +95,12 @@ impl Example {
                 }
             }
             Message::CloseFocused => {
-                if let Some(pane) = self.focus {
-                    if let Some(Pane { is_pinned, .. }) = self.panes.get(pane) {
-                        if !is_pinned {
-                            if let Some((_, sibling)) = self.panes.close(pane) {
-                                self.focus = Some(sibling);
-                            }
-                        }
-                    }
+                if let Some(pane) = self.focus
+                    && let Some(Pane { is_pinned, .. }) = self.panes.get(pane)
+                    && !is_pinned
+                    && let Some((_, sibling)) = self.panes.close(pane)
+                {
+                    self.focus = Some(sibling);
                 }
             }
         }
@@ -118,7 +116,7 @@ impl Example {
         })
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let focus = self.focus;
         let total_panes = self.panes.len();
 
@@ -0,0 +1,166 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// The days of the week, from the one of the epoch.
+const DAYS: [&str; 7] = [
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+];
+
+/// A date and time in a time zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    /// The seconds since the Unix epoch.
+    pub seconds: u64,
+    /// The offset of the time zone from UTC.
+    pub offset_minutes: i32,
+    pub year: i64,
+    /// The month, from 1.
+    pub month: i64,
+    /// The day of the month, from 1.
+    pub day: i64,
+    pub hour: u64,
+    pub minute: u64,
+    pub second: u64,
+}
+
+impl DateTime {
+    /// The current date and time in the local time zone.
+    pub fn now() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+
+        Self::from_seconds(seconds, local_offset_minutes(seconds))
+    }
+
+    pub fn from_seconds(seconds: u64, offset_minutes: i32) -> Self {
+        let local = seconds as i64 + i64::from(offset_minutes) * 60;
+        let time = local.rem_euclid(86_400) as u64;
+        let (year, month, day) = civil(local.div_euclid(86_400));
+
+        Self {
+            seconds,
+            offset_minutes,
+            year,
+            month,
+            day,
+            hour: time / 3600,
+            minute: time / 60 % 60,
+            second: time % 60,
+        }
+    }
+
+    pub fn month_name(&self) -> &'static str {
+        MONTHS[(self.month as usize).clamp(1, 12) - 1]
+    }
+
+    pub fn day_name(&self) -> &'static str {
+        let local = self.seconds as i64 + i64::from(self.offset_minutes) * 60;
+
+        DAYS[local.div_euclid(86_400).rem_euclid(7) as usize]
+    }
+
+    /// The date and time as `YYYY-MM-DDThh:mm:ss`, without the offset.
+    pub fn iso8601(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// The Gregorian year, month and day of a day since the Unix epoch.
+pub fn civil(days: i64) -> (i64, i64, i64) {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// The offset of the local time zone from UTC at a time, in minutes.
+#[cfg(unix)]
+fn local_offset_minutes(seconds: u64) -> i32 {
+    let time = seconds as libc::time_t;
+
+    // SAFETY: `localtime_r` only writes to the `tm` given, which any bit
+    // pattern of zeros is valid for.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return 0;
+    }
+
+    (tm.tm_gmtoff / 60) as i32
+}
+
+/// The offset of the local time zone from UTC, taken to be none where it
+/// cannot be read.
+#[cfg(not(unix))]
+fn local_offset_minutes(_seconds: u64) -> i32 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil(0), (1970, 1, 1));
+        assert_eq!(civil(-1), (1969, 12, 31));
+        assert_eq!(civil(11_016), (2000, 2, 29));
+        assert_eq!(civil(11_017), (2000, 3, 1));
+        assert_eq!(civil(20_819), (2027, 1, 1));
+    }
+
+    #[test]
+    fn date_times_in_utc() {
+        let time = DateTime::from_seconds(951_827_696, 0);
+
+        assert_eq!(time.iso8601(), "2000-02-29T12:34:56");
+        assert_eq!(time.month_name(), "February");
+        assert_eq!(time.day_name(), "Tuesday");
+    }
+
+    #[test]
+    fn date_times_in_a_time_zone() {
+        // Late on a Tuesday in UTC, early on Wednesday east of it.
+        let east = DateTime::from_seconds(951_868_800 - 1800, 120);
+
+        assert_eq!(east.iso8601(), "2000-03-01T01:30:00");
+        assert_eq!(east.day_name(), "Wednesday");
+
+        let west = DateTime::from_seconds(0, -300);
+
+        assert_eq!(west.iso8601(), "1969-12-31T19:00:00");
+        assert_eq!(west.day_name(), "Wednesday");
+    }
+}
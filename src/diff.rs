@@ -0,0 +1,224 @@
+use std::ops::Range;
+
+/// A contiguous change between two texts, in lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+impl Hunk {
+    pub fn kind(&self) -> Kind {
+        if self.old.is_empty() {
+            Kind::Added
+        } else if self.new.is_empty() {
+            Kind::Deleted
+        } else {
+            Kind::Modified
+        }
+    }
+
+    /// Whether the new lines of both hunks overlap, or touch for deletions.
+    pub fn overlaps(&self, other: &Hunk) -> bool {
+        if self.new.is_empty() || other.new.is_empty() {
+            self.new.start <= other.new.end && other.new.start <= self.new.end
+        } else {
+            self.new.start < other.new.end && other.new.start < self.new.end
+        }
+    }
+}
+
+/// Computes the line hunks turning `old` into `new`.
+pub fn hunks(old: &str, new: &str) -> Vec<Hunk> {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let mut hunks: Vec<Hunk> = Vec::new();
+
+    for op in diff.ops() {
+        if let similar::DiffOp::Equal { .. } = op {
+            continue;
+        }
+
+        let (old, new) = (op.old_range(), op.new_range());
+
+        match hunks.last_mut() {
+            Some(last) if last.old.end == old.start && last.new.end == new.start => {
+                last.old.end = old.end;
+                last.new.end = new.end;
+            }
+            _ => hunks.push(Hunk { old, new }),
+        }
+    }
+
+    hunks
+}
+
+/// Computes the changed byte ranges, by words, between two versions of a
+/// line.
+pub fn inline(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let diff = similar::TextDiff::from_words(old, new);
+
+    let old_offsets = offsets(diff.old_slices());
+    let new_offsets = offsets(diff.new_slices());
+
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+
+    for op in diff.ops() {
+        if let similar::DiffOp::Equal { .. } = op {
+            continue;
+        }
+
+        let (old, new) = (op.old_range(), op.new_range());
+
+        if !old.is_empty() {
+            removed.push(old_offsets[old.start]..old_offsets[old.end]);
+        }
+
+        if !new.is_empty() {
+            added.push(new_offsets[new.start]..new_offsets[new.end]);
+        }
+    }
+
+    (removed, added)
+}
+
+/// Returns the given lines of a text, including their line endings.
+pub fn lines(text: &str, range: Range<usize>) -> &str {
+    let mut offsets = line_offsets(text);
+
+    let start = offsets.nth(range.start).unwrap_or(text.len());
+    let end = if range.is_empty() {
+        start
+    } else {
+        offsets
+            .nth(range.len() - 1)
+            .unwrap_or(text.len())
+            .max(start)
+    };
+
+    &text[start..end]
+}
+
+/// Applies some of the hunks turning `old` into `new` to `old`.
+pub fn apply<'a>(old: &str, new: &str, hunks: impl IntoIterator<Item = &'a Hunk>) -> String {
+    let mut result = String::with_capacity(old.len());
+    let mut line = 0;
+
+    for hunk in hunks {
+        result.push_str(lines(old, line..hunk.old.start));
+        result.push_str(lines(new, hunk.new.clone()));
+
+        line = hunk.old.end;
+    }
+
+    result.push_str(lines(old, line..usize::MAX));
+
+    result
+}
+
+/// The byte offsets at which every slice starts, followed by the total
+/// length.
+fn offsets(slices: &[&str]) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(slices.iter().scan(0, |offset, slice| {
+            *offset += slice.len();
+            Some(*offset)
+        }))
+        .collect()
+}
+
+/// The byte offsets at which every line starts, followed by the length of
+/// the text.
+fn line_offsets(text: &str) -> impl Iterator<Item = usize> + '_ {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+        .filter(move |offset| *offset < text.len())
+        .chain(std::iter::once(text.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hunks_tell_their_kind() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nB\nc\nd\ne\n";
+
+        let hunks = hunks(old, new);
+
+        assert_eq!(
+            hunks,
+            [
+                Hunk {
+                    old: 1..2,
+                    new: 1..2
+                },
+                Hunk {
+                    old: 4..4,
+                    new: 4..5
+                },
+            ]
+        );
+        assert_eq!(hunks[0].kind(), Kind::Modified);
+        assert_eq!(hunks[1].kind(), Kind::Added);
+        assert_eq!(super::hunks(old, "a\nc\nd\n")[0].kind(), Kind::Deleted);
+    }
+
+    #[test]
+    fn apply_some_hunks() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nB\nc\nd\ne\n";
+        let hunks = hunks(old, new);
+
+        assert_eq!(apply(old, new, &hunks), new);
+        assert_eq!(apply(old, new, &[]), old);
+        assert_eq!(apply(old, new, &hunks[..1]), "a\nB\nc\nd\n");
+        assert_eq!(apply(old, new, &hunks[1..]), "a\nb\nc\nd\ne\n");
+    }
+
+    #[test]
+    fn lines_keep_their_endings() {
+        let text = "one\ntwo\nthree";
+
+        assert_eq!(lines(text, 0..1), "one\n");
+        assert_eq!(lines(text, 1..3), "two\nthree");
+        assert_eq!(lines(text, 1..1), "");
+        assert_eq!(lines(text, 2..usize::MAX), "three");
+        assert_eq!(lines(text, 5..6), "");
+    }
+
+    #[test]
+    fn inline_changes_are_words() {
+        let (removed, added) = inline("let x = 1;", "let y = 1;");
+
+        assert_eq!((removed.len(), added.len()), (1, 1));
+        assert_eq!((&removed[0], &added[0]), (&(4..5), &(4..5)));
+    }
+
+    #[test]
+    fn deletions_overlap_what_they_touch() {
+        let deleted = Hunk {
+            old: 2..3,
+            new: 2..2,
+        };
+        let after = Hunk {
+            old: 2..4,
+            new: 2..4,
+        };
+        let before = Hunk {
+            old: 0..1,
+            new: 0..1,
+        };
+
+        assert!(deleted.overlaps(&after));
+        assert!(!deleted.overlaps(&before));
+        assert!(!before.overlaps(&after));
+    }
+}
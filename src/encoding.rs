@@ -0,0 +1,295 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// How the characters of a file are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    /// UTF-16, little-endian, with a byte order mark.
+    Utf16Le,
+    /// UTF-16, big-endian, with a byte order mark.
+    Utf16Be,
+    /// ISO-8859-1, assumed for files that are not valid UTF-8.
+    Latin1,
+}
+
+/// How the lines of a file end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+/// How the text of a file is stored, to be kept when it is saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Format {
+    pub encoding: Encoding,
+    /// The line ending of most lines.
+    pub line_ending: LineEnding,
+    /// Whether the lines ended both ways when loaded. They all end with
+    /// `line_ending` once saved.
+    pub mixed: bool,
+    /// Whether some bytes could not be decoded and were replaced, so that
+    /// saving would not write them back.
+    #[serde(default)]
+    pub lossy: bool,
+}
+
+/// A character that an encoding cannot store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    pub encoding: Encoding,
+    pub character: char,
+}
+
+/// The bytes looked at to tell whether a file is binary.
+pub const SAMPLE: usize = 8000;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+impl Encoding {
+    pub const ALL: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Utf8Bom,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Latin1,
+    ];
+}
+
+impl LineEnding {
+    pub const ALL: [LineEnding; 2] = [LineEnding::Lf, LineEnding::Crlf];
+}
+
+/// Whether the contents of a file are not text, telling by a null byte at
+/// their start like Git does. UTF-16 text, full of them, is told by its
+/// byte order mark.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.starts_with(UTF16_LE_BOM) || bytes.starts_with(UTF16_BE_BOM) {
+        return false;
+    }
+
+    bytes[..bytes.len().min(SAMPLE)].contains(&0)
+}
+
+/// Decodes the contents of a file, telling how they were stored. Lines end
+/// with `\n` in the text, whatever their ending in the file.
+pub fn decode(bytes: &[u8]) -> (String, Format) {
+    let ((text, lossy), encoding) = if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        (utf8(rest), Encoding::Utf8Bom)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+        (utf16(rest, u16::from_le_bytes), Encoding::Utf16Le)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+        (utf16(rest, u16::from_be_bytes), Encoding::Utf16Be)
+    } else {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => ((text.to_owned(), false), Encoding::Utf8),
+            Err(_) => (
+                (bytes.iter().map(|&byte| char::from(byte)).collect(), false),
+                Encoding::Latin1,
+            ),
+        }
+    };
+
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+
+    let format = Format {
+        encoding,
+        line_ending: if crlf > lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        },
+        mixed: crlf > 0 && lf > 0,
+        lossy,
+    };
+
+    let text = if crlf > 0 {
+        text.replace("\r\n", "\n")
+    } else {
+        text
+    };
+
+    (text, format)
+}
+
+/// Encodes a text to be saved in a format.
+pub fn encode(text: &str, format: Format) -> Result<Vec<u8>, Error> {
+    let text = match format.line_ending {
+        LineEnding::Lf => std::borrow::Cow::Borrowed(text),
+        LineEnding::Crlf => std::borrow::Cow::Owned(text.replace('\n', "\r\n")),
+    };
+
+    Ok(match format.encoding {
+        Encoding::Utf8 => text.into_owned().into_bytes(),
+        Encoding::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+        Encoding::Utf16Le => UTF16_LE_BOM
+            .iter()
+            .copied()
+            .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect(),
+        Encoding::Utf16Be => UTF16_BE_BOM
+            .iter()
+            .copied()
+            .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect(),
+        Encoding::Latin1 => text
+            .chars()
+            .map(|character| {
+                u8::try_from(character).map_err(|_| Error {
+                    encoding: Encoding::Latin1,
+                    character,
+                })
+            })
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Decodes UTF-8, replacing what is not valid, and tells whether anything
+/// was.
+fn utf8(bytes: &[u8]) -> (String, bool) {
+    match String::from_utf8_lossy(bytes) {
+        std::borrow::Cow::Borrowed(text) => (text.to_owned(), false),
+        std::borrow::Cow::Owned(text) => (text, true),
+    }
+}
+
+/// Decodes UTF-16 from pairs of bytes, replacing what is not valid, and
+/// tells whether anything was, or a byte was left over.
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> (String, bool) {
+    let pairs = bytes.chunks_exact(2);
+    let mut lossy = !pairs.remainder().is_empty();
+    let units = pairs.map(|pair| unit([pair[0], pair[1]]));
+
+    let text = char::decode_utf16(units)
+        .map(|character| {
+            character.unwrap_or_else(|_| {
+                lossy = true;
+
+                char::REPLACEMENT_CHARACTER
+            })
+        })
+        .collect();
+
+    (text, lossy)
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 with BOM",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Latin1 => "Latin-1",
+        })
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} cannot be encoded in {}",
+            self.character, self.encoding
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_tells_the_encoding() {
+        assert_eq!(decode(b"caf\xc3\xa9").1.encoding, Encoding::Utf8);
+        assert_eq!(
+            decode(b"\xef\xbb\xbfcaf\xc3\xa9").1.encoding,
+            Encoding::Utf8Bom
+        );
+        assert_eq!(decode(b"\xff\xfec\0a\0").1.encoding, Encoding::Utf16Le);
+        assert_eq!(decode(b"\xfe\xff\0c\0a").1.encoding, Encoding::Utf16Be);
+        assert_eq!(decode(b"caf\xe9").0, "café");
+        assert_eq!(decode(b"caf\xe9").1.encoding, Encoding::Latin1);
+    }
+
+    #[test]
+    fn decode_and_encode_round_trip() {
+        let files: [&[u8]; 5] = [
+            b"one\ntwo\n",
+            b"\xef\xbb\xbfone\r\ntwo\r\n",
+            b"\xff\xfeo\0n\0e\0\r\0\n\0",
+            b"\xfe\xff\0o\0n\0e\0\n",
+            b"caf\xe9\r\n",
+        ];
+
+        for bytes in files {
+            let (text, format) = decode(bytes);
+
+            assert!(!text.contains('\r'));
+            assert!(!format.lossy);
+            assert_eq!(encode(&text, format).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn decode_tells_the_line_endings() {
+        let (text, format) = decode(b"a\r\nb\r\nc\n");
+
+        assert_eq!(text, "a\nb\nc\n");
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+        assert!(format.mixed);
+        assert!(!decode(b"a\nb\n").1.mixed);
+    }
+
+    #[test]
+    fn decode_flags_what_it_replaces() {
+        assert!(decode(b"\xef\xbb\xbfcaf\xe9").1.lossy);
+        // An unpaired surrogate.
+        assert!(decode(b"\xff\xfe\x00\xd8a\0").1.lossy);
+        // A byte left over.
+        assert!(decode(b"\xff\xfea\0b").1.lossy);
+        assert!(!decode(b"caf\xe9").1.lossy);
+    }
+
+    #[test]
+    fn encode_rejects_what_latin1_cannot_store() {
+        let format = Format {
+            encoding: Encoding::Latin1,
+            ..Format::default()
+        };
+
+        assert_eq!(
+            encode("€", format),
+            Err(Error {
+                encoding: Encoding::Latin1,
+                character: '€',
+            })
+        );
+    }
+
+    #[test]
+    fn binary_is_told_by_null_bytes() {
+        assert!(is_binary(b"\x7fELF\0\0"));
+        assert!(!is_binary(b"text"));
+        assert!(!is_binary(b"\xff\xfet\0"));
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::settings::Command;
+
+/// Placeholder replaced with the path of the file in formatter arguments.
+const FILE: &str = "{file}";
+
+/// How long a formatter may run before it is stopped and reported as
+/// failing, e.g. when it waits on a terminal.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub message: String,
+}
+
+/// Pipes the text of a file through a formatter, run from the directory of
+/// the file, returning what it writes out.
+///
+/// A formatter exiting with an error, running for too long, or writing
+/// nothing for some text, is reported as failing.
+pub async fn format(command: Command, path: PathBuf, text: String) -> Result<String, Error> {
+    let name = command.command.clone();
+
+    tokio::task::spawn_blocking(move || run(&command, &path, &text))
+        .await
+        .map_err(|error| Error {
+            message: error.to_string(),
+        })?
+        .map_err(|message| Error {
+            message: format!("{name}: {message}"),
+        })
+}
+
+fn run(command: &Command, path: &Path, text: &str) -> Result<String, String> {
+    let file = path.to_string_lossy();
+
+    let mut process = process::Command::new(&command.command);
+
+    process
+        .args(command.args.iter().map(|arg| arg.replace(FILE, &file)))
+        .current_dir(path.parent().unwrap_or(Path::new(".")))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // In a group of its own, to be stopped along with the processes it
+    // starts.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut process, 0);
+
+    let mut child = process.spawn().map_err(|error| error.to_string())?;
+
+    let result = communicate(&mut child, text, Instant::now() + TIMEOUT);
+
+    if result.is_err() {
+        stop(&mut child);
+    }
+
+    let (output, input) = result?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        return Err(match stderr.trim() {
+            "" => output.status.to_string(),
+            stderr => stderr.to_owned(),
+        });
+    }
+
+    input.map_err(|error| error.to_string())?;
+
+    let formatted = String::from_utf8(output.stdout)
+        .map_err(|_| String::from("the output is not valid UTF-8"))?;
+
+    if formatted.trim().is_empty() && !text.trim().is_empty() {
+        return Err(String::from("nothing was written out"));
+    }
+
+    Ok(formatted)
+}
+
+/// Writes the text to a formatter and reads its outputs until it exits,
+/// returning them with the result of the writing, or failing once the
+/// deadline is passed.
+fn communicate(
+    child: &mut process::Child,
+    text: &str,
+    deadline: Instant,
+) -> Result<(process::Output, io::Result<()>), String> {
+    // Written and read from other threads, in case the formatter fills its
+    // output before reading all of its input.
+    let input = child.stdin.take().map(|mut stdin| {
+        let text = text.to_owned();
+
+        background(move || stdin.write_all(text.as_bytes()))
+    });
+
+    let stdout = child.stdout.take().map(read);
+    let stderr = child.stderr.take().map(read);
+
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|error| error.to_string())? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            return Err(timed_out());
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let output = process::Output {
+        status,
+        stdout: receive(stdout, deadline)?.map_err(|error| error.to_string())?,
+        stderr: receive(stderr, deadline)?.map_err(|error| error.to_string())?,
+    };
+
+    Ok((output, receive(input, deadline)?))
+}
+
+/// Stops a formatter and the processes it started, for its pipes to close
+/// and the threads using them to finish.
+fn stop(child: &mut process::Child) {
+    #[cfg(unix)]
+    if let Ok(group) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: `kill` only sends a signal, here to the group of the
+        // formatter, which it was started in.
+        unsafe {
+            libc::kill(-group, libc::SIGKILL);
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Reads an output of the formatter to its end, in another thread.
+fn read(mut output: impl Read + Send + 'static) -> mpsc::Receiver<io::Result<Vec<u8>>> {
+    background(move || {
+        let mut bytes = Vec::new();
+
+        output.read_to_end(&mut bytes).map(|_| bytes)
+    })
+}
+
+/// Runs some work in another thread, sending its result once done.
+fn background<T: Send + 'static>(
+    work: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> mpsc::Receiver<io::Result<T>> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+
+    receiver
+}
+
+/// Waits for the result of some work done in another thread, failing once
+/// the deadline is passed.
+fn receive<T: Default>(
+    result: Option<mpsc::Receiver<io::Result<T>>>,
+    deadline: Instant,
+) -> Result<io::Result<T>, String> {
+    let Some(result) = result else {
+        return Ok(Ok(T::default()));
+    };
+
+    match result.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(result) => Ok(result),
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(timed_out()),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Ok(Err(io::Error::other(
+            "could not communicate with the formatter",
+        ))),
+    }
+}
+
+fn timed_out() -> String {
+    format!("timed out after {} seconds", TIMEOUT.as_secs())
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> Command {
+        Command {
+            command: String::from("sh"),
+            args: vec![String::from("-c"), script.to_owned()],
+        }
+    }
+
+    #[test]
+    fn formatters_pipe_the_text() {
+        let path = std::env::temp_dir().join("file.txt");
+
+        assert_eq!(
+            run(&shell("tr a-z A-Z"), &path, "text\n"),
+            Ok("TEXT\n".into())
+        );
+        assert_eq!(
+            run(&shell("echo invalid >&2; exit 1"), &path, "text\n"),
+            Err("invalid".into())
+        );
+        assert_eq!(
+            run(&shell("cat > /dev/null"), &path, "text\n"),
+            Err("nothing was written out".into())
+        );
+    }
+
+    #[test]
+    fn formatters_leaving_their_output_open_time_out() {
+        let path = std::env::temp_dir().join("file.txt");
+        let start = Instant::now();
+
+        let result = run(&shell("cat; sleep 60 &"), &path, "text\n");
+
+        assert_eq!(result, Err(timed_out()));
+        assert!(start.elapsed() < TIMEOUT + Duration::from_secs(5));
+    }
+}
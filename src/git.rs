@@ -0,0 +1,733 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::{diff, encoding};
+
+mod history;
+
+pub use history::{Annotation, Commit, CommitDiff, Time, blame, commit_diff, log};
+
+/// The state of a file in the working tree, ordered by precedence when
+/// several files of a directory have a different status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Ignored,
+    Untracked,
+    Added,
+    Modified,
+    Conflicted,
+}
+
+/// The status of every changed path of a repository, by absolute path.
+#[derive(Debug, Clone, Default)]
+pub struct Statuses {
+    files: BTreeMap<PathBuf, Status>,
+    directories: BTreeMap<PathBuf, Status>,
+}
+
+/// The changes of a repository, along with its branches and stashes.
+#[derive(Debug, Clone, Default)]
+pub struct Changes {
+    pub staged: Vec<Change>,
+    pub unstaged: Vec<Change>,
+    /// The current branch, unless `HEAD` is detached.
+    pub branch: Option<String>,
+    pub branches: Vec<String>,
+    pub stashes: Vec<String>,
+    /// The message of the commit at `HEAD`, for amending it.
+    pub message: Option<String>,
+}
+
+/// A changed file, in the index or in the working tree.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub path: PathBuf,
+    pub kind: Kind,
+    /// The contents before the change.
+    pub old: String,
+    /// The contents after the change.
+    pub new: String,
+    /// The format of the contents after the change, to stage them back.
+    pub format: encoding::Format,
+    pub hunks: Vec<diff::Hunk>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Conflicted,
+}
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub message: String,
+}
+
+impl Statuses {
+    /// Returns the status of a file, or the most important status among the
+    /// files of a directory.
+    pub fn get(&self, path: &Path) -> Option<Status> {
+        if let Some(status) = self.files.get(path).or(self.directories.get(path)) {
+            return Some(*status);
+        }
+
+        // Untracked and ignored directories are reported as a whole.
+        path.ancestors().skip(1).find_map(|ancestor| {
+            self.files
+                .get(ancestor)
+                .filter(|status| matches!(status, Status::Ignored | Status::Untracked))
+                .copied()
+        })
+    }
+
+    fn insert(&mut self, workdir: &Path, path: PathBuf, status: Status) {
+        if status != Status::Ignored {
+            for directory in path.ancestors().skip(1) {
+                if !directory.starts_with(workdir) {
+                    break;
+                }
+
+                let entry = self
+                    .directories
+                    .entry(directory.to_owned())
+                    .or_insert(status);
+
+                *entry = (*entry).max(status);
+            }
+        }
+
+        self.files.insert(path, status);
+    }
+}
+
+impl Status {
+    fn from_git(status: git2::Status) -> Option<Self> {
+        if status.is_conflicted() {
+            Some(Status::Conflicted)
+        } else if status.is_ignored() {
+            Some(Status::Ignored)
+        } else if status.is_wt_new() {
+            Some(Status::Untracked)
+        } else if status.is_index_new() {
+            Some(Status::Added)
+        } else if status.is_empty() {
+            None
+        } else {
+            Some(Status::Modified)
+        }
+    }
+
+    pub fn badge(self) -> &'static str {
+        match self {
+            Status::Ignored => "I",
+            Status::Untracked => "U",
+            Status::Added => "A",
+            Status::Modified => "M",
+            Status::Conflicted => "C",
+        }
+    }
+}
+
+impl Kind {
+    pub fn badge(self) -> &'static str {
+        match self {
+            Kind::Added => "A",
+            Kind::Modified => "M",
+            Kind::Deleted => "D",
+            Kind::Renamed => "R",
+            Kind::Conflicted => "C",
+        }
+    }
+
+    /// The status a file with this change is shown with.
+    pub fn status(self) -> Status {
+        match self {
+            Kind::Added => Status::Added,
+            Kind::Modified | Kind::Deleted | Kind::Renamed => Status::Modified,
+            Kind::Conflicted => Status::Conflicted,
+        }
+    }
+}
+
+/// Computes the status of the repository containing `path`, if any.
+pub async fn statuses(path: PathBuf) -> Result<Statuses, Error> {
+    blocking(move || {
+        let repository = match git2::Repository::discover(&path) {
+            Ok(repository) => repository,
+            Err(error) if error.code() == git2::ErrorCode::NotFound => {
+                return Ok(Statuses::default());
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        let Some(workdir) = repository.workdir() else {
+            return Ok(Statuses::default());
+        };
+
+        let mut options = git2::StatusOptions::new();
+        options
+            .include_untracked(true)
+            .include_ignored(true)
+            .recurse_untracked_dirs(false)
+            .recurse_ignored_dirs(false)
+            .exclude_submodules(true);
+
+        let mut statuses = Statuses::default();
+
+        for entry in repository.statuses(Some(&mut options))?.iter() {
+            let (Some(path), Some(status)) = (entry.path(), Status::from_git(entry.status()))
+            else {
+                continue;
+            };
+
+            statuses.insert(workdir, workdir.join(path), status);
+        }
+
+        Ok(statuses)
+    })
+    .await
+}
+
+/// Lists the staged and unstaged changes of the repository containing
+/// `path`, if any.
+pub async fn changes(path: PathBuf) -> Result<Changes, Error> {
+    blocking(move || {
+        let repository = match git2::Repository::discover(&path) {
+            Ok(repository) => repository,
+            Err(error) if error.code() == git2::ErrorCode::NotFound => {
+                return Ok(Changes::default());
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        let Some(workdir) = repository.workdir().map(Path::to_owned) else {
+            return Ok(Changes::default());
+        };
+
+        let mut options = git2::StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true)
+            .exclude_submodules(true);
+
+        let index = repository.index()?;
+        let mut changes = Changes::default();
+
+        for entry in repository.statuses(Some(&mut options))?.iter() {
+            let Some(relative) = entry.path().map(PathBuf::from) else {
+                continue;
+            };
+
+            let status = entry.status();
+            let path = workdir.join(&relative);
+
+            let in_index = if status.is_index_new() {
+                Some(Kind::Added)
+            } else if status.is_index_deleted() {
+                Some(Kind::Deleted)
+            } else if status.is_index_renamed() {
+                Some(Kind::Renamed)
+            } else if status.is_index_modified() || status.is_index_typechange() {
+                Some(Kind::Modified)
+            } else {
+                None
+            };
+
+            let in_workdir = if status.is_conflicted() {
+                Some(Kind::Conflicted)
+            } else if status.is_wt_new() {
+                Some(Kind::Added)
+            } else if status.is_wt_deleted() {
+                Some(Kind::Deleted)
+            } else if status.is_wt_renamed() {
+                Some(Kind::Renamed)
+            } else if status.is_wt_modified() || status.is_wt_typechange() {
+                Some(Kind::Modified)
+            } else {
+                None
+            };
+
+            if let Some(kind) = in_index {
+                let old = blob_at_head(&repository, &relative)?.unwrap_or_default();
+                let (new, format, _) = staged(&repository, &index, &relative)?;
+
+                changes
+                    .staged
+                    .push(Change::new(path.clone(), kind, old, new, format));
+            }
+
+            if let Some(kind) = in_workdir {
+                let (old, format, _) = staged(&repository, &index, &relative)?;
+                let (new, format) = match kind {
+                    Kind::Deleted => (String::new(), format),
+                    _ => std::fs::read(&path)
+                        .map(|bytes| encoding::decode(&bytes))
+                        .unwrap_or_default(),
+                };
+
+                changes
+                    .unstaged
+                    .push(Change::new(path, kind, old, new, format));
+            }
+        }
+
+        changes.branch = match repository.head() {
+            Ok(head) if head.is_branch() => head.shorthand().map(str::to_owned),
+            Ok(_) => None,
+            Err(error) if error.code() == git2::ErrorCode::UnbornBranch => repository
+                .find_reference("HEAD")?
+                .symbolic_target()
+                .and_then(|target| target.strip_prefix("refs/heads/"))
+                .map(str::to_owned),
+            Err(error) => return Err(error.into()),
+        };
+
+        for branch in repository.branches(Some(git2::BranchType::Local))? {
+            let (branch, _) = branch?;
+
+            if let Some(name) = branch.name()? {
+                changes.branches.push(name.to_owned());
+            }
+        }
+
+        changes.message =
+            head_commit(&repository)?.and_then(|commit| commit.message().map(str::to_owned));
+
+        let mut repository = repository;
+
+        repository.stash_foreach(|_, message, _| {
+            changes.stashes.push(message.to_owned());
+            true
+        })?;
+
+        Ok(changes)
+    })
+    .await
+}
+
+/// Stages the whole contents of some files, or their deletion.
+pub async fn stage(paths: Vec<PathBuf>) -> Result<(), Error> {
+    blocking(move || {
+        for path in paths {
+            let (repository, relative) = open_existing(&path)?;
+            let mut index = repository.index()?;
+
+            if path.exists() {
+                index.add_path(&relative)?;
+            } else {
+                index.remove_path(&relative)?;
+            }
+
+            index.write()?;
+        }
+
+        Ok(())
+    })
+    .await
+}
+
+/// Resets the staged contents of some files to `HEAD`.
+pub async fn unstage(paths: Vec<PathBuf>) -> Result<(), Error> {
+    blocking(move || {
+        for path in paths {
+            let (repository, relative) = open_existing(&path)?;
+            let head = head_commit(&repository)?;
+
+            repository.reset_default(head.as_ref().map(|commit| commit.as_object()), [relative])?;
+        }
+
+        Ok(())
+    })
+    .await
+}
+
+/// Commits the staged changes of the repository containing `path`, or
+/// replaces the commit at `HEAD` with them.
+pub async fn commit(path: PathBuf, message: String, amend: bool) -> Result<String, Error> {
+    blocking(move || {
+        let repository = git2::Repository::discover(&path)?;
+        let mut index = repository.index()?;
+        let tree = repository.find_tree(index.write_tree()?)?;
+        let head = head_commit(&repository)?;
+
+        let id = match head {
+            Some(head) if amend => {
+                head.amend(Some("HEAD"), None, None, None, Some(&message), Some(&tree))?
+            }
+            head => {
+                let signature = repository.signature()?;
+                let parents: Vec<_> = head.iter().collect();
+
+                repository.commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    &message,
+                    &tree,
+                    &parents,
+                )?
+            }
+        };
+
+        Ok(id.to_string()[..7].to_owned())
+    })
+    .await
+}
+
+/// Switches the working tree to a local branch, keeping local changes that
+/// do not conflict.
+pub async fn checkout(path: PathBuf, branch: String) -> Result<(), Error> {
+    blocking(move || {
+        let repository = git2::Repository::discover(&path)?;
+        let branch = repository.find_branch(&branch, git2::BranchType::Local)?;
+        let reference = branch.get();
+
+        repository.checkout_tree(
+            &reference.peel(git2::ObjectType::Commit)?,
+            Some(git2::build::CheckoutBuilder::new().safe()),
+        )?;
+
+        repository.set_head(reference.name().ok_or_else(|| Error {
+            message: String::from("branch name is not valid UTF-8"),
+        })?)?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Creates a branch at `HEAD` and switches to it.
+pub async fn create_branch(path: PathBuf, branch: String) -> Result<(), Error> {
+    blocking(move || {
+        let repository = git2::Repository::discover(&path)?;
+
+        let Some(head) = head_commit(&repository)? else {
+            return Err(Error {
+                message: String::from("cannot create a branch before the first commit"),
+            });
+        };
+
+        let branch = repository.branch(&branch, &head, false)?;
+
+        repository.set_head(branch.get().name().ok_or_else(|| Error {
+            message: String::from("branch name is not valid UTF-8"),
+        })?)?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Stashes the local changes, including untracked files.
+pub async fn stash(path: PathBuf) -> Result<(), Error> {
+    blocking(move || {
+        let mut repository = git2::Repository::discover(&path)?;
+        let signature = repository.signature()?;
+
+        repository.stash_save(&signature, "", Some(git2::StashFlags::INCLUDE_UNTRACKED))?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Applies a stash and drops it.
+pub async fn pop(path: PathBuf, stash: usize) -> Result<(), Error> {
+    blocking(move || {
+        let mut repository = git2::Repository::discover(&path)?;
+
+        repository.stash_pop(stash, None)?;
+
+        Ok(())
+    })
+    .await
+}
+
+/// Returns the common ancestor of a conflicted file, as recorded in the
+/// index during a merge.
+pub async fn ancestor(path: PathBuf) -> Result<Option<String>, Error> {
+    blocking(move || {
+        let Some((repository, relative)) = open(&path)? else {
+            return Ok(None);
+        };
+
+        let Some(entry) = repository.index()?.get_path(&relative, 1) else {
+            return Ok(None);
+        };
+
+        let blob = repository.find_blob(entry.id)?;
+
+        Ok(Some(decode(blob.content())))
+    })
+    .await
+}
+
+/// Returns the contents of a file at `HEAD`, if it is tracked.
+pub async fn head(path: PathBuf) -> Result<Option<String>, Error> {
+    blocking(move || {
+        let Some((repository, relative)) = open(&path)? else {
+            return Ok(None);
+        };
+
+        blob_at_head(&repository, &relative)
+    })
+    .await
+}
+
+/// Stages the changes of `contents` overlapping the given hunk, leaving the
+/// other changes of the file unstaged.
+///
+/// The staged contents are saved in the given format, like the file.
+pub async fn stage_hunk(
+    path: PathBuf,
+    contents: String,
+    format: encoding::Format,
+    hunk: diff::Hunk,
+) -> Result<(), Error> {
+    blocking(move || {
+        let (repository, relative) = open_existing(&path)?;
+        let mut index = repository.index()?;
+        let (staged, _, mode) = staged(&repository, &index, &relative)?;
+
+        let hunks = diff::hunks(&staged, &contents);
+        let staged = diff::apply(
+            &staged,
+            &contents,
+            hunks.iter().filter(|other| other.overlaps(&hunk)),
+        );
+
+        write_index(
+            &mut index,
+            &relative,
+            mode,
+            &encoding::encode(&staged, format)?,
+        )
+    })
+    .await
+}
+
+/// Unstages a hunk of the staged changes of a file, as listed in its
+/// [`Change`].
+pub async fn unstage_hunk(path: PathBuf, hunk: diff::Hunk) -> Result<(), Error> {
+    blocking(move || {
+        let (repository, relative) = open_existing(&path)?;
+        let mut index = repository.index()?;
+        let (staged, format, mode) = staged(&repository, &index, &relative)?;
+        let head = blob_at_head(&repository, &relative)?.unwrap_or_default();
+
+        let hunks = diff::hunks(&head, &staged);
+        let staged = diff::apply(&head, &staged, hunks.iter().filter(|other| **other != hunk));
+
+        write_index(
+            &mut index,
+            &relative,
+            mode,
+            &encoding::encode(&staged, format)?,
+        )
+    })
+    .await
+}
+
+/// Whether some changed paths belong to the internals of a repository.
+pub fn is_internal(paths: &[PathBuf]) -> bool {
+    paths.iter().any(|path| {
+        path.components()
+            .any(|component| component.as_os_str() == ".git")
+    })
+}
+
+/// Opens the repository containing `path`, returning the path relative to
+/// its working directory.
+fn open(path: &Path) -> Result<Option<(git2::Repository, PathBuf)>, Error> {
+    let repository = match git2::Repository::discover(path.parent().unwrap_or(path)) {
+        Ok(repository) => repository,
+        Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+
+    let Some(relative) = repository
+        .workdir()
+        .and_then(|workdir| path.strip_prefix(workdir).ok())
+        .map(Path::to_owned)
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some((repository, relative)))
+}
+
+/// Opens the repository containing `path`, failing if there is none.
+fn open_existing(path: &Path) -> Result<(git2::Repository, PathBuf), Error> {
+    open(path)?.ok_or_else(|| Error {
+        message: format!("{} is not in a repository", path.display()),
+    })
+}
+
+/// Returns the contents of a file at `HEAD`, if it is tracked.
+fn blob_at_head(repository: &git2::Repository, relative: &Path) -> Result<Option<String>, Error> {
+    let Some(commit) = head_commit(repository)? else {
+        return Ok(None);
+    };
+
+    let entry = match commit.tree()?.get_path(relative) {
+        Ok(entry) => entry,
+        Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+
+    let blob = repository.find_blob(entry.id())?;
+
+    Ok(Some(decode(blob.content())))
+}
+
+/// Returns the commit of `HEAD`, unless the current branch has none yet.
+fn head_commit(repository: &git2::Repository) -> Result<Option<git2::Commit<'_>>, Error> {
+    match repository.head() {
+        Ok(head) => Ok(Some(head.peel_to_commit()?)),
+        Err(error) if error.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Returns the staged contents of a file with their format and its mode, or
+/// nothing for a new file.
+fn staged(
+    repository: &git2::Repository,
+    index: &git2::Index,
+    relative: &Path,
+) -> Result<(String, encoding::Format, u32), Error> {
+    match index.get_path(relative, 0) {
+        Some(entry) => {
+            let blob = repository.find_blob(entry.id)?;
+            let (contents, format) = encoding::decode(blob.content());
+
+            Ok((contents, format, entry.mode))
+        }
+        None => Ok((String::new(), encoding::Format::default(), 0o100644)),
+    }
+}
+
+/// Stages some contents for a file.
+fn write_index(
+    index: &mut git2::Index,
+    relative: &Path,
+    mode: u32,
+    contents: &[u8],
+) -> Result<(), Error> {
+    let entry = git2::IndexEntry {
+        ctime: git2::IndexTime::new(0, 0),
+        mtime: git2::IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: contents.len() as u32,
+        id: git2::Oid::zero(),
+        flags: 0,
+        flags_extended: 0,
+        path: relative.to_string_lossy().into_owned().into_bytes(),
+    };
+
+    index.add_frombuffer(&entry, contents)?;
+    index.write()?;
+
+    Ok(())
+}
+
+/// Decodes the contents of a file like the editor does, for them to be
+/// compared with its buffers.
+fn decode(bytes: &[u8]) -> String {
+    encoding::decode(bytes).0
+}
+
+/// Runs a blocking git operation off the UI thread.
+async fn blocking<T: Send + 'static>(
+    operation: impl FnOnce() -> Result<T, Error> + Send + 'static,
+) -> Result<T, Error> {
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|error| Error {
+            message: error.to_string(),
+        })?
+}
+
+impl Change {
+    fn new(path: PathBuf, kind: Kind, old: String, new: String, format: encoding::Format) -> Self {
+        let hunks = diff::hunks(&old, &new);
+
+        Self {
+            path,
+            kind,
+            old,
+            new,
+            format,
+            hunks,
+        }
+    }
+}
+
+impl From<git2::Error> for Error {
+    fn from(error: git2::Error) -> Self {
+        Self {
+            message: error.message().to_owned(),
+        }
+    }
+}
+
+impl From<encoding::Error> for Error {
+    fn from(error: encoding::Error) -> Self {
+        Self {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "git: {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn staged_hunks_keep_the_format_of_the_file() {
+        let root = std::env::temp_dir().join(format!("rine-git-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+
+        let repository = git2::Repository::init(&root).unwrap();
+        let path = repository.workdir().unwrap().join("file.txt");
+        std::fs::write(&path, "a\r\nb\r\nc\r\nd\r\n").unwrap();
+
+        let mut index = repository.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+
+        let (contents, format) = encoding::decode(b"a\r\nB\r\nc\r\nd\r\ne\r\n");
+        let hunk = diff::hunks("a\nb\nc\nd\n", &contents)[0].clone();
+
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(stage_hunk(path, contents, format, hunk))
+            .unwrap();
+
+        let repository = git2::Repository::open(&root).unwrap();
+        let index = repository.index().unwrap();
+        let entry = index.get_path(Path::new("file.txt"), 0).unwrap();
+        let blob = repository.find_blob(entry.id).unwrap();
+
+        assert_eq!(blob.content(), b"a\r\nB\r\nc\r\nd\r\n");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,342 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::{date, encoding};
+
+use super::{Error, blocking, decode, open};
+
+/// A commit of the history, as listed in the log.
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub id: String,
+    pub author: String,
+    pub time: Time,
+    pub message: String,
+    pub parents: Vec<String>,
+    /// The lanes of the graph on the row of this commit.
+    pub graph: String,
+}
+
+/// The moment of a commit, in the time zone of its author.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Time {
+    pub seconds: i64,
+    pub offset_minutes: i32,
+}
+
+/// The lines of a file last changed by the same commit.
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub lines: Range<usize>,
+    /// The commit, or `None` for uncommitted changes.
+    pub commit: Option<Commit>,
+}
+
+/// The changes of a commit to one of its files.
+#[derive(Debug, Clone)]
+pub struct CommitDiff {
+    pub commit: Commit,
+    /// The changed files, relative to the working directory.
+    pub files: Vec<PathBuf>,
+    pub path: PathBuf,
+    pub old: String,
+    pub new: String,
+}
+
+impl Commit {
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(7)]
+    }
+
+    pub fn summary(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+
+    fn new(commit: &git2::Commit<'_>) -> Self {
+        let author = commit.author();
+
+        Self {
+            id: commit.id().to_string(),
+            author: author.name().unwrap_or_default().to_owned(),
+            time: Time {
+                seconds: author.when().seconds(),
+                offset_minutes: author.when().offset_minutes(),
+            },
+            message: commit.message().unwrap_or_default().to_owned(),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+            graph: String::new(),
+        }
+    }
+}
+
+impl Time {
+    /// Formats the day, as `YYYY-MM-DD`.
+    pub fn date(self) -> String {
+        let (year, month, day) = self.civil();
+
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+
+    /// Formats the day and minute, as `YYYY-MM-DD HH:MM`.
+    pub fn date_time(self) -> String {
+        let seconds = (self.seconds + i64::from(self.offset_minutes) * 60).rem_euclid(86_400);
+
+        format!(
+            "{} {:02}:{:02}",
+            self.date(),
+            seconds / 3600,
+            seconds % 3600 / 60
+        )
+    }
+
+    /// The Gregorian date of the local day.
+    fn civil(self) -> (i64, i64, i64) {
+        date::civil((self.seconds + i64::from(self.offset_minutes) * 60).div_euclid(86_400))
+    }
+}
+
+/// Lists the commits reachable from `HEAD`, newest first, optionally only
+/// those changing a path.
+pub async fn log(root: PathBuf, path: Option<PathBuf>, limit: usize) -> Result<Vec<Commit>, Error> {
+    blocking(move || {
+        let repository = match git2::Repository::discover(&root) {
+            Ok(repository) => repository,
+            Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let relative = match &path {
+            Some(path) => Some(relative(&repository, path)?),
+            None => None,
+        };
+
+        let mut walk = repository.revwalk()?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+        match walk.push_head() {
+            Ok(()) => {}
+            Err(error) if error.code() == git2::ErrorCode::UnbornBranch => {
+                return Ok(Vec::new());
+            }
+            Err(error) => return Err(error.into()),
+        }
+
+        let mut commits = Vec::new();
+
+        for id in walk {
+            let commit = repository.find_commit(id?)?;
+
+            if let Some(relative) = &relative
+                && !changes(&commit, relative)?
+            {
+                continue;
+            }
+
+            commits.push(Commit::new(&commit));
+
+            if commits.len() == limit {
+                break;
+            }
+        }
+
+        // The parents of a filtered commit are not listed.
+        if relative.is_none() {
+            graph(&mut commits);
+        }
+
+        Ok(commits)
+    })
+    .await
+}
+
+/// Finds the commit that last changed every line of a file, taking the
+/// unsaved contents of its buffer into account, saved in the given format.
+pub async fn blame(
+    path: PathBuf,
+    contents: String,
+    format: encoding::Format,
+) -> Result<Vec<Annotation>, Error> {
+    blocking(move || {
+        let Some((repository, relative)) = open(&path)? else {
+            return Ok(Vec::new());
+        };
+
+        let blame = match repository.blame_file(&relative, None) {
+            Ok(blame) => blame,
+            Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let blame = blame.blame_buffer(&encoding::encode(&contents, format)?)?;
+
+        let mut annotations = Vec::new();
+
+        for hunk in blame.iter() {
+            let start = hunk.final_start_line().saturating_sub(1);
+            let id = hunk.final_commit_id();
+
+            let commit = if id.is_zero() {
+                None
+            } else {
+                Some(Commit::new(&repository.find_commit(id)?))
+            };
+
+            annotations.push(Annotation {
+                lines: start..start + hunk.lines_in_hunk(),
+                commit,
+            });
+        }
+
+        Ok(annotations)
+    })
+    .await
+}
+
+/// Loads the changes of a commit to one of its files, or to its first
+/// changed file.
+pub async fn commit_diff(
+    root: PathBuf,
+    id: String,
+    path: Option<PathBuf>,
+) -> Result<CommitDiff, Error> {
+    blocking(move || {
+        let repository = git2::Repository::discover(&root)?;
+        let commit = repository.find_commit(git2::Oid::from_str(&id)?)?;
+        let tree = commit.tree()?;
+        let parent = match commit.parents().next() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+
+        let diff = repository.diff_tree_to_tree(parent.as_ref(), Some(&tree), None)?;
+
+        let files: Vec<PathBuf> = diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+            .map(Path::to_owned)
+            .collect();
+
+        let path = match path {
+            Some(path) => relative(&repository, &path)?,
+            None => files.first().cloned().unwrap_or_default(),
+        };
+
+        let blob = |tree: Option<&git2::Tree<'_>>| -> Result<String, Error> {
+            let Some(entry) = tree.and_then(|tree| tree.get_path(&path).ok()) else {
+                return Ok(String::new());
+            };
+
+            let blob = repository.find_blob(entry.id())?;
+
+            Ok(decode(blob.content()))
+        };
+
+        Ok(CommitDiff {
+            commit: Commit::new(&commit),
+            old: blob(parent.as_ref())?,
+            new: blob(Some(&tree))?,
+            files,
+            path,
+        })
+    })
+    .await
+}
+
+/// Whether a commit changes a path, compared to every one of its parents, so
+/// that merges taking the path from one side are left out.
+fn changes(commit: &git2::Commit<'_>, path: &Path) -> Result<bool, Error> {
+    let entry = |tree: git2::Tree<'_>| tree.get_path(path).ok().map(|entry| entry.id());
+
+    let current = entry(commit.tree()?);
+
+    if commit.parent_count() == 0 {
+        return Ok(current.is_some());
+    }
+
+    for parent in commit.parents() {
+        if entry(parent.tree()?) == current {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Draws the lanes of the commit graph, one row per commit.
+fn graph(commits: &mut [Commit]) {
+    // The commit expected next on every lane.
+    let mut lanes: Vec<Option<String>> = Vec::new();
+
+    for commit in commits.iter_mut() {
+        let lane = match lanes
+            .iter()
+            .position(|lane| lane.as_ref() == Some(&commit.id))
+        {
+            Some(lane) => lane,
+            None => match lanes.iter().position(Option::is_none) {
+                Some(lane) => lane,
+                None => {
+                    lanes.push(None);
+                    lanes.len() - 1
+                }
+            },
+        };
+
+        // Branches merging into this commit end here.
+        let merged: Vec<usize> = lanes
+            .iter()
+            .enumerate()
+            .filter(|(other, expected)| *other != lane && expected.as_ref() == Some(&commit.id))
+            .map(|(other, _)| other)
+            .collect();
+
+        commit.graph = lanes
+            .iter()
+            .enumerate()
+            .map(|(index, expected)| match expected {
+                _ if index == lane => '●',
+                _ if merged.contains(&index) => '┘',
+                Some(_) => '│',
+                None => ' ',
+            })
+            .collect();
+
+        for other in merged {
+            lanes[other] = None;
+        }
+
+        let mut parents = commit.parents.iter();
+
+        lanes[lane] = parents.next().cloned();
+
+        for parent in parents {
+            if lanes.iter().any(|lane| lane.as_ref() == Some(parent)) {
+                continue;
+            }
+
+            match lanes.iter().position(Option::is_none) {
+                Some(free) => lanes[free] = Some(parent.clone()),
+                None => lanes.push(Some(parent.clone())),
+            }
+        }
+
+        while lanes.last().is_some_and(Option::is_none) {
+            lanes.pop();
+        }
+    }
+}
+
+/// Makes a path relative to the working directory of a repository.
+fn relative(repository: &git2::Repository, path: &Path) -> Result<PathBuf, Error> {
+    if path.is_relative() {
+        return Ok(path.to_owned());
+    }
+
+    repository
+        .workdir()
+        .and_then(|workdir| path.strip_prefix(workdir).ok())
+        .map(Path::to_owned)
+        .ok_or_else(|| Error {
+            message: format!("{} is outside of the repository", path.display()),
+        })
+}
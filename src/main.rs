@@ -1,6 +1,18 @@
+use std::path::PathBuf;
+
 use iced::{self, *};
 
+pub mod date;
+pub mod diff;
+pub mod encoding;
+pub mod format;
+pub mod git;
+pub mod merge;
 pub mod panes;
+pub mod recovery;
+pub mod settings;
+pub mod trash;
+pub mod watcher;
 
 pub fn main() -> iced::Result {
     iced::application("Rine", Rine::update, Rine::view)
@@ -8,40 +20,141 @@ pub fn main() -> iced::Result {
         .theme(Rine::theme)
         .font(include_bytes!("../fonts/window-icons.ttf").as_slice())
         .default_font(Rine::FONT)
-        .run()
+        .run_with(Rine::new)
 }
 
 struct Rine {
+    root: PathBuf,
+    user: settings::Settings,
+    project: settings::ProjectSettings,
+    themes: settings::Themes,
     panes: panes::Panes,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 enum Message {
     Panes(panes::Message),
+    SettingsChanged,
 }
 
 impl Rine {
     const FONT: Font = Font::MONOSPACE;
 
-    fn new() -> Self {
-        Self {
-            panes: panes::Panes::new(),
+    fn new() -> (Self, Task<Message>) {
+        let root = std::env::args()
+            .nth(1)
+            .map(PathBuf::from)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default();
+        let root = std::fs::canonicalize(&root).unwrap_or(root);
+
+        let mut errors = Vec::new();
+
+        let user = settings::Settings::load().unwrap_or_else(|error| {
+            errors.push(error);
+            settings::Settings::default()
+        });
+
+        let project = settings::ProjectSettings::load(&root).unwrap_or_else(|error| {
+            errors.push(error);
+            settings::ProjectSettings::default()
+        });
+
+        let (themes, theme_errors) = settings::Themes::load();
+        errors.extend(theme_errors);
+
+        let (snippets, snippet_errors) = settings::Snippets::load();
+        errors.extend(snippet_errors);
+
+        let (mut panes, task) = panes::Panes::new(
+            root.clone(),
+            &user,
+            user.clone().merge(project.clone()),
+            &themes,
+        );
+
+        for error in errors {
+            panes.hub.error(error.to_string());
         }
+
+        panes.editor.recover(recovery::read(&root));
+        panes.editor.set_snippets(snippets);
+
+        (
+            Self {
+                root,
+                user,
+                project,
+                themes,
+                panes,
+            },
+            task.map(Message::Panes),
+        )
     }
 
-    fn update(&mut self, message: Message) {
+    fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            Message::Panes(message) => self.panes.update(message),
+            Message::Panes(message) => match self.panes.update(message) {
+                panes::Action::None => {}
+                panes::Action::Run(task) => return task.map(Message::Panes),
+                panes::Action::SettingsEdited(user) => {
+                    if let Err(error) = user.save() {
+                        self.panes.hub.error(error.to_string());
+                    }
+
+                    self.user = *user;
+                    self.apply();
+                }
+            },
+            Message::SettingsChanged => {
+                // Keep the previous settings when a file is momentarily invalid
+                // while it is being edited.
+                match settings::Settings::load() {
+                    Ok(user) => self.user = user,
+                    Err(error) => self.panes.hub.error(error.to_string()),
+                }
+
+                match settings::ProjectSettings::load(&self.root) {
+                    Ok(project) => self.project = project,
+                    Err(error) => self.panes.hub.error(error.to_string()),
+                }
+
+                let (themes, errors) = settings::Themes::load();
+
+                for error in errors {
+                    self.panes.hub.error(error.to_string());
+                }
+
+                self.themes = themes;
+
+                let (snippets, errors) = settings::Snippets::load();
+
+                for error in errors {
+                    self.panes.hub.error(error.to_string());
+                }
+
+                self.panes.editor.set_snippets(snippets);
+                self.apply();
+            }
         }
+
+        Task::none()
+    }
+
+    fn apply(&mut self) {
+        let settings = self.user.clone().merge(self.project.clone());
+
+        self.panes.apply(&self.user, settings, &self.themes);
     }
 
     fn subscription(&self) -> Subscription<Message> {
         let panes = self.panes.subscriptions().map(Message::Panes);
+        let settings = settings::watch(self.root.clone()).map(|()| Message::SettingsChanged);
 
-        Subscription::batch([panes])
+        Subscription::batch([panes, settings])
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let panes = self.panes.view().map(Message::Panes);
 
         widget::container(panes)
@@ -53,12 +166,6 @@ impl Rine {
     }
 
     fn theme(&self) -> Theme {
-        Theme::Ferra
-    }
-}
-
-impl Default for Rine {
-    fn default() -> Self {
-        Self::new()
+        self.panes.appearance.theme.clone()
     }
 }
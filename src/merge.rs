@@ -0,0 +1,297 @@
+use std::ops::Range;
+
+use crate::diff;
+
+/// A part of a file with conflict markers.
+#[derive(Debug, Clone)]
+pub enum Segment {
+    Common(String),
+    Conflict(Conflict),
+}
+
+/// The two sides of a conflicting change, and their common ancestor when
+/// known.
+#[derive(Debug, Clone, Default)]
+pub struct Conflict {
+    pub ours: String,
+    pub base: Option<String>,
+    pub theirs: String,
+    pub ours_label: String,
+    pub theirs_label: String,
+    /// The conflict, markers included, as found in the file.
+    pub raw: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    /// Ours, followed by theirs.
+    Both,
+}
+
+enum State {
+    Common,
+    Ours,
+    Base,
+    Theirs,
+}
+
+/// Whether a text contains conflict markers.
+pub fn has_conflicts(text: &str) -> bool {
+    parse(text)
+        .iter()
+        .any(|segment| matches!(segment, Segment::Conflict(_)))
+}
+
+/// Splits a text around its conflict markers, in the `merge` or `diff3`
+/// style.
+pub fn parse(text: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut common = String::new();
+    let mut conflict = Conflict::default();
+    let mut state = State::Common;
+
+    for line in text.split_inclusive('\n') {
+        match state {
+            State::Common => {
+                if let Some(label) = line.strip_prefix("<<<<<<<") {
+                    conflict.ours_label = label.trim().to_owned();
+                    state = State::Ours;
+                } else {
+                    common.push_str(line);
+                    continue;
+                }
+            }
+            State::Ours if line.starts_with("|||||||") => {
+                conflict.base = Some(String::new());
+                state = State::Base;
+            }
+            State::Ours | State::Base if line.starts_with("=======") => {
+                state = State::Theirs;
+            }
+            State::Ours => conflict.ours.push_str(line),
+            State::Base => conflict.base.get_or_insert_with(String::new).push_str(line),
+            State::Theirs => {
+                if let Some(label) = line.strip_prefix(">>>>>>>") {
+                    conflict.raw.push_str(line);
+                    conflict.theirs_label = label.trim().to_owned();
+
+                    if !common.is_empty() {
+                        segments.push(Segment::Common(std::mem::take(&mut common)));
+                    }
+
+                    segments.push(Segment::Conflict(std::mem::take(&mut conflict)));
+                    state = State::Common;
+
+                    continue;
+                }
+
+                conflict.theirs.push_str(line);
+            }
+        }
+
+        conflict.raw.push_str(line);
+    }
+
+    // An unterminated conflict is left as is.
+    common.push_str(&conflict.raw);
+
+    if !common.is_empty() {
+        segments.push(Segment::Common(common));
+    }
+
+    segments
+}
+
+/// Finds the common ancestor of every conflict lacking one, given the whole
+/// ancestor of the file.
+///
+/// The side of each conflict is located in the full version of `ours`, then
+/// mapped to the ancestor through their differences.
+pub fn fill_bases(segments: &mut [Segment], ancestor: &str) {
+    let mut ours = String::new();
+    let mut ranges = Vec::new();
+    let mut line = 0;
+
+    for segment in segments.iter() {
+        let text = match segment {
+            Segment::Common(text) => text,
+            Segment::Conflict(conflict) => &conflict.ours,
+        };
+
+        let lines = text.lines().count();
+
+        if let Segment::Conflict(_) = segment {
+            ranges.push(line..line + lines);
+        }
+
+        ours.push_str(text);
+        line += lines;
+    }
+
+    let hunks = diff::hunks(ancestor, &ours);
+
+    let conflicts = segments.iter_mut().filter_map(|segment| match segment {
+        Segment::Conflict(conflict) => Some(conflict),
+        Segment::Common(_) => None,
+    });
+
+    for (conflict, range) in conflicts.zip(ranges) {
+        if conflict.base.is_none() {
+            let range = map(&hunks, range);
+
+            conflict.base = Some(diff::lines(ancestor, range).to_owned());
+        }
+    }
+}
+
+/// Writes the text of the segments, with the given resolution of every
+/// conflict. Unresolved conflicts keep their markers.
+pub fn resolve(segments: &[Segment], resolutions: &[Option<Resolution>]) -> String {
+    let mut result = String::new();
+    let mut resolutions = resolutions.iter();
+
+    for segment in segments {
+        match segment {
+            Segment::Common(text) => result.push_str(text),
+            Segment::Conflict(conflict) => match resolutions.next().copied().flatten() {
+                Some(Resolution::Ours) => result.push_str(&conflict.ours),
+                Some(Resolution::Theirs) => result.push_str(&conflict.theirs),
+                Some(Resolution::Both) => {
+                    result.push_str(&conflict.ours);
+                    result.push_str(&conflict.theirs);
+                }
+                None => result.push_str(&conflict.raw),
+            },
+        }
+    }
+
+    result
+}
+
+/// Maps a range of lines of the new side of some hunks to the old side.
+fn map(hunks: &[diff::Hunk], range: Range<usize>) -> Range<usize> {
+    let translate = |line: usize, is_end: bool| {
+        let mut offset: isize = 0;
+
+        for hunk in hunks {
+            if line <= hunk.new.start {
+                break;
+            }
+
+            if line < hunk.new.end {
+                return if is_end { hunk.old.end } else { hunk.old.start };
+            }
+
+            offset = hunk.old.end as isize - hunk.new.end as isize;
+        }
+
+        (line as isize + offset).max(0) as usize
+    };
+
+    let start = translate(range.start, false);
+
+    start..translate(range.end, true).max(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MERGE: &str = "\
+one
+<<<<<<< HEAD
+two
+=======
+deux
+>>>>>>> branch
+three
+";
+
+    const DIFF3: &str = "\
+<<<<<<< ours
+b
+||||||| base
+a
+=======
+c
+>>>>>>> theirs
+";
+
+    fn conflicts(segments: &[Segment]) -> Vec<&Conflict> {
+        segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Conflict(conflict) => Some(conflict),
+                Segment::Common(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_merge_style() {
+        let segments = parse(MERGE);
+        let conflicts = conflicts(&segments);
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].ours, "two\n");
+        assert_eq!(conflicts[0].theirs, "deux\n");
+        assert_eq!(conflicts[0].base, None);
+        assert_eq!(conflicts[0].ours_label, "HEAD");
+        assert_eq!(conflicts[0].theirs_label, "branch");
+        assert_eq!(
+            conflicts[0].raw,
+            "<<<<<<< HEAD\ntwo\n=======\ndeux\n>>>>>>> branch\n"
+        );
+    }
+
+    #[test]
+    fn parse_diff3_style() {
+        let segments = parse(DIFF3);
+        let conflicts = conflicts(&segments);
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(conflicts[0].ours, "b\n");
+        assert_eq!(conflicts[0].base.as_deref(), Some("a\n"));
+        assert_eq!(conflicts[0].theirs, "c\n");
+    }
+
+    #[test]
+    fn unterminated_conflicts_are_text() {
+        let text = "one\n<<<<<<< HEAD\ntwo\n=======\n";
+
+        assert!(!has_conflicts(text));
+        assert!(has_conflicts(MERGE));
+        assert_eq!(resolve(&parse(text), &[]), text);
+    }
+
+    #[test]
+    fn resolve_each_conflict() {
+        let segments = parse(MERGE);
+
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Ours)]),
+            "one\ntwo\nthree\n"
+        );
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Theirs)]),
+            "one\ndeux\nthree\n"
+        );
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Both)]),
+            "one\ntwo\ndeux\nthree\n"
+        );
+        assert_eq!(resolve(&segments, &[None]), MERGE);
+    }
+
+    #[test]
+    fn fill_bases_from_the_ancestor() {
+        let mut segments = parse(MERGE);
+
+        fill_bases(&mut segments, "zero\none\n2\nthree\n");
+
+        assert_eq!(conflicts(&segments)[0].base.as_deref(), Some("2\n"));
+    }
+}
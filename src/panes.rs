@@ -1,17 +1,36 @@
+use std::path::PathBuf;
+
+use iced::Task;
 use iced::widget::pane_grid::{self, *};
+use serde::{Deserialize, Serialize};
+
+use crate::settings;
 
 mod style;
 mod titlebar;
 
+mod diff;
 mod editor;
+mod history;
 mod hub;
+mod image;
+mod preferences;
+mod preview;
 mod project;
+mod source_control;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Pane {
     Editor,
     Project,
     Hub,
+    Preferences,
+    SourceControl,
+    Diff,
+    History,
+    Preview,
+    Image,
 }
 
 impl Pane {
@@ -20,6 +39,12 @@ impl Pane {
             Pane::Editor => editor::title(),
             Pane::Project => project::title(),
             Pane::Hub => hub::title(),
+            Pane::Preferences => preferences::title(),
+            Pane::SourceControl => source_control::title(),
+            Pane::Diff => diff::title(),
+            Pane::History => history::title(),
+            Pane::Preview => preview::title(),
+            Pane::Image => image::title(),
         }
     }
 }
@@ -28,13 +53,24 @@ pub struct Panes {
     pub panes: pane_grid::State<Pane>,
     pub focus: pane_grid::Pane,
 
+    /// The effective settings, with the project settings applied.
+    pub settings: settings::Settings,
+    pub appearance: settings::Appearance,
+
     pub editor: editor::Editor,
     pub project: project::Project,
     pub hub: hub::Hub,
+    pub preferences: preferences::Preferences,
+    pub source_control: source_control::SourceControl,
+    pub diff: diff::Diff,
+    pub history: history::History,
+    pub preview: preview::Preview,
+    pub image: image::Image,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Message {
+    Open(Pane),
     Restore,
     Close(pane_grid::Pane),
     Maximize(pane_grid::Pane),
@@ -46,30 +82,103 @@ pub enum Message {
     Editor(editor::Message),
     Project(project::Message),
     Hub(hub::Message),
+    Preferences(preferences::Message),
+    SourceControl(source_control::Message),
+    Diff(diff::Message),
+    History(history::Message),
+    Preview(preview::Message),
+    Image(image::Message),
+    /// A command for the project pane, applied if it has the focus.
+    ProjectCommand(project::Command),
+}
+
+/// What the application should do after an update of the panes.
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    /// The user settings were edited and should be saved.
+    SettingsEdited(Box<settings::Settings>),
 }
 
 impl Panes {
-    pub fn new() -> Self {
-        let (mut panes, pane) = pane_grid::State::new(Pane::Project);
-        panes.split(Axis::Horizontal, pane, Pane::Hub);
-        panes.split(Axis::Vertical, pane, Pane::Editor);
-
-        let focus = pane;
-        let editor = editor::Editor::new();
-        let project = project::Project::new();
+    pub fn new(
+        root: PathBuf,
+        user: &settings::Settings,
+        settings: settings::Settings,
+        themes: &settings::Themes,
+    ) -> (Self, Task<Message>) {
+        let panes = pane_grid::State::with_configuration(configuration(&settings.layout));
+
+        let focus = *panes
+            .iter()
+            .map(|(pane, _)| pane)
+            .min()
+            .expect("layout has at least one pane");
+        let mut editor = editor::Editor::new();
+        let index_task = editor.index(root.clone(), settings.excludes.clone());
+        let (project, project_task) =
+            project::Project::new(root.clone(), settings.excludes.clone());
         let hub = hub::Hub::new();
+        let preferences = preferences::Preferences::new(user, themes.names());
+        let appearance = themes.appearance(&user.theme);
+        let (source_control, source_control_task) =
+            source_control::SourceControl::new(root.clone());
+        let diff = diff::Diff::new(root.clone());
+        let (history, history_task) = history::History::new(root);
+        let preview = preview::Preview::new();
+        let image = image::Image::new();
 
-        Self {
-            panes,
-            focus,
-            editor,
-            project,
-            hub,
-        }
+        (
+            Self {
+                panes,
+                focus,
+                settings,
+                appearance,
+                editor,
+                project,
+                hub,
+                preferences,
+                source_control,
+                diff,
+                history,
+                preview,
+                image,
+            },
+            Task::batch([
+                project_task.map(Message::Project),
+                source_control_task.map(Message::SourceControl),
+                history_task.map(Message::History),
+                index_task.map(Message::Editor),
+            ]),
+        )
+    }
+
+    /// Applies reloaded user settings and the resulting effective settings.
+    pub fn apply(
+        &mut self,
+        user: &settings::Settings,
+        settings: settings::Settings,
+        themes: &settings::Themes,
+    ) {
+        self.preferences.reset(user, themes.names());
+        self.appearance = themes.appearance(&user.theme);
+        self.project.exclude(settings.excludes.clone());
+        self.settings = settings;
     }
 
-    pub fn update(&mut self, message: Message) {
+    pub fn update(&mut self, message: Message) -> Action {
         match message {
+            Message::Open(kind) => {
+                if let Some((pane, _)) = self.panes.iter().find(|(_, pane)| **pane == kind) {
+                    self.focus = *pane;
+                } else if let Some((pane, _)) = self.panes.split(Axis::Vertical, self.focus, kind) {
+                    self.focus = pane;
+                }
+
+                if kind == Pane::Preview {
+                    return Action::Run(self.preview());
+                }
+            }
             Message::Restore => {
                 self.panes.restore();
             }
@@ -91,57 +200,256 @@ impl Panes {
             Message::Resized(pane_grid::ResizeEvent { split, ratio }) => {
                 self.panes.resize(split, ratio);
             }
-            Message::Editor(message) => self.editor.update(message),
-            Message::Project(message) => self.project.update(message),
+            Message::Editor(message) => match self.editor.update(message, &self.settings) {
+                editor::Action::None => return Action::Run(self.preview()),
+                editor::Action::Run(task) => {
+                    return Action::Run(Task::batch([task.map(Message::Editor), self.preview()]));
+                }
+                editor::Action::Preview => return self.update(Message::Open(Pane::Preview)),
+                editor::Action::Compare(path, contents) => {
+                    return self.compare(diff::Source::Buffer(path, contents));
+                }
+                editor::Action::ShowCommit(id, path) => {
+                    return self.compare(diff::Source::Commit {
+                        root: self.project.root.clone(),
+                        id,
+                        path: Some(path),
+                    });
+                }
+                editor::Action::ShowHistory(path) => {
+                    self.update(Message::Open(Pane::History));
+
+                    return Action::Run(self.history.filter(path).map(Message::History));
+                }
+                editor::Action::Error(error) => self.hub.error(error),
+            },
+            Message::Project(message) => {
+                let reload = match &message {
+                    project::Message::Changed(paths) => Task::batch([
+                        self.editor.changed(paths).map(Message::Editor),
+                        self.editor.reindex(paths).map(Message::Editor),
+                        self.image.changed(paths).map(Message::Image),
+                        self.source_control.refresh().map(Message::SourceControl),
+                        if crate::git::is_internal(paths) {
+                            self.history.refresh().map(Message::History)
+                        } else {
+                            Task::none()
+                        },
+                    ]),
+                    _ => Task::none(),
+                };
+
+                match self.project.update(message) {
+                    project::Action::None => return Action::Run(reload),
+                    project::Action::Run(task) => {
+                        return Action::Run(Task::batch([task.map(Message::Project), reload]));
+                    }
+                    project::Action::Open(path) => return self.open(path),
+                    project::Action::Renamed(from, to) => {
+                        return Action::Run(self.editor.rename(&from, &to).map(Message::Editor));
+                    }
+                    project::Action::Error(error) => self.hub.error(error),
+                }
+            }
             Message::Hub(message) => self.hub.update(message),
+            Message::SourceControl(message) => match self.source_control.update(message) {
+                source_control::Action::None => {}
+                source_control::Action::Run(task) => {
+                    return Action::Run(task.map(Message::SourceControl));
+                }
+                source_control::Action::Info(info, task) => {
+                    self.hub.info(info);
+
+                    return Action::Run(task.map(Message::SourceControl));
+                }
+                source_control::Action::Compare(path) => {
+                    return self.compare(diff::Source::Head(path));
+                }
+                source_control::Action::Error(error, task) => {
+                    self.hub.error(error);
+
+                    return Action::Run(task.map(Message::SourceControl));
+                }
+            },
+            Message::Diff(message) => match self.diff.update(message, &self.settings) {
+                diff::Action::None => {}
+                diff::Action::Run(task) => return Action::Run(task.map(Message::Diff)),
+                diff::Action::Error(error) => self.hub.error(error),
+            },
+            Message::History(message) => match self.history.update(message) {
+                history::Action::None => {}
+                history::Action::Run(task) => return Action::Run(task.map(Message::History)),
+                history::Action::ShowDiff(id) => {
+                    let filter = self.history.filter.trim();
+
+                    return self.compare(diff::Source::Commit {
+                        root: self.project.root.clone(),
+                        id,
+                        path: (!filter.is_empty()).then(|| self.project.root.join(filter)),
+                    });
+                }
+                history::Action::Error(error) => self.hub.error(error),
+            },
+            Message::Preview(message) => match self.preview.update(message) {
+                preview::Action::None => {}
+                preview::Action::Open(path) => return self.open(path),
+                preview::Action::Error(error) => self.hub.error(error),
+            },
+            Message::Image(message) => match self.image.update(message) {
+                image::Action::None => {}
+                image::Action::Run(task) => return Action::Run(task.map(Message::Image)),
+                image::Action::Error(error) => self.hub.error(error),
+            },
+            Message::ProjectCommand(command) => {
+                if self.panes.get(self.focus) == Some(&Pane::Project) {
+                    return self.update(Message::Project(project::Message::Command(command)));
+                }
+            }
+            Message::Preferences(message) => {
+                if let Some(user) = self.preferences.update(message) {
+                    return Action::SettingsEdited(Box::new(user));
+                }
+            }
             _ => {}
         }
+
+        Action::None
+    }
+
+    /// Opens a file in the image pane if it is an image, in the editor
+    /// otherwise.
+    fn open(&mut self, path: PathBuf) -> Action {
+        if image::is_image(&path) {
+            self.update(Message::Open(Pane::Image));
+
+            return Action::Run(self.image.open(path).map(Message::Image));
+        }
+
+        self.update(Message::Open(Pane::Editor));
+
+        Action::Run(self.editor.open(path, &self.settings).map(Message::Editor))
+    }
+
+    /// Shows the Markdown file focused in the editor in the preview pane,
+    /// if it is open.
+    fn preview(&mut self) -> Task<Message> {
+        if !self.panes.iter().any(|(_, pane)| *pane == Pane::Preview) {
+            return Task::none();
+        }
+
+        self.preview
+            .show(
+                self.editor.markdown(),
+                self.editor.offset(),
+                self.appearance.theme.extended_palette().is_dark,
+            )
+            .map(Message::Preview)
+    }
+
+    /// Shows a comparison in the diff pane.
+    fn compare(&mut self, source: diff::Source) -> Action {
+        self.update(Message::Open(Pane::Diff));
+
+        Action::Run(self.diff.compare(source).map(Message::Diff))
     }
 
     pub fn subscriptions(&self) -> iced::Subscription<Message> {
-        iced::keyboard::on_key_press(|_, _| None)
+        let keyboard = iced::keyboard::on_key_press(|key, modifiers| match key.as_ref() {
+            iced::keyboard::Key::Character(",") if modifiers.command() => {
+                Some(Message::Open(Pane::Preferences))
+            }
+            iced::keyboard::Key::Character("g") if modifiers.command() && modifiers.shift() => {
+                Some(Message::Open(Pane::SourceControl))
+            }
+            iced::keyboard::Key::Character("h") if modifiers.command() && modifiers.shift() => {
+                Some(Message::Open(Pane::History))
+            }
+            iced::keyboard::Key::Character("v") if modifiers.command() && modifiers.shift() => {
+                Some(Message::Open(Pane::Preview))
+            }
+            key => project_command(key, modifiers).map(Message::ProjectCommand),
+        });
+
+        let project = self.project.subscription().map(Message::Project);
+        let editor = self
+            .editor
+            .subscription(&self.settings)
+            .map(Message::Editor);
+
+        iced::Subscription::batch([keyboard, project, editor])
     }
 
-    pub fn view(&self) -> iced::Element<Message> {
+    pub fn view(&self) -> iced::Element<'_, Message> {
         let pane_grid = PaneGrid::new(&self.panes, |id, pane, is_maximized| {
             let focus = id == self.focus;
 
-            let title_bar =
-                titlebar::title_bar(id, pane.clone(), self.panes.len() == 1, is_maximized);
+            let title_bar = titlebar::title_bar(
+                id,
+                *pane,
+                self.panes.len() == 1,
+                is_maximized,
+                match pane {
+                    Pane::Editor => self.editor.mode(&self.settings),
+                    _ => None,
+                },
+                &self.appearance.panes,
+            );
 
             Content::new({
                 let content = match pane {
-                    Pane::Editor => self.editor.view().map(Message::Editor),
+                    Pane::Editor => self
+                        .editor
+                        .view(&self.settings, &self.appearance)
+                        .map(Message::Editor),
                     Pane::Project => self.project.view().map(Message::Project),
-                    Pane::Hub => self.hub.view().map(Message::Hub),
+                    Pane::Hub => self.hub.view(&self.settings).map(Message::Hub),
+                    Pane::Preferences => self.preferences.view().map(Message::Preferences),
+                    Pane::SourceControl => self
+                        .source_control
+                        .view(&self.settings)
+                        .map(Message::SourceControl),
+                    Pane::Diff => self
+                        .diff
+                        .view(&self.settings, &self.appearance)
+                        .map(Message::Diff),
+                    Pane::History => self.history.view(&self.settings).map(Message::History),
+                    Pane::Preview => self
+                        .preview
+                        .view(&self.settings, &self.appearance)
+                        .map(Message::Preview),
+                    Pane::Image => self.image.view(&self.appearance).map(Message::Image),
                 };
 
                 iced::widget::mouse_area(
                     iced::widget::container(
-                        iced::widget::container(iced::widget::container(content).padding(5))
-                            .style(style::pane_content)
-                            .width(iced::Length::Fill)
-                            .height(iced::Length::Fill),
+                        iced::widget::container(
+                            iced::widget::container(content).padding(self.settings.padding),
+                        )
+                        .style(|theme| style::pane_content(theme, &self.appearance.panes))
+                        .width(iced::Length::Fill)
+                        .height(iced::Length::Fill),
                     )
                     .padding(iced::Padding {
                         top: -1.0,
-                        right: 5.0,
-                        bottom: 5.0,
-                        left: 5.0,
+                        right: self.settings.padding,
+                        bottom: self.settings.padding,
+                        left: self.settings.padding,
                     }),
                 )
                 .on_enter(Message::MouseEnter(id))
             })
             .title_bar(title_bar)
-            .style(if focus {
-                style::full_pane_focus
-            } else {
-                style::full_pane
+            .style(move |theme| {
+                if focus {
+                    style::full_pane_focus(theme, &self.appearance.panes)
+                } else {
+                    style::full_pane(theme, &self.appearance.panes)
+                }
             })
         })
         .width(iced::Length::Fill)
         .height(iced::Length::Fill)
-        .spacing(5)
+        .spacing(self.settings.spacing)
         .on_click(Message::Clicked)
         .on_drag(Message::Dragged)
         .on_resize(10, Message::Resized);
@@ -149,7 +457,48 @@ impl Panes {
         iced::widget::container(pane_grid)
             .width(iced::Length::Fill)
             .height(iced::Length::Fill)
-            .padding(5)
+            .padding(self.settings.spacing)
             .into()
     }
 }
+
+/// The key bindings of the project pane.
+fn project_command(
+    key: iced::keyboard::Key<&str>,
+    modifiers: iced::keyboard::Modifiers,
+) -> Option<project::Command> {
+    use iced::keyboard::{Key, key::Named};
+
+    let command = match key {
+        Key::Named(Named::F2) => project::Command::Rename,
+        Key::Named(Named::Delete) => project::Command::Delete,
+        Key::Named(Named::Backspace) if modifiers.command() => project::Command::Delete,
+        Key::Character("n") if modifiers.command() && modifiers.shift() => {
+            project::Command::NewFolder
+        }
+        Key::Character("n") if modifiers.command() => project::Command::NewFile,
+        Key::Character("c") if modifiers.command() => project::Command::Copy,
+        Key::Character("x") if modifiers.command() => project::Command::Cut,
+        Key::Character("v") if modifiers.command() => project::Command::Paste,
+        Key::Character("d") if modifiers.command() => project::Command::Duplicate,
+        Key::Character("z") if modifiers.command() => project::Command::Undo,
+        _ => return None,
+    };
+
+    Some(command)
+}
+
+fn configuration(layout: &settings::Layout) -> Configuration<Pane> {
+    match layout {
+        settings::Layout::Pane(pane) => Configuration::Pane(*pane),
+        settings::Layout::Split { split, ratio, a, b } => Configuration::Split {
+            axis: match split {
+                settings::Axis::Horizontal => Axis::Horizontal,
+                settings::Axis::Vertical => Axis::Vertical,
+            },
+            ratio: ratio.clamp(0.0, 1.0),
+            a: Box::new(configuration(a)),
+            b: Box::new(configuration(b)),
+        },
+    }
+}
@@ -0,0 +1,700 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use iced::widget::{
+    button, column, container, horizontal_space, pick_list, rich_text, row, scrollable, span, text,
+    text_input,
+};
+use iced::{Color, Element, Task};
+
+use crate::settings::{Appearance, Settings};
+use crate::{diff, encoding, git};
+
+use super::style;
+
+/// The unchanged lines shown around every hunk.
+const CONTEXT: usize = 3;
+
+pub struct Diff {
+    pub root: PathBuf,
+    pub source: Option<Source>,
+    pub comparison: Option<Comparison>,
+    pub mode: Mode,
+    /// The unchanged regions shown in full, by index.
+    pub expanded: BTreeSet<usize>,
+    /// The hunk navigated to.
+    pub current: usize,
+    /// The files to compare, as typed.
+    pub left: String,
+    pub right: String,
+    scrollables: [scrollable::Id; 2],
+    offset: f32,
+}
+
+/// What is compared.
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// A file in the working tree against its version at `HEAD`.
+    Head(PathBuf),
+    /// Two arbitrary files.
+    Files(PathBuf, PathBuf),
+    /// An unsaved buffer against its file on disk.
+    Buffer(PathBuf, String),
+    /// The changes of a commit to one of its files, or to its first one.
+    Commit {
+        root: PathBuf,
+        id: String,
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub old_title: String,
+    pub new_title: String,
+    pub old: String,
+    pub new: String,
+    pub hunks: Vec<diff::Hunk>,
+    /// The files changed by a commit, and the one compared.
+    pub files: Vec<String>,
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    SideBySide,
+    Inline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Loaded(Result<Comparison, Error>),
+    Reload,
+    ModeToggled,
+    Expand(usize),
+    Previous,
+    Next,
+    Scrolled(Side, scrollable::Viewport),
+    LeftEdited(String),
+    RightEdited(String),
+    CompareFiles,
+    FileSelected(String),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    Io(PathBuf, io::ErrorKind),
+    Git(git::Error),
+}
+
+/// A line of the diff, as shown.
+struct Line<'a> {
+    kind: Kind,
+    old: Option<Cell<'a>>,
+    new: Option<Cell<'a>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Equal,
+    Changed,
+    Collapsed { region: usize, lines: usize },
+}
+
+struct Cell<'a> {
+    number: usize,
+    text: &'a str,
+    /// The changed byte ranges within the line.
+    changes: Vec<Range<usize>>,
+}
+
+impl Diff {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            source: None,
+            comparison: None,
+            mode: Mode::SideBySide,
+            expanded: BTreeSet::new(),
+            current: 0,
+            left: String::new(),
+            right: String::new(),
+            scrollables: [scrollable::Id::unique(), scrollable::Id::unique()],
+            offset: 0.0,
+        }
+    }
+
+    /// Starts comparing something else.
+    pub fn compare(&mut self, source: Source) -> Task<Message> {
+        if let Source::Files(left, right) = &source {
+            self.left = relative(&self.root, left);
+            self.right = relative(&self.root, right);
+        }
+
+        self.source = Some(source.clone());
+        self.expanded.clear();
+        self.current = 0;
+
+        Task::perform(load(source), Message::Loaded)
+    }
+
+    pub fn update(&mut self, message: Message, settings: &Settings) -> Action {
+        match message {
+            Message::Loaded(Ok(comparison)) => {
+                self.comparison = Some(comparison);
+                self.current = 0;
+
+                Action::Run(self.scroll_to_hunk(settings))
+            }
+            Message::Loaded(Err(error)) => Action::Error(error.to_string()),
+            Message::Reload => match self.source.clone() {
+                Some(source) => Action::Run(Task::perform(load(source), Message::Loaded)),
+                None => Action::None,
+            },
+            Message::ModeToggled => {
+                self.mode = match self.mode {
+                    Mode::SideBySide => Mode::Inline,
+                    Mode::Inline => Mode::SideBySide,
+                };
+
+                Action::Run(self.scroll_to_hunk(settings))
+            }
+            Message::Expand(region) => {
+                self.expanded.insert(region);
+
+                Action::None
+            }
+            Message::Previous => {
+                self.current = self.current.saturating_sub(1);
+
+                Action::Run(self.scroll_to_hunk(settings))
+            }
+            Message::Next => {
+                let hunks = self
+                    .comparison
+                    .as_ref()
+                    .map_or(0, |comparison| comparison.hunks.len());
+
+                self.current = (self.current + 1).min(hunks.saturating_sub(1));
+
+                Action::Run(self.scroll_to_hunk(settings))
+            }
+            Message::Scrolled(side, viewport) => {
+                let offset = viewport.absolute_offset().y;
+
+                if (offset - self.offset).abs() < 0.5 || self.mode == Mode::Inline {
+                    return Action::None;
+                }
+
+                self.offset = offset;
+
+                let other = match side {
+                    Side::Left => &self.scrollables[1],
+                    Side::Right => &self.scrollables[0],
+                };
+
+                Action::Run(scrollable::scroll_to(
+                    other.clone(),
+                    scrollable::AbsoluteOffset { x: 0.0, y: offset },
+                ))
+            }
+            Message::LeftEdited(left) => {
+                self.left = left;
+
+                Action::None
+            }
+            Message::RightEdited(right) => {
+                self.right = right;
+
+                Action::None
+            }
+            Message::CompareFiles => {
+                if self.left.trim().is_empty() || self.right.trim().is_empty() {
+                    return Action::None;
+                }
+
+                let left = self.root.join(self.left.trim());
+                let right = self.root.join(self.right.trim());
+
+                Action::Run(self.compare(Source::Files(left, right)))
+            }
+            Message::FileSelected(file) => match self.source.clone() {
+                Some(Source::Commit { root, id, .. }) => {
+                    Action::Run(self.compare(Source::Commit {
+                        root,
+                        id,
+                        path: Some(PathBuf::from(file)),
+                    }))
+                }
+                _ => Action::None,
+            },
+        }
+    }
+
+    pub fn view<'a>(
+        &'a self,
+        settings: &'a Settings,
+        appearance: &'a Appearance,
+    ) -> Element<'a, Message> {
+        let inputs = row![
+            text_input("Left file", &self.left)
+                .size(12)
+                .on_input(Message::LeftEdited)
+                .on_submit(Message::CompareFiles),
+            text_input("Right file", &self.right)
+                .size(12)
+                .on_input(Message::RightEdited)
+                .on_submit(Message::CompareFiles),
+            button(text("Compare").size(12)).on_press(Message::CompareFiles),
+        ]
+        .spacing(5);
+
+        let Some(comparison) = &self.comparison else {
+            return column![inputs, text("Nothing to compare yet.").size(12)]
+                .spacing(5)
+                .into();
+        };
+
+        let navigation = row![
+            text(format!(
+                "{} ↔ {}",
+                comparison.old_title, comparison.new_title
+            ))
+            .size(12),
+            horizontal_space(),
+            text(if comparison.hunks.is_empty() {
+                String::from("No changes")
+            } else {
+                format!("Hunk {} of {}", self.current + 1, comparison.hunks.len())
+            })
+            .size(12),
+            button(text("↑").size(12))
+                .padding([1, 6])
+                .on_press(Message::Previous),
+            button(text("↓").size(12))
+                .padding([1, 6])
+                .on_press(Message::Next),
+            button(
+                text(match self.mode {
+                    Mode::SideBySide => "Inline",
+                    Mode::Inline => "Side by side",
+                })
+                .size(12),
+            )
+            .padding([1, 6])
+            .on_press(Message::ModeToggled),
+            button(text("Reload").size(12))
+                .padding([1, 6])
+                .on_press(Message::Reload),
+        ]
+        .spacing(5);
+
+        let files = (!comparison.files.is_empty()).then(|| {
+            pick_list(
+                comparison.files.as_slice(),
+                comparison.file.as_ref(),
+                Message::FileSelected,
+            )
+            .text_size(12)
+        });
+
+        let (lines, _) = self.lines(comparison);
+        let line_height = settings.line_height();
+        let palette = appearance.theme.extended_palette();
+
+        let cell = |cell: &Option<Cell<'a>>, kind: Kind, sign: &'a str, tint: Color| {
+            let mut spans = Vec::new();
+            let number;
+
+            match cell {
+                Some(cell) => {
+                    number = (cell.number + 1).to_string();
+
+                    let mut start = 0;
+
+                    for change in &cell.changes {
+                        spans.push(span(&cell.text[start..change.start]));
+                        spans.push(
+                            span(&cell.text[change.clone()]).background(Color { a: 0.45, ..tint }),
+                        );
+
+                        start = change.end;
+                    }
+
+                    spans.push(span(&cell.text[start..]));
+                }
+                None => number = String::new(),
+            }
+
+            let background = match (kind, cell) {
+                (Kind::Changed, Some(_)) => Some(Color { a: 0.15, ..tint }),
+                (Kind::Changed, None) => Some(Color {
+                    a: 0.3,
+                    ..palette.background.strong.color
+                }),
+                _ => None,
+            };
+
+            container(
+                row![
+                    text(number)
+                        .font(settings.font())
+                        .size(settings.text_size)
+                        .line_height(iced::Pixels(line_height))
+                        .width(48)
+                        .align_x(iced::alignment::Horizontal::Right)
+                        .style(|theme: &iced::Theme| text::Style {
+                            color: Some(theme.extended_palette().background.strong.color),
+                        }),
+                    text(if cell.is_some() && kind == Kind::Changed {
+                        sign
+                    } else {
+                        " "
+                    })
+                    .font(settings.font())
+                    .size(settings.text_size)
+                    .line_height(iced::Pixels(line_height)),
+                    rich_text(spans)
+                        .font(settings.font())
+                        .size(settings.text_size)
+                        .line_height(iced::Pixels(line_height))
+                        .wrapping(text::Wrapping::None),
+                ]
+                .spacing(6),
+            )
+            .width(iced::Length::Fill)
+            .height(line_height)
+            .clip(true)
+            .style(move |_| container::Style {
+                background: background.map(Into::into),
+                ..Default::default()
+            })
+            .into()
+        };
+
+        let collapsed = |region: usize, lines: usize| {
+            button(
+                text(format!("⋯ {lines} unchanged lines"))
+                    .size(12)
+                    .line_height(iced::Pixels(line_height)),
+            )
+            .width(iced::Length::Fill)
+            .height(line_height)
+            .padding([0, 54])
+            .style(|theme, status| style::entry(theme, status, false))
+            .on_press(Message::Expand(region))
+            .into()
+        };
+
+        let removed = palette.danger.base.color;
+        let added = palette.success.base.color;
+
+        let content: Element<'a, Message> = match self.mode {
+            Mode::SideBySide => {
+                let side = |side: Side| {
+                    column(lines.iter().map(|line| match line.kind {
+                        Kind::Collapsed { region, lines } => collapsed(region, lines),
+                        kind => match side {
+                            Side::Left => cell(&line.old, kind, "-", removed),
+                            Side::Right => cell(&line.new, kind, "+", added),
+                        },
+                    }))
+                };
+
+                let left = scrollable(side(Side::Left))
+                    .id(self.scrollables[0].clone())
+                    .on_scroll(|viewport| Message::Scrolled(Side::Left, viewport))
+                    .height(iced::Length::Fill);
+
+                let right = scrollable(side(Side::Right))
+                    .id(self.scrollables[1].clone())
+                    .on_scroll(|viewport| Message::Scrolled(Side::Right, viewport))
+                    .height(iced::Length::Fill);
+
+                row![left, right].spacing(5).into()
+            }
+            Mode::Inline => scrollable(column(lines.iter().map(|line| match line.kind {
+                Kind::Collapsed { region, lines } => collapsed(region, lines),
+                Kind::Changed if line.old.is_some() => cell(&line.old, line.kind, "-", removed),
+                Kind::Changed => cell(&line.new, line.kind, "+", added),
+                Kind::Equal => cell(&line.new, line.kind, " ", added),
+            })))
+            .id(self.scrollables[0].clone())
+            .height(iced::Length::Fill)
+            .into(),
+        };
+
+        column![inputs, navigation]
+            .push_maybe(files)
+            .push(content)
+            .spacing(5)
+            .into()
+    }
+
+    /// Lays out the lines of a comparison, along with the index of the first
+    /// line of every hunk.
+    fn lines<'a>(&self, comparison: &'a Comparison) -> (Vec<Line<'a>>, Vec<usize>) {
+        let old: Vec<&str> = comparison.old.lines().collect();
+        let new: Vec<&str> = comparison.new.lines().collect();
+
+        let end = diff::Hunk {
+            old: old.len()..old.len(),
+            new: new.len()..new.len(),
+        };
+
+        let mut lines = Vec::new();
+        let mut starts = Vec::new();
+        let (mut o, mut n) = (0, 0);
+
+        let cell = |lines: &[&'a str], number: usize| Cell {
+            number,
+            text: lines[number],
+            changes: Vec::new(),
+        };
+
+        for (region, hunk) in comparison.hunks.iter().chain([&end]).enumerate() {
+            let unchanged = hunk.old.start.saturating_sub(o);
+            let is_first = region == 0;
+            let is_last = region == comparison.hunks.len();
+
+            let head = if is_first { 0 } else { CONTEXT };
+            let tail = if is_last { 0 } else { CONTEXT };
+
+            let shown: Vec<usize> =
+                if self.expanded.contains(&region) || unchanged <= head + tail + 1 {
+                    (0..unchanged).collect()
+                } else {
+                    (0..head).chain(unchanged - tail..unchanged).collect()
+                };
+
+            for (index, i) in shown.iter().enumerate() {
+                if index == head && shown.len() < unchanged {
+                    lines.push(Line {
+                        kind: Kind::Collapsed {
+                            region,
+                            lines: unchanged - shown.len(),
+                        },
+                        old: None,
+                        new: None,
+                    });
+                }
+
+                lines.push(Line {
+                    kind: Kind::Equal,
+                    old: Some(cell(&old, o + i)),
+                    new: Some(cell(&new, n + i)),
+                });
+            }
+
+            if shown.len() == head && shown.len() < unchanged {
+                lines.push(Line {
+                    kind: Kind::Collapsed {
+                        region,
+                        lines: unchanged - shown.len(),
+                    },
+                    old: None,
+                    new: None,
+                });
+            }
+
+            if is_last {
+                break;
+            }
+
+            starts.push(lines.len());
+
+            let changed = |i: usize| {
+                let old = (i < hunk.old.len()).then(|| cell(&old, hunk.old.start + i));
+                let new = (i < hunk.new.len()).then(|| cell(&new, hunk.new.start + i));
+
+                match (old, new) {
+                    (Some(mut old), Some(mut new)) => {
+                        (old.changes, new.changes) = diff::inline(old.text, new.text);
+
+                        (Some(old), Some(new))
+                    }
+                    pair => pair,
+                }
+            };
+
+            let length = hunk.old.len().max(hunk.new.len());
+
+            match self.mode {
+                Mode::SideBySide => {
+                    for i in 0..length {
+                        let (old, new) = changed(i);
+
+                        lines.push(Line {
+                            kind: Kind::Changed,
+                            old,
+                            new,
+                        });
+                    }
+                }
+                Mode::Inline => {
+                    let pairs: Vec<_> = (0..length).map(changed).collect();
+                    let (old, new): (Vec<_>, Vec<_>) = pairs.into_iter().unzip();
+
+                    lines.extend(old.into_iter().flatten().map(|old| Line {
+                        kind: Kind::Changed,
+                        old: Some(old),
+                        new: None,
+                    }));
+
+                    lines.extend(new.into_iter().flatten().map(|new| Line {
+                        kind: Kind::Changed,
+                        old: None,
+                        new: Some(new),
+                    }));
+                }
+            }
+
+            o = hunk.old.end;
+            n = hunk.new.end;
+        }
+
+        (lines, starts)
+    }
+
+    /// Scrolls to the current hunk, leaving some context above it.
+    fn scroll_to_hunk(&mut self, settings: &Settings) -> Task<Message> {
+        let Some(comparison) = &self.comparison else {
+            return Task::none();
+        };
+
+        let (_, starts) = self.lines(comparison);
+
+        let Some(start) = starts.get(self.current) else {
+            return Task::none();
+        };
+
+        let offset = (start.saturating_sub(CONTEXT) as f32 * settings.line_height()).max(0.0);
+
+        self.offset = offset;
+
+        Task::batch(self.scrollables.iter().map(|id| {
+            scrollable::scroll_to(id.clone(), scrollable::AbsoluteOffset { x: 0.0, y: offset })
+        }))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, kind) => write!(f, "{}: {kind}", path.display()),
+            Error::Git(error) => error.fmt(f),
+        }
+    }
+}
+
+pub fn title() -> &'static str {
+    "Diff"
+}
+
+async fn load(source: Source) -> Result<Comparison, Error> {
+    let (mut files, mut file) = (Vec::new(), None);
+
+    let (old_title, old, new_title, new) = match source {
+        Source::Head(path) => {
+            let head = git::head(path.clone()).await.map_err(Error::Git)?;
+            let new = read(&path).await?.unwrap_or_default();
+            let name = name(&path);
+
+            (
+                format!("{name} (HEAD)"),
+                head.unwrap_or_default(),
+                name,
+                new,
+            )
+        }
+        Source::Files(left, right) => (
+            name(&left),
+            read(&left)
+                .await?
+                .ok_or(Error::Io(left.clone(), io::ErrorKind::NotFound))?,
+            name(&right),
+            read(&right)
+                .await?
+                .ok_or(Error::Io(right.clone(), io::ErrorKind::NotFound))?,
+        ),
+        Source::Buffer(path, contents) => {
+            let name = name(&path);
+
+            (
+                format!("{name} (on disk)"),
+                read(&path).await?.unwrap_or_default(),
+                format!("{name} (unsaved)"),
+                contents,
+            )
+        }
+        Source::Commit { root, id, path } => {
+            let diff = git::commit_diff(root, id, path).await.map_err(Error::Git)?;
+
+            let name = diff.path.display().to_string();
+            let short_id = diff.commit.short_id();
+
+            files = diff
+                .files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect();
+            file = Some(name.clone());
+
+            (
+                format!("{name} ({short_id}^)"),
+                diff.old,
+                format!("{name} ({short_id}: {})", diff.commit.summary()),
+                diff.new,
+            )
+        }
+    };
+
+    let hunks = diff::hunks(&old, &new);
+
+    Ok(Comparison {
+        old_title,
+        new_title,
+        old,
+        new,
+        hunks,
+        files,
+        file,
+    })
+}
+
+/// Reads a file, decoded like in the editor, or nothing if it does not exist.
+async fn read(path: &Path) -> Result<Option<String>, Error> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => Ok(Some(encoding::decode(&bytes).0)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(Error::Io(path.to_owned(), error.kind())),
+    }
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
@@ -1,28 +1,2694 @@
-use iced::widget::text_editor::{self};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi;
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use iced::widget::text_editor::{self, Motion};
+use iced::widget::{
+    self, button, column, container, horizontal_space, pick_list, row, scrollable, stack, text,
+    text_input,
+};
+use iced::{Subscription, Task, keyboard};
+use ropey::Rope;
+
+use crate::settings::{self, Appearance, Autosave, Settings};
+use crate::{diff, encoding, format, git, recovery};
+
+use super::style;
+
+mod blame;
+mod brackets;
+mod completion;
+mod cursors;
+mod decorations;
+mod document;
+mod fold;
+mod gutter;
+mod hex;
+mod highlighter;
+mod merge;
+mod minimap;
+mod numbers;
+mod search;
+mod snippet;
+mod typing;
+mod undo;
+mod vim;
+
+/// How often the changes of dirty buffers are written to the recovery
+/// journal.
+const JOURNAL_INTERVAL: Duration = Duration::from_secs(5);
 
 pub struct Editor {
+    pub buffers: Vec<Buffer>,
+    pub active: usize,
+    /// The hunk of the active buffer whose actions are shown.
+    pub hunk: Option<usize>,
+    scrollable: scrollable::Id,
+    viewport: Option<scrollable::Viewport>,
+    modifiers: keyboard::Modifiers,
+    /// Where a rectangular selection started, while dragging.
+    column: Option<cursors::Position>,
+    vim: vim::Vim,
+    /// The line under the pointer in the minimap.
+    minimap: usize,
+    /// Whether the minimap is being dragged.
+    dragging: bool,
+    /// The folded lines of the files closed in this session.
+    folded: BTreeMap<PathBuf, Vec<fold::Span>>,
+    /// When the last edit was typed.
+    edited: Option<Instant>,
+    /// A digest of the contents of the files written to the recovery
+    /// journal.
+    journaled: BTreeMap<PathBuf, u64>,
+    /// The unsaved changes left by a previous session, to be restored or
+    /// discarded.
+    recovered: Vec<recovery::Entry>,
+    snippets: settings::Snippets,
+    /// The completions offered for the word being typed, if any.
+    completion: Option<completion::Completion>,
+    /// The words of the project files, and where they are read from.
+    words: Arc<completion::Words>,
+    project: Option<(PathBuf, Vec<String>)>,
+    indexing: bool,
+    /// Whether the project changed while it was being indexed.
+    outdated: bool,
+    /// Whether the snippets of the active buffer are listed to be inserted.
+    picking: bool,
+    /// The unsaved buffer asked to be closed, until it is saved or
+    /// discarded.
+    confirming: Option<PathBuf>,
+    /// The buffers to close once saved.
+    closing: BTreeSet<PathBuf>,
+    /// The buffer asked to be saved while some bytes of its file could not
+    /// be decoded, until saving over them is confirmed.
+    overwriting: Option<PathBuf>,
+    /// The find bar, when open.
+    search: Option<search::Search>,
+}
+
+pub struct Buffer {
+    pub path: PathBuf,
     pub content: text_editor::Content,
+    pub is_dirty: bool,
+    /// The contents of the file at `HEAD`, if it is tracked.
+    pub head: Option<String>,
+    /// The changes of the buffer since `HEAD`.
+    pub hunks: Vec<diff::Hunk>,
+    /// Whether the buffer contains conflict markers.
+    pub conflicted: bool,
+    /// The conflicts being resolved, if any.
+    pub merge: Option<merge::Merge>,
+    /// The last commit of every line, when shown.
+    pub blame: Option<Vec<git::Annotation>>,
+    /// The file, when too large to be edited, shown instead of the content.
+    pub document: Option<document::Document>,
+    /// The file, when binary, shown in hexadecimal instead of the content.
+    pub hex: Option<hex::Hex>,
+    /// The cursors besides the one of the content.
+    pub cursors: Vec<cursors::Cursor>,
+    /// How the file is stored, kept when it is saved.
+    pub format: encoding::Format,
+    /// The snippet being filled in, if any.
+    pub snippet: Option<snippet::Session>,
+    undo: undo::Undo,
+    /// The lines taken out of the content while folded.
+    folds: Vec<fold::Fold>,
+    /// The ranges of lines that can be folded, in lines of the full text.
+    foldable: Vec<Range<usize>>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Message {}
+/// The contents of a file, as loaded.
+#[derive(Debug, Clone)]
+pub enum Contents {
+    Text(Arc<String>, encoding::Format),
+    /// A file over the size of large files, to be shown read-only.
+    Large(Rope),
+    /// A file that is not text, to be shown in hexadecimal.
+    Binary(Arc<Vec<u8>>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Opened(Result<(PathBuf, Contents), Error>),
+    HeadLoaded(PathBuf, Result<Option<String>, git::Error>),
+    Select(usize),
+    /// Closes a buffer, asking first if it is unsaved.
+    Close(usize),
+    /// Saves the unsaved buffer asked to be closed, closing it once saved.
+    SaveAndClose,
+    /// Closes the unsaved buffer asked to be closed, discarding its changes.
+    Discard,
+    CancelClose,
+    Edit(text_editor::Action),
+    /// Saves the active buffer, asking first if its file was not decoded
+    /// exactly.
+    Save,
+    /// Saves the buffer asked to be saved, writing the replacements of the
+    /// bytes that could not be decoded.
+    SaveAnyway,
+    CancelSave,
+    /// A file was saved with the given text.
+    Saved(Result<(PathBuf, String), Error>),
+    Scrolled(scrollable::Viewport),
+    ToggleHunk(usize),
+    RevertHunk(usize),
+    StageHunk(usize),
+    Staged(Result<(), git::Error>),
+    Compare,
+    Resolve,
+    AncestorLoaded(PathBuf, Result<Option<String>, git::Error>),
+    Merge(merge::Message),
+    Resolved(Result<PathBuf, Error>, bool),
+    ToggleBlame,
+    BlameLoaded(PathBuf, Result<Vec<git::Annotation>, git::Error>),
+    ShowCommit(String),
+    ShowHistory,
+    Document(PathBuf, document::Message),
+    Hex(PathBuf, hex::Message),
+    ModifiersChanged(keyboard::Modifiers),
+    AddNextOccurrence,
+    ClearCursors,
+    Undo,
+    Redo,
+    Vim(vim::Key),
+    /// Folds or unfolds the lines after a line of the full text.
+    ToggleFold(usize),
+    Fold,
+    Unfold,
+    FoldAll,
+    UnfoldAll,
+    /// The clipboard was read, to be put after or before the cursor.
+    Pasted(Option<String>, bool, usize),
+    MinimapMoved(usize),
+    MinimapPressed,
+    MinimapReleased,
+    /// Moves the cursor to a line of the content.
+    JumpTo(usize),
+    Indent,
+    Outdent,
+    Format,
+    Preview,
+    /// A file was piped through its formatter, from the given text, and
+    /// should be saved afterwards in the given format if asked to.
+    Formatted(
+        PathBuf,
+        String,
+        Result<String, format::Error>,
+        Option<encoding::Format>,
+    ),
+    FormatFailed(format::Error),
+    WindowUnfocused,
+    Autosave,
+    /// Writes the changes of the dirty buffers to the recovery journal.
+    Journal,
+    Journaled(Result<(), recovery::Error>),
+    Recover(usize),
+    DiscardRecovered(usize),
+    EncodingSelected(encoding::Encoding),
+    LineEndingSelected(encoding::LineEnding),
+    /// Offers completions for the word before the cursor, however short.
+    Complete,
+    CompletionMoved(isize),
+    CompletionAccepted(usize),
+    CompletionDismissed,
+    Indexed(Arc<completion::Words>),
+    ToggleSnippets,
+    /// Inserts a snippet at the cursor, by its index.
+    InsertSnippet(usize),
+    /// Replaces the current stop of the snippet being filled in with one of
+    /// its choices.
+    Choose(String),
+    /// Opens the find bar, searching for the selected text if any.
+    Find,
+    QueryEdited(String),
+    /// Selects the next match of the search, or the previous one.
+    FindNext(bool),
+    CloseFind,
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    /// An unsaved buffer should be compared to its file.
+    Compare(PathBuf, String),
+    /// The changes of a commit to a file should be shown.
+    ShowCommit(String, PathBuf),
+    /// The commits changing a file should be listed.
+    ShowHistory(PathBuf),
+    /// The Markdown file being edited should be previewed.
+    Preview,
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    Io(PathBuf, io::ErrorKind),
+    Encoding(PathBuf, encoding::Error),
+}
 
 impl Editor {
     pub fn new() -> Self {
         Self {
-            content: text_editor::Content::new(),
+            buffers: Vec::new(),
+            active: 0,
+            hunk: None,
+            scrollable: scrollable::Id::unique(),
+            viewport: None,
+            modifiers: keyboard::Modifiers::default(),
+            column: None,
+            vim: vim::Vim::default(),
+            minimap: 0,
+            dragging: false,
+            folded: BTreeMap::new(),
+            edited: None,
+            journaled: BTreeMap::new(),
+            recovered: Vec::new(),
+            snippets: settings::Snippets::default(),
+            completion: None,
+            words: Arc::default(),
+            project: None,
+            indexing: false,
+            outdated: false,
+            picking: false,
+            confirming: None,
+            overwriting: None,
+            closing: BTreeSet::new(),
+            search: None,
+        }
+    }
+
+    /// Opens a file, or selects it if it is already open.
+    pub fn open(&mut self, path: PathBuf, settings: &Settings) -> Task<Message> {
+        if let Some(index) = self.buffers.iter().position(|buffer| buffer.path == path) {
+            self.select(index);
+
+            return Task::none();
+        }
+
+        Task::perform(
+            load_file(path, settings.large_file_size * 1024 * 1024),
+            Message::Opened,
+        )
+    }
+
+    /// Reloads the `HEAD` contents of the open files after a repository
+    /// change.
+    pub fn changed(&mut self, paths: &[PathBuf]) -> Task<Message> {
+        if !git::is_internal(paths) {
+            return Task::none();
+        }
+
+        Task::batch(
+            self.buffers
+                .iter()
+                .filter(|buffer| buffer.is_editable())
+                .map(|buffer| load_head(buffer.path.clone())),
+        )
+    }
+
+    /// Follows a file or directory renamed or moved with the open files in
+    /// it, reloading their `HEAD` contents.
+    pub fn rename(&mut self, from: &std::path::Path, to: &std::path::Path) -> Task<Message> {
+        let mut tasks = Vec::new();
+
+        for buffer in &mut self.buffers {
+            if let Some(path) = super::project::renamed(&buffer.path, from, to) {
+                buffer.path = path;
+
+                if buffer.is_editable() {
+                    tasks.push(load_head(buffer.path.clone()));
+                }
+            }
         }
+
+        if let Some(path) = &mut self.confirming
+            && let Some(renamed) = super::project::renamed(path, from, to)
+        {
+            *path = renamed;
+        }
+
+        if let Some(path) = &mut self.overwriting
+            && let Some(renamed) = super::project::renamed(path, from, to)
+        {
+            *path = renamed;
+        }
+
+        self.closing = std::mem::take(&mut self.closing)
+            .into_iter()
+            .map(|path| super::project::renamed(&path, from, to).unwrap_or(path))
+            .collect();
+
+        self.folded = std::mem::take(&mut self.folded)
+            .into_iter()
+            .map(|(path, spans)| {
+                (
+                    super::project::renamed(&path, from, to).unwrap_or(path),
+                    spans,
+                )
+            })
+            .collect();
+
+        Task::batch(tasks)
     }
 
-    pub fn update(&mut self, message: Message) {
-        match message {}
+    pub fn update(&mut self, message: Message, settings: &Settings) -> Action {
+        if matches!(
+            message,
+            Message::Edit(text_editor::Action::Edit(_))
+                | Message::Vim(_)
+                | Message::Pasted(..)
+                | Message::Indent
+                | Message::Outdent
+                | Message::Undo
+                | Message::Redo
+        ) {
+            self.edited = Some(Instant::now());
+        }
+
+        if matches!(
+            message,
+            Message::Vim(_)
+                | Message::Pasted(..)
+                | Message::Indent
+                | Message::Outdent
+                | Message::Undo
+                | Message::Redo
+                | Message::ClearCursors
+        ) {
+            self.completion = None;
+        }
+
+        match message {
+            Message::Opened(Ok((path, Contents::Large(rope)))) => {
+                self.buffers.push(Buffer {
+                    path,
+                    content: text_editor::Content::new(),
+                    is_dirty: false,
+                    head: None,
+                    hunks: Vec::new(),
+                    conflicted: false,
+                    merge: None,
+                    blame: None,
+                    document: Some(document::Document::new(rope)),
+                    hex: None,
+                    cursors: Vec::new(),
+                    format: encoding::Format::default(),
+                    snippet: None,
+                    undo: undo::Undo::default(),
+                    folds: Vec::new(),
+                    foldable: Vec::new(),
+                });
+
+                self.select(self.buffers.len() - 1);
+
+                Action::None
+            }
+            Message::Opened(Ok((path, Contents::Binary(bytes)))) => {
+                self.buffers.push(Buffer {
+                    path,
+                    content: text_editor::Content::new(),
+                    is_dirty: false,
+                    head: None,
+                    hunks: Vec::new(),
+                    conflicted: false,
+                    merge: None,
+                    blame: None,
+                    document: None,
+                    hex: Some(hex::Hex::new(bytes)),
+                    cursors: Vec::new(),
+                    format: encoding::Format::default(),
+                    snippet: None,
+                    undo: undo::Undo::default(),
+                    folds: Vec::new(),
+                    foldable: Vec::new(),
+                });
+
+                self.select(self.buffers.len() - 1);
+
+                Action::None
+            }
+            Message::Opened(Ok((path, Contents::Text(contents, format)))) => {
+                self.buffers.push(Buffer {
+                    path: path.clone(),
+                    content: text_editor::Content::with_text(&contents),
+                    is_dirty: false,
+                    head: None,
+                    hunks: Vec::new(),
+                    conflicted: crate::merge::has_conflicts(&contents),
+                    merge: None,
+                    blame: None,
+                    document: None,
+                    hex: None,
+                    cursors: Vec::new(),
+                    format,
+                    snippet: None,
+                    undo: undo::Undo::default(),
+                    folds: Vec::new(),
+                    foldable: Vec::new(),
+                });
+
+                self.select(self.buffers.len() - 1);
+
+                if let Some(buffer) = self.buffers.last_mut() {
+                    buffer.refresh();
+
+                    if let Some(spans) = self.folded.get(&path) {
+                        buffer.restore(spans);
+                    }
+                }
+
+                Action::Run(load_head(path))
+            }
+            Message::Opened(Err(error)) => Action::Error(error.to_string()),
+            Message::HeadLoaded(path, result) => {
+                let Some(buffer) = self.buffers.iter_mut().find(|buffer| buffer.path == path)
+                else {
+                    return Action::None;
+                };
+
+                match result {
+                    Ok(head) => {
+                        buffer.head = head;
+                        buffer.refresh();
+
+                        Action::None
+                    }
+                    Err(error) => Action::Error(error.to_string()),
+                }
+            }
+            Message::Select(index) => {
+                let previous = self.buffers.get(self.active);
+                let autosave = (settings.autosave == Autosave::FocusChange && index != self.active)
+                    .then(|| previous.and_then(save))
+                    .flatten();
+
+                self.select(index);
+
+                Action::Run(autosave.unwrap_or_else(Task::none))
+            }
+            Message::Close(index) => {
+                let Some(buffer) = self.buffers.get(index) else {
+                    return Action::None;
+                };
+
+                if buffer.is_dirty {
+                    self.confirming = Some(buffer.path.clone());
+
+                    return Action::None;
+                }
+
+                Action::Run(self.close(index))
+            }
+            Message::SaveAndClose => {
+                let Some(path) = self.confirming.take() else {
+                    return Action::None;
+                };
+
+                let Some(index) = self.buffers.iter().position(|buffer| buffer.path == path) else {
+                    return Action::None;
+                };
+
+                self.closing.insert(path);
+                self.select(index);
+
+                self.update(Message::Save, settings)
+            }
+            Message::Discard => {
+                let Some(path) = self.confirming.take() else {
+                    return Action::None;
+                };
+
+                match self.buffers.iter().position(|buffer| buffer.path == path) {
+                    Some(index) => Action::Run(self.close(index)),
+                    None => Action::None,
+                }
+            }
+            Message::CancelClose => {
+                self.confirming = None;
+
+                Action::None
+            }
+            Message::Edit(action) => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                if !matches!(action, text_editor::Action::Scroll { .. }) {
+                    self.completion = None;
+                }
+
+                let follow = !matches!(
+                    action,
+                    text_editor::Action::Click(_)
+                        | text_editor::Action::Drag(_)
+                        | text_editor::Action::Scroll { .. }
+                );
+
+                if let text_editor::Action::Edit(edit) = &action {
+                    if settings.vim {
+                        self.vim.record(edit);
+                    }
+
+                    buffer
+                        .undo
+                        .record(&buffer.content, &buffer.folds, Some(edit));
+                    buffer.is_dirty = true;
+
+                    let extension = buffer.extension().to_owned();
+                    let unit = typing::unit(&buffer.content, settings.tab_width);
+
+                    buffer.change(|content, others| {
+                        cursors::edit(content, others, |content| match edit {
+                            text_editor::Edit::Insert(c) => {
+                                typing::insert(content, *c, &extension, &unit);
+                            }
+                            text_editor::Edit::Enter => typing::enter(content, &extension, &unit),
+                            text_editor::Edit::Backspace => {
+                                typing::backspace(content, &extension, &unit);
+                            }
+                            _ => content.perform(action.clone()),
+                        });
+                    });
+                    buffer.refresh();
+                    self.hunk = None;
+                    self.suggest(edit, settings);
+
+                    return Action::Run(self.scroll_to_cursor(settings));
+                }
+
+                buffer.undo.interrupt();
+
+                match action {
+                    text_editor::Action::Click(_) if self.modifiers.alt() => {
+                        let cursor = cursors::primary(&mut buffer.content);
+
+                        buffer.content.perform(action);
+
+                        let position = buffer.content.cursor_position();
+
+                        if !buffer.cursors.contains(&cursor) && cursor.head != position {
+                            buffer.cursors.push(cursor);
+                        }
+
+                        self.column = Some(position);
+                    }
+                    text_editor::Action::Drag(_) if self.modifiers.alt() => {
+                        buffer.content.perform(action);
+
+                        if let Some(anchor) = self.column {
+                            cursors::select_column(
+                                &mut buffer.content,
+                                &mut buffer.cursors,
+                                anchor,
+                            );
+                        }
+                    }
+                    text_editor::Action::Move(_) | text_editor::Action::Select(_) => {
+                        cursors::perform(&mut buffer.content, &mut buffer.cursors, action);
+                    }
+                    text_editor::Action::Scroll { .. } => buffer.content.perform(action),
+                    _ => {
+                        buffer.cursors.clear();
+                        self.column = None;
+                        buffer.content.perform(action);
+                    }
+                }
+
+                if follow {
+                    return Action::Run(self.scroll_to_cursor(settings));
+                }
+
+                Action::None
+            }
+            Message::Save => {
+                let Some(buffer) = self
+                    .buffers
+                    .get(self.active)
+                    .filter(|buffer| buffer.is_editable())
+                else {
+                    return Action::None;
+                };
+
+                if buffer.format.lossy {
+                    self.overwriting = Some(buffer.path.clone());
+
+                    return Action::None;
+                }
+
+                if settings.format_on_save
+                    && let Some(formatter) = settings.formatters.get(buffer.extension())
+                {
+                    return Action::Run(run_formatter(
+                        buffer,
+                        formatter.clone(),
+                        Some(buffer.format),
+                    ));
+                }
+
+                Action::Run(Task::perform(
+                    save_file(buffer.path.clone(), buffer.text(), buffer.format),
+                    Message::Saved,
+                ))
+            }
+            Message::SaveAnyway => {
+                let Some(path) = self.overwriting.take() else {
+                    return Action::None;
+                };
+
+                let Some(index) = self.buffers.iter().position(|buffer| buffer.path == path) else {
+                    return Action::None;
+                };
+
+                self.buffers[index].format.lossy = false;
+                self.select(index);
+
+                self.update(Message::Save, settings)
+            }
+            Message::CancelSave => {
+                if let Some(path) = self.overwriting.take() {
+                    self.closing.remove(&path);
+                }
+
+                Action::None
+            }
+            Message::Format => {
+                let Some(buffer) = self
+                    .buffers
+                    .get(self.active)
+                    .filter(|buffer| buffer.is_editable())
+                else {
+                    return Action::None;
+                };
+
+                match settings.formatters.get(buffer.extension()) {
+                    Some(formatter) => Action::Run(run_formatter(buffer, formatter.clone(), None)),
+                    None => Action::Error(format!(
+                        "No formatter is set for .{} files",
+                        buffer.extension()
+                    )),
+                }
+            }
+            Message::Formatted(path, original, result, save) => {
+                let (formatted, error) = match result {
+                    Ok(formatted) => (Some(formatted), None),
+                    Err(error) => (None, Some(error)),
+                };
+
+                // A buffer is left open to see why it was not formatted.
+                if error.is_some() {
+                    self.closing.remove(&path);
+                }
+
+                // Saved as formatted even when closed while formatting, in
+                // the format of the buffer if converted meanwhile.
+                let (text, format) =
+                    match self.buffers.iter_mut().find(|buffer| buffer.path == path) {
+                        Some(buffer) => {
+                            // Edits made while formatting are kept over the
+                            // formatted text.
+                            if let Some(formatted) = &formatted
+                                && buffer.text() == original
+                            {
+                                buffer.reformat(formatted);
+                                self.hunk = None;
+                            }
+
+                            (buffer.text(), save.map(|_| buffer.format))
+                        }
+                        None => (formatted.unwrap_or(original), save),
+                    };
+
+                let saved = format
+                    .map(|format| Task::perform(save_file(path, text, format), Message::Saved));
+                let failed = error.map(|error| Task::done(Message::FormatFailed(error)));
+
+                Action::Run(Task::batch(saved.into_iter().chain(failed)))
+            }
+            Message::FormatFailed(error) => Action::Error(error.to_string()),
+            Message::Preview => Action::Preview,
+            Message::Saved(Ok((path, saved))) => {
+                let Some(index) = self.buffers.iter().position(|buffer| buffer.path == path) else {
+                    return Action::None;
+                };
+
+                let buffer = &mut self.buffers[index];
+
+                // Edits made while saving are still to be saved.
+                buffer.is_dirty = buffer.text() != saved;
+                buffer.format.mixed = false;
+
+                if self.closing.remove(&path) && !buffer.is_dirty {
+                    return Action::Run(self.close(index));
+                }
+
+                if buffer.blame.is_some() {
+                    return Action::Run(load_blame(buffer));
+                }
+
+                Action::None
+            }
+            Message::Saved(Err(error)) => {
+                let (Error::Io(path, _) | Error::Encoding(path, _)) = &error;
+
+                self.closing.remove(path);
+
+                Action::Error(error.to_string())
+            }
+            Message::Scrolled(viewport) => {
+                self.viewport = Some(viewport);
+
+                Action::None
+            }
+            Message::ToggleHunk(index) => {
+                self.hunk = (self.hunk != Some(index)).then_some(index);
+
+                Action::None
+            }
+            Message::RevertHunk(index) => {
+                self.hunk = None;
+
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                // The lines of hunks are lines of the full text.
+                buffer.unfold_all();
+
+                let (Some(head), Some(hunk)) = (&buffer.head, buffer.hunks.get(index)) else {
+                    return Action::None;
+                };
+
+                let original = diff::lines(head, hunk.old.clone()).to_owned();
+
+                buffer.undo.record(&buffer.content, &buffer.folds, None);
+                buffer.cursors.clear();
+
+                replace_lines(&mut buffer.content, hunk.new.clone(), &original);
+                buffer.is_dirty = true;
+                buffer.refresh();
+
+                Action::None
+            }
+            Message::StageHunk(index) => {
+                self.hunk = None;
+
+                let Some(buffer) = self.buffers.get(self.active) else {
+                    return Action::None;
+                };
+
+                let Some(hunk) = buffer.hunks.get(index) else {
+                    return Action::None;
+                };
+
+                Action::Run(Task::perform(
+                    git::stage_hunk(
+                        buffer.path.clone(),
+                        buffer.text(),
+                        buffer.format,
+                        hunk.clone(),
+                    ),
+                    Message::Staged,
+                ))
+            }
+            Message::Staged(Ok(())) => Action::None,
+            Message::Staged(Err(error)) => Action::Error(error.to_string()),
+            Message::Compare => match self.buffers.get(self.active) {
+                Some(buffer) => Action::Compare(buffer.path.clone(), buffer.text()),
+                None => Action::None,
+            },
+            Message::Resolve => {
+                let Some(buffer) = self.buffers.get(self.active) else {
+                    return Action::None;
+                };
+
+                let path = buffer.path.clone();
+
+                Action::Run(Task::perform(git::ancestor(path.clone()), move |result| {
+                    Message::AncestorLoaded(path.clone(), result)
+                }))
+            }
+            Message::AncestorLoaded(path, result) => {
+                let Some(buffer) = self.buffers.iter_mut().find(|buffer| buffer.path == path)
+                else {
+                    return Action::None;
+                };
+
+                // The conflicts are still worth resolving without their
+                // ancestor.
+                let (ancestor, action) = match result {
+                    Ok(ancestor) => (ancestor, Action::None),
+                    Err(error) => (None, Action::Error(error.to_string())),
+                };
+
+                buffer.merge = Some(merge::Merge::new(&buffer.text(), ancestor.as_deref()));
+
+                action
+            }
+            Message::Merge(message) => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                let Some(merge) = &mut buffer.merge else {
+                    return Action::None;
+                };
+
+                merge.update(&message);
+
+                match message {
+                    merge::Message::Write { stage } => Action::Run(Task::perform(
+                        save_file(buffer.path.clone(), merge.result(), buffer.format),
+                        move |result| Message::Resolved(result.map(|(path, _)| path), stage),
+                    )),
+                    merge::Message::Cancel => {
+                        buffer.merge = None;
+
+                        Action::None
+                    }
+                    merge::Message::Accept(..) | merge::Message::Reset(_) => Action::None,
+                }
+            }
+            Message::Resolved(Ok(path), stage) => {
+                let Some(buffer) = self.buffers.iter_mut().find(|buffer| buffer.path == path)
+                else {
+                    return Action::None;
+                };
+
+                if let Some(merge) = buffer.merge.take() {
+                    buffer.undo.record(&buffer.content, &buffer.folds, None);
+                    buffer.cursors.clear();
+                    buffer.folds.clear();
+                    buffer.content = text_editor::Content::with_text(&merge.result());
+                    buffer.is_dirty = false;
+                    buffer.refresh();
+                }
+
+                if stage {
+                    return Action::Run(Task::perform(git::stage(vec![path]), Message::Staged));
+                }
+
+                Action::None
+            }
+            Message::Resolved(Err(error), _) => Action::Error(error.to_string()),
+            Message::ToggleBlame => {
+                let Some(buffer) = self
+                    .buffers
+                    .get_mut(self.active)
+                    .filter(|buffer| buffer.is_editable())
+                else {
+                    return Action::None;
+                };
+
+                if buffer.blame.take().is_some() {
+                    return Action::None;
+                }
+
+                Action::Run(load_blame(buffer))
+            }
+            Message::BlameLoaded(path, result) => {
+                let Some(buffer) = self.buffers.iter_mut().find(|buffer| buffer.path == path)
+                else {
+                    return Action::None;
+                };
+
+                match result {
+                    Ok(annotations) => {
+                        buffer.blame = Some(annotations);
+
+                        Action::None
+                    }
+                    Err(error) => Action::Error(error.to_string()),
+                }
+            }
+            Message::ShowCommit(id) => match self.buffers.get(self.active) {
+                Some(buffer) => Action::ShowCommit(id, buffer.path.clone()),
+                None => Action::None,
+            },
+            Message::ShowHistory => match self.buffers.get(self.active) {
+                Some(buffer) => Action::ShowHistory(buffer.path.clone()),
+                None => Action::None,
+            },
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+
+                Action::None
+            }
+            Message::Find => {
+                let selected = self
+                    .buffers
+                    .get(self.active)
+                    .and_then(|buffer| buffer.content.selection())
+                    .filter(|selection| !selection.contains('\n'));
+
+                let search = match (self.search.take(), selected) {
+                    (_, Some(selected)) => search::Search::new(selected),
+                    (Some(search), None) => search,
+                    (None, None) => search::Search::new(String::new()),
+                };
+
+                let input = search.input.clone();
+
+                self.search = Some(search);
+
+                Action::Run(Task::batch([
+                    text_input::focus(input.clone()),
+                    text_input::select_all(input),
+                ]))
+            }
+            Message::QueryEdited(query) => {
+                let Some(search) = &mut self.search else {
+                    return Action::None;
+                };
+
+                search.query = query;
+
+                // The search goes on from the start of the current match.
+                self.find(false, true, settings)
+            }
+            Message::FindNext(backward) => self.find(backward, false, settings),
+            Message::CloseFind => {
+                self.search = None;
+
+                Action::None
+            }
+            Message::AddNextOccurrence => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                buffer.undo.interrupt();
+                cursors::add_next(&mut buffer.content, &mut buffer.cursors);
+
+                Action::Run(self.scroll_to_cursor(settings))
+            }
+            Message::WindowUnfocused if settings.autosave == Autosave::FocusChange => {
+                Action::Run(self.save_all())
+            }
+            Message::WindowUnfocused => Action::None,
+            Message::Autosave => {
+                let delay = Duration::from_secs(settings.autosave_delay);
+
+                if self.edited.is_some_and(|edited| edited.elapsed() < delay) {
+                    return Action::None;
+                }
+
+                Action::Run(self.save_all())
+            }
+            Message::Journal => {
+                let mut journaled = BTreeMap::new();
+                let mut tasks = Vec::new();
+
+                for buffer in self.buffers.iter().filter(|buffer| buffer.is_dirty) {
+                    let contents = buffer.text();
+                    let hash = recovery::hash(&contents);
+
+                    if self.journaled.get(&buffer.path) != Some(&hash) {
+                        let entry = recovery::Entry {
+                            path: buffer.path.clone(),
+                            contents,
+                            format: buffer.format,
+                        };
+
+                        tasks.push(Task::perform(recovery::write(entry), Message::Journaled));
+                    }
+
+                    journaled.insert(buffer.path.clone(), hash);
+                }
+
+                for path in self.journaled.keys() {
+                    if !journaled.contains_key(path) {
+                        tasks.push(Task::perform(
+                            recovery::remove(path.clone()),
+                            Message::Journaled,
+                        ));
+                    }
+                }
+
+                self.journaled = journaled;
+
+                Action::Run(Task::batch(tasks))
+            }
+            Message::Journaled(Ok(())) => Action::None,
+            Message::Journaled(Err(error)) => Action::Error(error.to_string()),
+            Message::Recover(index) => {
+                if index >= self.recovered.len() {
+                    return Action::None;
+                }
+
+                let entry = self.recovered.remove(index);
+
+                if let Some(index) = self
+                    .buffers
+                    .iter()
+                    .position(|buffer| buffer.path == entry.path)
+                {
+                    self.select(index);
+                    self.buffers[index].reformat(&entry.contents);
+                    self.buffers[index].format = entry.format;
+
+                    return Action::None;
+                }
+
+                let contents = Contents::Text(Arc::new(entry.contents), entry.format);
+                let action = self.update(Message::Opened(Ok((entry.path, contents))), settings);
+
+                if let Some(buffer) = self.buffers.last_mut() {
+                    buffer.is_dirty = true;
+                }
+
+                action
+            }
+            Message::DiscardRecovered(index) => {
+                if index >= self.recovered.len() {
+                    return Action::None;
+                }
+
+                let entry = self.recovered.remove(index);
+
+                Action::Run(Task::perform(
+                    recovery::remove(entry.path),
+                    Message::Journaled,
+                ))
+            }
+            Message::EncodingSelected(encoding) => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                if buffer.format.encoding != encoding {
+                    buffer.format.encoding = encoding;
+                    buffer.is_dirty = true;
+                }
+
+                Action::None
+            }
+            Message::LineEndingSelected(line_ending) => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                if buffer.format.line_ending != line_ending || buffer.format.mixed {
+                    buffer.format.line_ending = line_ending;
+                    buffer.format.mixed = false;
+                    buffer.is_dirty = true;
+                }
+
+                Action::None
+            }
+            Message::Complete => {
+                let Some(buffer) = self
+                    .buffers
+                    .get(self.active)
+                    .filter(|buffer| buffer.is_editable())
+                else {
+                    return Action::None;
+                };
+
+                let (start, prefix) = word_before(&buffer.content);
+                self.completion = self.offer(start, prefix);
+
+                Action::None
+            }
+            Message::CompletionMoved(by) => {
+                if let Some(completion) = &mut self.completion {
+                    completion.select(by);
+                }
+
+                Action::None
+            }
+            Message::CompletionAccepted(index) => {
+                let Some(completion) = self.completion.take() else {
+                    return Action::None;
+                };
+
+                let (Some(item), Some(buffer)) = (
+                    completion.items.get(index),
+                    self.buffers.get_mut(self.active),
+                ) else {
+                    return Action::None;
+                };
+
+                buffer.undo.record(&buffer.content, &buffer.folds, None);
+                buffer.is_dirty = true;
+
+                let head = buffer.content.cursor_position();
+
+                buffer.change(|content, _| {
+                    cursors::select(
+                        content,
+                        cursors::Cursor {
+                            anchor: completion.start,
+                            head,
+                        },
+                    );
+                    paste(content, item.label.clone());
+                });
+                buffer.refresh();
+                self.hunk = None;
+                self.edited = Some(Instant::now());
+
+                Action::Run(self.scroll_to_cursor(settings))
+            }
+            Message::CompletionDismissed => {
+                self.completion = None;
+
+                Action::None
+            }
+            Message::Indexed(words) => {
+                self.words = words;
+                self.indexing = false;
+
+                match self.project.clone() {
+                    Some((root, excludes)) if self.outdated => {
+                        Action::Run(self.index(root, excludes))
+                    }
+                    _ => Action::None,
+                }
+            }
+            Message::ToggleSnippets => {
+                self.picking = !self.picking;
+
+                Action::None
+            }
+            Message::InsertSnippet(index) => {
+                let Some(snippet) = self.snippets.snippets.get(index) else {
+                    return Action::None;
+                };
+
+                let body = snippet.body.clone();
+                self.picking = false;
+
+                self.insert_snippet(&body, settings)
+            }
+            Message::Choose(choice) => {
+                let Some(buffer) = self
+                    .buffers
+                    .get_mut(self.active)
+                    .filter(|buffer| buffer.snippet.is_some())
+                else {
+                    return Action::None;
+                };
+
+                buffer.undo.record(&buffer.content, &buffer.folds, None);
+                buffer.is_dirty = true;
+
+                buffer.change(|content, others| {
+                    cursors::edit(content, others, |content| paste(content, choice.clone()));
+                });
+                buffer.refresh();
+                self.hunk = None;
+
+                Action::None
+            }
+            Message::MinimapMoved(line) => {
+                self.minimap = line;
+
+                if self.dragging {
+                    Action::Run(self.scroll_to_line(line, settings))
+                } else {
+                    Action::None
+                }
+            }
+            Message::MinimapPressed => {
+                self.dragging = true;
+
+                Action::Run(self.scroll_to_line(self.minimap, settings))
+            }
+            Message::MinimapReleased => {
+                self.dragging = false;
+
+                Action::None
+            }
+            Message::JumpTo(line) => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                buffer.undo.interrupt();
+                buffer.cursors.clear();
+                cursors::place(&mut buffer.content, (line, 0));
+
+                Action::Run(self.scroll_to_cursor(settings))
+            }
+            Message::Indent | Message::Outdent => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                let backward = matches!(message, Message::Outdent);
+
+                // Tab moves between the stops of a snippet, or expands the
+                // snippet whose prefix was just typed.
+                if let Some(mut session) = buffer.snippet.take() {
+                    buffer.undo.interrupt();
+
+                    if session.advance(&mut buffer.content, &mut buffer.cursors, backward) {
+                        buffer.snippet = Some(session);
+                    }
+
+                    return Action::Run(self.scroll_to_cursor(settings));
+                }
+
+                if !backward && buffer.cursors.is_empty() && buffer.content.selection().is_none() {
+                    let (line, index) = buffer.content.cursor_position();
+                    let before = buffer
+                        .content
+                        .line(line)
+                        .map(|text| text[..index.min(text.len())].to_owned())
+                        .unwrap_or_default();
+
+                    if let Some((snippet, prefix)) =
+                        snippet::find(self.snippets.of(buffer.extension()), &before)
+                    {
+                        let body = snippet.body.clone();
+
+                        cursors::select(
+                            &mut buffer.content,
+                            cursors::Cursor {
+                                anchor: (line, index - prefix),
+                                head: (line, index),
+                            },
+                        );
+
+                        return self.insert_snippet(&body, settings);
+                    }
+                }
+
+                let unit = typing::unit(&buffer.content, settings.tab_width);
+
+                buffer.undo.record(&buffer.content, &buffer.folds, None);
+                buffer.is_dirty = true;
+                buffer.change(|content, others| {
+                    cursors::edit(content, others, |content| match message {
+                        Message::Indent => typing::indent(content, &unit),
+                        _ => typing::outdent(content, &unit),
+                    });
+                });
+                buffer.refresh();
+                self.hunk = None;
+
+                Action::Run(self.scroll_to_cursor(settings))
+            }
+            Message::ClearCursors => {
+                if let Some(buffer) = self.buffers.get_mut(self.active) {
+                    buffer.cursors.clear();
+                    buffer.snippet = None;
+                }
+
+                Action::None
+            }
+            Message::Undo | Message::Redo => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                let changed = match message {
+                    Message::Undo => buffer.undo.undo(&mut buffer.content, &mut buffer.folds),
+                    _ => buffer.undo.redo(&mut buffer.content, &mut buffer.folds),
+                };
+
+                if !changed {
+                    return Action::None;
+                }
+
+                buffer.cursors.clear();
+                buffer.snippet = None;
+                buffer.is_dirty = true;
+                buffer.refresh();
+                self.hunk = None;
+
+                Action::Run(self.scroll_to_cursor(settings))
+            }
+            Message::Vim(key) => {
+                let Some(buffer) = self
+                    .buffers
+                    .get_mut(self.active)
+                    .filter(|buffer| buffer.is_editable())
+                else {
+                    return Action::None;
+                };
+
+                let before = buffer.text();
+                let (line, index) = buffer.content.cursor_position();
+                let cursor = (buffer.full_line(line), index);
+                let indent = " ".repeat(settings.tab_width);
+
+                // The key is handled again if its change unfolds lines.
+                let vim = &mut self.vim;
+                let saved = (!buffer.folds.is_empty()).then(|| vim.clone());
+                let mut effect = vim::Effect::None;
+
+                buffer.change(|content, _| {
+                    if let Some(saved) = &saved {
+                        *vim = saved.clone();
+                    }
+
+                    effect = vim.key(key, content, &indent);
+                });
+
+                if buffer.text() != before {
+                    // A whole command is undone at once.
+                    buffer.undo.push(before, cursor);
+                    buffer.cursors.clear();
+                    buffer.is_dirty = true;
+                    buffer.refresh();
+                    self.hunk = None;
+                }
+
+                let is_dirty = buffer.is_dirty;
+                let path = buffer.path.clone();
+
+                match effect {
+                    vim::Effect::None => Action::Run(self.scroll_to_cursor(settings)),
+                    vim::Effect::Save => self.update(Message::Save, settings),
+                    vim::Effect::Quit { force: false } if is_dirty => Action::Error(String::from(
+                        "No write since last change (add ! to override)",
+                    )),
+                    vim::Effect::Quit { .. } => Action::Run(self.close(self.active)),
+                    vim::Effect::SaveAndQuit if is_dirty => {
+                        // Closed once saved, and left open if saving fails.
+                        self.closing.insert(path);
+
+                        self.update(Message::Save, settings)
+                    }
+                    vim::Effect::SaveAndQuit => Action::Run(self.close(self.active)),
+                    vim::Effect::Undo(count) | vim::Effect::Redo(count) => {
+                        let message = match effect {
+                            vim::Effect::Undo(_) => Message::Undo,
+                            _ => Message::Redo,
+                        };
+
+                        let mut action = Action::None;
+
+                        for _ in 0..count {
+                            action = self.update(message.clone(), settings);
+                        }
+
+                        action
+                    }
+                    vim::Effect::Copy(text) => Action::Run(iced::clipboard::write(text)),
+                    vim::Effect::Paste { before, count } => Action::Run(
+                        iced::clipboard::read()
+                            .map(move |text| Message::Pasted(text, before, count)),
+                    ),
+                    vim::Effect::Error(error) => Action::Error(error),
+                }
+            }
+            Message::Pasted(text, before, count) => {
+                let (Some(text), Some(buffer)) = (text, self.buffers.get_mut(self.active)) else {
+                    return Action::None;
+                };
+
+                let (line, index) = buffer.content.cursor_position();
+                let vim = &mut self.vim;
+
+                buffer
+                    .undo
+                    .push(buffer.text(), (buffer.full_line(line), index));
+                buffer.change(|content, _| vim.paste(content, text.clone(), before, count));
+                buffer.is_dirty = true;
+                buffer.refresh();
+                self.hunk = None;
+
+                Action::Run(self.scroll_to_cursor(settings))
+            }
+            Message::ToggleFold(line) => {
+                if let Some(buffer) = self.buffers.get_mut(self.active) {
+                    buffer.toggle_fold(line);
+                }
+
+                Action::None
+            }
+            Message::Fold | Message::Unfold => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                let line = buffer.full_line(buffer.content.cursor_position().0);
+
+                match message {
+                    Message::Fold => buffer.fold(line),
+                    _ => buffer.unfold(line),
+                }
+
+                Action::Run(self.scroll_to_cursor(settings))
+            }
+            Message::FoldAll | Message::UnfoldAll => {
+                let Some(buffer) = self.buffers.get_mut(self.active) else {
+                    return Action::None;
+                };
+
+                match message {
+                    Message::FoldAll => buffer.fold_all(),
+                    _ => buffer.unfold_all(),
+                }
+
+                Action::Run(self.scroll_to_cursor(settings))
+            }
+            Message::Document(path, message) => {
+                let Some(document) = self
+                    .buffers
+                    .iter_mut()
+                    .find(|buffer| buffer.path == path)
+                    .and_then(|buffer| buffer.document.as_mut())
+                else {
+                    return Action::None;
+                };
+
+                Action::Run(
+                    document
+                        .update(message, settings)
+                        .map(move |message| Message::Document(path.clone(), message)),
+                )
+            }
+            Message::Hex(path, message) => {
+                let Some(hex) = self
+                    .buffers
+                    .iter_mut()
+                    .find(|buffer| buffer.path == path)
+                    .and_then(|buffer| buffer.hex.as_mut())
+                else {
+                    return Action::None;
+                };
+
+                Action::Run(
+                    hex.update(message, settings)
+                        .map(move |message| Message::Hex(path.clone(), message)),
+                )
+            }
+        }
     }
 
-    pub fn view(&self) -> iced::Element<Message> {
-        iced::widget::text("Welcome to the editor!").into()
+    pub fn view<'a>(
+        &'a self,
+        settings: &'a Settings,
+        appearance: &'a Appearance,
+    ) -> iced::Element<'a, Message> {
+        let recovered = (!self.recovered.is_empty()).then(|| {
+            column(self.recovered.iter().enumerate().map(|(index, entry)| {
+                row![
+                    text(format!(
+                        "Unsaved changes to {} were recovered.",
+                        entry.path.display()
+                    ))
+                    .size(12),
+                    horizontal_space(),
+                    button(text("Restore").size(12))
+                        .padding([1, 6])
+                        .on_press(Message::Recover(index)),
+                    button(text("Discard").size(12))
+                        .padding([1, 6])
+                        .style(|theme, status| style::button(theme, status, &appearance.panes))
+                        .on_press(Message::DiscardRecovered(index)),
+                ]
+                .spacing(10)
+                .into()
+            }))
+            .spacing(5)
+        });
+
+        let confirming = self.confirming.as_ref().map(|path| {
+            row![
+                text(format!("{} has unsaved changes.", path.display())).size(12),
+                horizontal_space(),
+                button(text("Save").size(12))
+                    .padding([1, 6])
+                    .on_press(Message::SaveAndClose),
+                button(text("Discard").size(12))
+                    .padding([1, 6])
+                    .style(|theme, status| style::button(theme, status, &appearance.panes))
+                    .on_press(Message::Discard),
+                button(text("Cancel").size(12))
+                    .padding([1, 6])
+                    .style(|theme, status| style::button(theme, status, &appearance.panes))
+                    .on_press(Message::CancelClose),
+            ]
+            .spacing(10)
+        });
+
+        let overwriting = self.overwriting.as_ref().map(|path| {
+            row![
+                text(format!(
+                    "Some bytes of {} could not be decoded. Saving replaces them.",
+                    path.display()
+                ))
+                .size(12),
+                horizontal_space(),
+                button(text("Save Anyway").size(12))
+                    .padding([1, 6])
+                    .on_press(Message::SaveAnyway),
+                button(text("Cancel").size(12))
+                    .padding([1, 6])
+                    .style(|theme, status| style::button(theme, status, &appearance.panes))
+                    .on_press(Message::CancelSave),
+            ]
+            .spacing(10)
+        });
+
+        column![]
+            .push_maybe(recovered)
+            .push_maybe(confirming)
+            .push_maybe(overwriting)
+            .push(self.buffer(settings, appearance))
+            .spacing(5)
+            .into()
+    }
+
+    /// The tabs of the buffers, and the active one.
+    fn buffer<'a>(
+        &'a self,
+        settings: &'a Settings,
+        appearance: &'a Appearance,
+    ) -> iced::Element<'a, Message> {
+        let Some(buffer) = self.buffers.get(self.active) else {
+            return text("Welcome to the editor!").into();
+        };
+
+        let tabs = scrollable(row(self.buffers.iter().enumerate().map(
+            |(index, buffer)| {
+                let name = buffer
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+
+                let selected = index == self.active;
+
+                button(
+                    row![
+                        text(if buffer.is_dirty {
+                            format!("{name} •")
+                        } else {
+                            name
+                        })
+                        .size(12),
+                        button(text("×").size(12))
+                            .padding(0)
+                            .style(|theme, status| style::button(theme, status, &appearance.panes))
+                            .on_press(Message::Close(index)),
+                    ]
+                    .spacing(6),
+                )
+                .padding([2, 8])
+                .style(move |theme, status| style::entry(theme, status, selected))
+                .on_press(Message::Select(index))
+                .into()
+            },
+        )))
+        .direction(scrollable::Direction::Horizontal(
+            scrollable::Scrollbar::new().width(2).scroller_width(2),
+        ));
+
+        if let Some(document) = &buffer.document {
+            let path = buffer.path.clone();
+
+            return column![
+                tabs,
+                document
+                    .view(settings, appearance)
+                    .map(move |message| Message::Document(path.clone(), message)),
+            ]
+            .spacing(5)
+            .into();
+        }
+
+        if let Some(hex) = &buffer.hex {
+            let path = buffer.path.clone();
+
+            return column![
+                tabs,
+                hex.view(settings, appearance)
+                    .map(move |message| Message::Hex(path.clone(), message)),
+            ]
+            .spacing(5)
+            .into();
+        }
+
+        if let Some(merge) = &buffer.merge {
+            return column![tabs, merge.view(settings).map(Message::Merge)]
+                .spacing(5)
+                .into();
+        }
+
+        let line_height = settings.line_height();
+        let has_cursors = !buffer.cursors.is_empty();
+        let has_snippet = buffer.snippet.is_some();
+        let completion = self
+            .completion
+            .as_ref()
+            .map(|completion| completion.selected);
+        let vim = settings.vim.then_some(self.vim.mode);
+
+        let editor = widget::text_editor(&buffer.content)
+            .height(iced::Length::Shrink)
+            .padding(0)
+            .font(settings.font())
+            .size(settings.text_size)
+            .line_height(iced::Pixels(line_height))
+            .wrapping(text::Wrapping::None)
+            .highlight_with::<highlighter::Highlighter>(
+                highlighter::Settings {
+                    token: buffer.extension().to_owned(),
+                    is_dark: appearance.theme.extended_palette().is_dark,
+                    syntax: appearance.syntax,
+                },
+                |format, _theme| *format,
+            )
+            .on_action(Message::Edit)
+            .key_binding(move |key_press| {
+                let modifiers = key_press.modifiers;
+
+                if let Some(selected) = completion {
+                    let message = match key_press.key.as_ref() {
+                        keyboard::Key::Named(keyboard::key::Named::ArrowUp) => {
+                            Some(Message::CompletionMoved(-1))
+                        }
+                        keyboard::Key::Named(keyboard::key::Named::ArrowDown) => {
+                            Some(Message::CompletionMoved(1))
+                        }
+                        keyboard::Key::Named(
+                            keyboard::key::Named::Enter | keyboard::key::Named::Tab,
+                        ) => Some(Message::CompletionAccepted(selected)),
+                        keyboard::Key::Named(keyboard::key::Named::Escape) => {
+                            Some(Message::CompletionDismissed)
+                        }
+                        _ => None,
+                    };
+
+                    if let Some(message) = message {
+                        return Some(text_editor::Binding::Custom(message));
+                    }
+                }
+
+                if let Some(mode) = vim
+                    && let Some(key) = vim::Key::from_key_press(&key_press, mode)
+                {
+                    return Some(text_editor::Binding::Custom(Message::Vim(key)));
+                }
+
+                let message = match key_press.key.as_ref() {
+                    keyboard::Key::Character("s") if modifiers.command() => Message::Save,
+                    keyboard::Key::Character("f") if modifiers.command() => Message::Find,
+                    keyboard::Key::Character("f" | "F") if modifiers.shift() && modifiers.alt() => {
+                        Message::Format
+                    }
+                    keyboard::Key::Character("d") if modifiers.command() => {
+                        Message::AddNextOccurrence
+                    }
+                    keyboard::Key::Character("z") if modifiers.command() && modifiers.shift() => {
+                        Message::Redo
+                    }
+                    keyboard::Key::Character("z") if modifiers.command() => Message::Undo,
+                    keyboard::Key::Character("y") if modifiers.command() => Message::Redo,
+                    keyboard::Key::Character("[" | "{")
+                        if modifiers.command() && modifiers.alt() =>
+                    {
+                        Message::FoldAll
+                    }
+                    keyboard::Key::Character("]" | "}")
+                        if modifiers.command() && modifiers.alt() =>
+                    {
+                        Message::UnfoldAll
+                    }
+                    keyboard::Key::Character("[" | "{")
+                        if modifiers.command() && modifiers.shift() =>
+                    {
+                        Message::Fold
+                    }
+                    keyboard::Key::Character("]" | "}")
+                        if modifiers.command() && modifiers.shift() =>
+                    {
+                        Message::Unfold
+                    }
+                    keyboard::Key::Named(keyboard::key::Named::Space) if modifiers.control() => {
+                        Message::Complete
+                    }
+                    keyboard::Key::Character("j") if modifiers.command() => Message::ToggleSnippets,
+                    keyboard::Key::Named(keyboard::key::Named::Escape)
+                        if has_cursors || has_snippet =>
+                    {
+                        Message::ClearCursors
+                    }
+                    keyboard::Key::Named(keyboard::key::Named::Tab)
+                        if key_press.status == text_editor::Status::Focused
+                            && vim.is_none_or(|mode| mode == vim::Mode::Insert) =>
+                    {
+                        if modifiers.shift() {
+                            Message::Outdent
+                        } else {
+                            Message::Indent
+                        }
+                    }
+                    _ => return text_editor::Binding::from_key_press(key_press),
+                };
+
+                Some(text_editor::Binding::Custom(message))
+            });
+
+        let lines = self.lines_in_view(buffer, settings);
+
+        let completion = self.completion.as_ref().map(|completion| {
+            let line = buffer
+                .content
+                .line(completion.start.0)
+                .map(|text| text.to_string())
+                .unwrap_or_default();
+
+            completion.view(&line, settings)
+        });
+
+        let editor = stack![editor, decorations::view(buffer, lines.clone(), settings)]
+            .push_maybe(has_cursors.then(|| {
+                cursors::view(
+                    &buffer.content,
+                    &buffer.cursors,
+                    settings,
+                    appearance.theme.extended_palette().primary.base.color,
+                )
+            }))
+            .push_maybe(completion);
+
+        let numbers = numbers::view(buffer, lines.clone(), settings);
+        let gutter = gutter::view(buffer, self.hunk, line_height);
+        let folds = fold::view(buffer, line_height);
+
+        let blame = buffer
+            .blame
+            .as_deref()
+            .map(|annotations| blame::view(buffer, annotations, settings));
+
+        let query = self
+            .search
+            .as_ref()
+            .map(|search| search.query.as_str())
+            .filter(|query| !query.is_empty());
+
+        let minimap = minimap::view(buffer, lines, query, settings);
+
+        let content = scrollable(
+            row![]
+                .push_maybe(blame)
+                .push_maybe(numbers)
+                .push(gutter)
+                .push(folds)
+                .push(editor)
+                .spacing(4),
+        )
+        .id(self.scrollable.clone())
+        .on_scroll(Message::Scrolled)
+        .height(iced::Length::Fill);
+
+        let content = row![content, minimap].spacing(4);
+
+        let status = row![
+            text(buffer.path.display().to_string()).size(12),
+            horizontal_space(),
+            button(text("Snippets").size(12))
+                .padding([1, 6])
+                .style(|theme, status| style::button(theme, status, &appearance.panes))
+                .on_press(Message::ToggleSnippets),
+            button(text("Format").size(12))
+                .padding([1, 6])
+                .style(|theme, status| style::button(theme, status, &appearance.panes))
+                .on_press_maybe(
+                    settings
+                        .formatters
+                        .contains_key(buffer.extension())
+                        .then_some(Message::Format)
+                ),
+            button(text("Preview").size(12))
+                .padding([1, 6])
+                .style(|theme, status| style::button(theme, status, &appearance.panes))
+                .on_press_maybe(is_markdown(buffer.extension()).then_some(Message::Preview)),
+            button(text("Compare with saved").size(12))
+                .padding([1, 6])
+                .style(|theme, status| style::button(theme, status, &appearance.panes))
+                .on_press_maybe(buffer.is_dirty.then_some(Message::Compare)),
+            button(text("History").size(12))
+                .padding([1, 6])
+                .style(|theme, status| style::button(theme, status, &appearance.panes))
+                .on_press(Message::ShowHistory),
+            button(
+                text(if buffer.blame.is_some() {
+                    "Hide blame"
+                } else {
+                    "Blame"
+                })
+                .size(12)
+            )
+            .padding([1, 6])
+            .style(|theme, status| style::button(theme, status, &appearance.panes))
+            .on_press(Message::ToggleBlame),
+            button(
+                text(if buffer.folds.is_empty() {
+                    "Fold all"
+                } else {
+                    "Unfold all"
+                })
+                .size(12)
+            )
+            .padding([1, 6])
+            .style(|theme, status| style::button(theme, status, &appearance.panes))
+            .on_press(if buffer.folds.is_empty() {
+                Message::FoldAll
+            } else {
+                Message::UnfoldAll
+            }),
+            text({
+                let (line, column) = buffer.content.cursor_position();
+                let line = buffer.full_line(line);
+
+                match buffer.cursors.len() {
+                    0 => format!("{}:{}", line + 1, column + 1),
+                    cursors => format!("{}:{} ({} cursors)", line + 1, column + 1, cursors + 1),
+                }
+            })
+            .size(12),
+        ]
+        .push_maybe(
+            buffer
+                .format
+                .mixed
+                .then(|| text("Mixed line endings").size(12)),
+        )
+        .push_maybe(
+            buffer
+                .format
+                .lossy
+                .then(|| text("Undecodable bytes").size(12)),
+        )
+        .push(
+            pick_list(
+                encoding::LineEnding::ALL,
+                Some(buffer.format.line_ending),
+                Message::LineEndingSelected,
+            )
+            .text_size(12)
+            .padding([1, 6]),
+        )
+        .push(
+            pick_list(
+                encoding::Encoding::ALL,
+                Some(buffer.format.encoding),
+                Message::EncodingSelected,
+            )
+            .text_size(12)
+            .padding([1, 6]),
+        )
+        .spacing(10);
+
+        let find = self.search.as_ref().map(|search| {
+            let count = search::matches(&buffer.content.text(), &search.query).len();
+
+            row![
+                text_input("Find", &search.query)
+                    .id(search.input.clone())
+                    .size(12)
+                    .padding([1, 6])
+                    .on_input(Message::QueryEdited)
+                    .on_submit(Message::FindNext(false)),
+                text(match count {
+                    1 => String::from("1 match"),
+                    count => format!("{count} matches"),
+                })
+                .size(12),
+                button(text("Previous").size(12))
+                    .padding([1, 6])
+                    .on_press_maybe((count > 0).then_some(Message::FindNext(true))),
+                button(text("Next").size(12))
+                    .padding([1, 6])
+                    .on_press_maybe((count > 0).then_some(Message::FindNext(false))),
+                button(text("Close").size(12))
+                    .padding([1, 6])
+                    .style(|theme, status| style::button(theme, status, &appearance.panes))
+                    .on_press(Message::CloseFind),
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center)
+        });
+
+        let conflicts = buffer.conflicted.then(|| {
+            row![
+                text("This file has merge conflicts.").size(12),
+                horizontal_space(),
+                button(text("Resolve conflicts").size(12))
+                    .padding([1, 6])
+                    .on_press(Message::Resolve),
+            ]
+            .spacing(10)
+        });
+
+        let choices = buffer
+            .snippet
+            .as_ref()
+            .filter(|session| !session.choices().is_empty())
+            .map(|session| {
+                row![text("Choices:").size(12)]
+                    .extend(session.choices().iter().map(|choice| {
+                        button(text(choice).size(12))
+                            .padding([1, 6])
+                            .style(|theme, status| style::button(theme, status, &appearance.panes))
+                            .on_press(Message::Choose(choice.clone()))
+                            .into()
+                    }))
+                    .spacing(5)
+                    .wrap()
+            });
+
+        let snippets = self.picking.then(|| {
+            let extension = buffer.extension();
+            let snippets: Vec<_> = self
+                .snippets
+                .snippets
+                .iter()
+                .enumerate()
+                .filter(|(_, snippet)| snippet.applies_to(extension))
+                .collect();
+
+            let list: iced::Element<'_, Message> = if snippets.is_empty() {
+                text(match settings::Snippets::directory() {
+                    Some(directory) => format!(
+                        "No snippets for this file. Snippets are read from {}.",
+                        directory.display()
+                    ),
+                    None => String::from("No snippets for this file."),
+                })
+                .size(12)
+                .into()
+            } else {
+                column(snippets.into_iter().map(|(index, snippet)| {
+                    button(
+                        row![
+                            text(snippet.prefixes.join(", ")).size(12).width(120),
+                            text(&snippet.name).size(12).width(200),
+                            text(&snippet.description).size(12),
+                        ]
+                        .spacing(10),
+                    )
+                    .width(iced::Length::Fill)
+                    .padding([1, 6])
+                    .style(|theme, status| style::button(theme, status, &appearance.panes))
+                    .on_press(Message::InsertSnippet(index))
+                    .into()
+                }))
+                .into()
+            };
+
+            scrollable(list).height(160)
+        });
+
+        column![tabs]
+            .push_maybe(conflicts)
+            .push_maybe(find)
+            .push(container(content).height(iced::Length::Fill))
+            .push_maybe(choices)
+            .push_maybe(snippets)
+            .push(status)
+            .spacing(5)
+            .into()
+    }
+
+    /// The path and text of the active buffer, if it is Markdown.
+    pub fn markdown(&self) -> Option<(PathBuf, String)> {
+        self.buffers
+            .get(self.active)
+            .filter(|buffer| buffer.is_editable() && is_markdown(buffer.extension()))
+            .map(|buffer| (buffer.path.clone(), buffer.text()))
+    }
+
+    /// How far the active buffer is scrolled, from 0 at the top to 1 at the
+    /// bottom, once it has been scrolled.
+    pub fn offset(&self) -> Option<f32> {
+        self.viewport
+            .map(|viewport| viewport.relative_offset().y)
+            .filter(|offset| offset.is_finite())
+    }
+
+    /// The Vim mode of the active buffer and the command being typed, if
+    /// Vim keybindings are on.
+    pub fn mode(&self, settings: &Settings) -> Option<String> {
+        self.buffers
+            .get(self.active)
+            .filter(|buffer| settings.vim && buffer.is_editable())
+            .map(|_| self.vim.status())
+    }
+
+    /// Follows the modifiers, which change what clicking and dragging do.
+    pub fn subscription(&self, settings: &Settings) -> Subscription<Message> {
+        let events = iced::event::listen_with(|event, _status, _window| match event {
+            iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                Some(Message::ModifiersChanged(modifiers))
+            }
+            iced::Event::Window(iced::window::Event::Unfocused) => Some(Message::WindowUnfocused),
+            _ => None,
+        });
+
+        let is_dirty = self.buffers.iter().any(|buffer| buffer.is_dirty);
+
+        let autosave = (settings.autosave == Autosave::Delay && is_dirty).then(|| {
+            iced::time::every(Duration::from_secs(settings.autosave_delay.max(1)))
+                .map(|_| Message::Autosave)
+        });
+
+        // Kept running to drop the journal of files saved since.
+        let journal = (settings.recovery && (is_dirty || !self.journaled.is_empty()))
+            .then(|| iced::time::every(JOURNAL_INTERVAL).map(|_| Message::Journal));
+
+        // The find bar takes the key presses, so Escape is listened to even
+        // when captured.
+        let find = self.search.is_some().then(|| {
+            iced::event::listen_with(|event, _status, _window| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                    ..
+                }) => Some(Message::CloseFind),
+                _ => None,
+            })
+        });
+
+        Subscription::batch(
+            [Some(events), autosave, journal, find]
+                .into_iter()
+                .flatten(),
+        )
+    }
+
+    fn select(&mut self, index: usize) {
+        if self.active != index {
+            self.hunk = None;
+            self.completion = None;
+            self.vim.reset();
+        }
+
+        self.active = index;
+    }
+
+    /// Selects a match of the search in the active buffer, from the cursor
+    /// or, when `again`, from the start of the selection.
+    fn find(&mut self, backward: bool, again: bool, settings: &Settings) -> Action {
+        let (Some(search), Some(buffer)) = (&self.search, self.buffers.get_mut(self.active)) else {
+            return Action::None;
+        };
+
+        let current = cursors::primary(&mut buffer.content);
+        let (start, end) = current.range();
+
+        let offset = if again || backward {
+            cursors::offset(&buffer.content, start)
+        } else {
+            cursors::offset(&buffer.content, end)
+        };
+
+        let matches = search::matches(&buffer.content.text(), &search.query);
+
+        let Some(found) = search::next(&matches, offset, backward) else {
+            cursors::select(&mut buffer.content, current);
+
+            return Action::None;
+        };
+
+        let found = cursors::Cursor {
+            anchor: cursors::position(&buffer.content, found.start),
+            head: cursors::position(&buffer.content, found.end),
+        };
+
+        buffer.cursors.clear();
+        cursors::select(&mut buffer.content, found);
+
+        Action::Run(self.scroll_to_cursor(settings))
+    }
+
+    /// Closes a buffer, dropping its entry in the recovery journal, if any.
+    fn close(&mut self, index: usize) -> Task<Message> {
+        if index >= self.buffers.len() {
+            return Task::none();
+        }
+
+        let buffer = self.buffers.remove(index);
+
+        if buffer.is_editable() {
+            self.folded.insert(buffer.path.clone(), buffer.spans());
+        }
+
+        // The active buffer stays active when another one is closed.
+        match index.cmp(&self.active) {
+            std::cmp::Ordering::Less => self.active -= 1,
+            std::cmp::Ordering::Equal => {
+                self.hunk = None;
+                self.completion = None;
+                self.vim.reset();
+            }
+            std::cmp::Ordering::Greater => {}
+        }
+
+        self.active = self.active.min(self.buffers.len().saturating_sub(1));
+
+        if self.journaled.remove(&buffer.path).is_none() {
+            return Task::none();
+        }
+
+        Task::perform(recovery::remove(buffer.path), Message::Journaled)
+    }
+
+    /// The lines of the content of a buffer in view, or those of a tall
+    /// screen until the editor is first scrolled.
+    fn lines_in_view(&self, buffer: &Buffer, settings: &Settings) -> Range<usize> {
+        let line_height = settings.line_height();
+        let (offset, height) = self.viewport.map_or((0.0, 2000.0), |viewport| {
+            (viewport.absolute_offset().y, viewport.bounds().height)
+        });
+
+        let count = buffer.content.line_count();
+        let start = ((offset / line_height).floor() as usize).min(count);
+        let end = (((offset + height) / line_height).ceil() as usize + 1).min(count);
+
+        start..end
+    }
+
+    /// Saves the dirty buffers, unformatted.
+    fn save_all(&self) -> Task<Message> {
+        Task::batch(self.buffers.iter().filter_map(save))
+    }
+
+    /// Offers to restore the unsaved changes left by a previous session.
+    pub fn recover(&mut self, entries: Vec<recovery::Entry>) {
+        self.recovered = entries;
+    }
+
+    /// Inserts a snippet over the selection of the active buffer, and enters
+    /// its first stop.
+    fn insert_snippet(&mut self, body: &str, settings: &Settings) -> Action {
+        let Some(buffer) = self
+            .buffers
+            .get_mut(self.active)
+            .filter(|buffer| buffer.is_editable())
+        else {
+            return Action::None;
+        };
+
+        buffer.cursors.clear();
+
+        let selected = buffer.content.selection().unwrap_or_default();
+        let selection = cursors::primary(&mut buffer.content);
+        let (line, index) = selection.anchor.min(selection.head);
+        let text = buffer
+            .content
+            .line(line)
+            .map(|text| text.to_string())
+            .unwrap_or_default();
+        let indent: String = text
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+
+        let word_start = text[..index]
+            .rfind(|c: char| !c.is_alphanumeric() && c != '_')
+            .map_or(0, |start| start + 1);
+        let word_end = text[index..]
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .map_or(text.len(), |end| index + end);
+
+        let context = snippet::Context {
+            path: &buffer.path,
+            selected: &selected,
+            line: &text,
+            word: &text[word_start..word_end],
+            line_index: buffer.full_line(line),
+        };
+
+        let unit = typing::unit(&buffer.content, settings.tab_width);
+        let expansion = snippet::expand(body, &indent, &unit, |name| context.variable(name));
+
+        cursors::select(&mut buffer.content, selection);
+
+        buffer.undo.record(&buffer.content, &buffer.folds, None);
+        buffer.is_dirty = true;
+
+        let mut session = None;
+
+        buffer.change(|content, cursors| {
+            session = snippet::Session::start(content, cursors, expansion.clone());
+        });
+
+        buffer.snippet = session;
+        buffer.refresh();
+        self.hunk = None;
+        self.edited = Some(Instant::now());
+
+        Action::Run(self.scroll_to_cursor(settings))
+    }
+
+    /// Reads the words of the files of a project, to be offered as
+    /// completions, once at a time.
+    pub fn index(&mut self, root: PathBuf, excludes: Vec<String>) -> Task<Message> {
+        self.project = Some((root.clone(), excludes.clone()));
+
+        if self.indexing {
+            self.outdated = true;
+
+            return Task::none();
+        }
+
+        self.indexing = true;
+        self.outdated = false;
+
+        Task::perform(completion::index(root, excludes), Message::Indexed)
+    }
+
+    /// Reads the words of the project again after files changed outside of
+    /// its repository.
+    pub fn reindex(&mut self, paths: &[PathBuf]) -> Task<Message> {
+        let Some((root, excludes)) = self.project.clone() else {
+            return Task::none();
+        };
+
+        let changed = paths
+            .iter()
+            .any(|path| path.starts_with(&root) && !path.starts_with(root.join(".git")));
+
+        if changed {
+            self.index(root, excludes)
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Offers completions for the word being typed after an edit, or updates
+    /// or dismisses those offered.
+    ///
+    /// Completions are only offered for files without a language server.
+    fn suggest(&mut self, edit: &text_editor::Edit, settings: &Settings) {
+        let Some(buffer) = self.buffers.get(self.active) else {
+            return;
+        };
+
+        let typing = match edit {
+            text_editor::Edit::Insert(c) => completion::is_word(*c),
+            text_editor::Edit::Backspace => self.completion.is_some(),
+            _ => false,
+        };
+
+        if !typing
+            || !buffer.cursors.is_empty()
+            || settings.language_servers.contains_key(buffer.extension())
+        {
+            self.completion = None;
+
+            return;
+        }
+
+        let (start, prefix) = word_before(&buffer.content);
+
+        self.completion = match self.completion.take() {
+            Some(mut offered) if offered.start == start && !prefix.is_empty() => {
+                offered.filter(prefix).then_some(offered)
+            }
+            _ if prefix.chars().count() >= completion::MIN_PREFIX => self.offer(start, prefix),
+            _ => None,
+        };
+    }
+
+    /// The completions of a prefix, from the words of the open files and of
+    /// the project.
+    fn offer(&self, start: cursors::Position, prefix: String) -> Option<completion::Completion> {
+        let mut words = completion::Words::new();
+
+        for buffer in self.buffers.iter().filter(|buffer| buffer.is_editable()) {
+            completion::count(&buffer.text(), &mut words);
+        }
+
+        let buffers = words
+            .into_iter()
+            .map(|(label, count)| completion::Candidate {
+                label,
+                source: completion::Source::Buffers,
+                count,
+            });
+
+        let project = self
+            .words
+            .iter()
+            .map(|(label, count)| completion::Candidate {
+                label: label.clone(),
+                source: completion::Source::Project,
+                count: *count,
+            });
+
+        completion::Completion::new(start, prefix, buffers.chain(project))
+    }
+
+    /// Uses reloaded snippets.
+    pub fn set_snippets(&mut self, snippets: settings::Snippets) {
+        self.snippets = snippets;
+    }
+
+    /// Scrolls the active buffer to bring a line to the middle of the view.
+    fn scroll_to_line(&self, line: usize, settings: &Settings) -> Task<Message> {
+        let Some(viewport) = self.viewport else {
+            return Task::none();
+        };
+
+        let line_height = settings.line_height();
+        let y = line as f32 * line_height - (viewport.bounds().height - line_height) / 2.0;
+
+        scrollable::scroll_to(
+            self.scrollable.clone(),
+            scrollable::AbsoluteOffset {
+                x: viewport.absolute_offset().x,
+                y: y.max(0.0),
+            },
+        )
+    }
+
+    /// Scrolls the active buffer to keep its cursor visible.
+    fn scroll_to_cursor(&self, settings: &Settings) -> Task<Message> {
+        let (Some(buffer), Some(viewport)) = (self.buffers.get(self.active), self.viewport) else {
+            return Task::none();
+        };
+
+        let line_height = settings.line_height();
+        let top = buffer.content.cursor_position().0 as f32 * line_height;
+        let offset = viewport.absolute_offset().y;
+        let height = viewport.bounds().height;
+
+        let y = if top < offset {
+            top
+        } else if top + line_height > offset + height {
+            top + line_height - height
+        } else {
+            return Task::none();
+        };
+
+        scrollable::scroll_to(
+            self.scrollable.clone(),
+            scrollable::AbsoluteOffset {
+                x: viewport.absolute_offset().x,
+                y,
+            },
+        )
+    }
+}
+
+impl Buffer {
+    /// Recomputes the changes since `HEAD`, looks for conflicts and finds
+    /// the lines that can be folded.
+    fn refresh(&mut self) {
+        let text = self.text();
+
+        self.hunks = match &self.head {
+            Some(head) => diff::hunks(head, &text),
+            None => Vec::new(),
+        };
+
+        self.conflicted = crate::merge::has_conflicts(&text);
+        self.foldable = fold::ranges(&text, self.extension());
+    }
+
+    /// Replaces the text with its formatted version, changing only the lines
+    /// that differ so that the cursors and folds elsewhere stay in place.
+    fn reformat(&mut self, formatted: &str) {
+        let hunks = diff::hunks(&self.text(), formatted);
+
+        if hunks.is_empty() {
+            return;
+        }
+
+        // The lines of hunks are lines of the full text.
+        let spans = self.spans();
+        self.unfold_all();
+
+        self.undo.record(&self.content, &self.folds, None);
+
+        let moved = |(line, index): cursors::Position| {
+            let mut shift = 0;
+
+            for hunk in &hunks {
+                if line < hunk.old.start {
+                    break;
+                }
+
+                if line < hunk.old.end {
+                    let offset = (line - hunk.old.start).min(hunk.new.len().saturating_sub(1));
+
+                    return (hunk.new.start + offset, index);
+                }
+
+                shift += hunk.new.len() as isize - hunk.old.len() as isize;
+            }
+
+            (line.saturating_add_signed(shift), index)
+        };
+
+        let primary = cursors::primary(&mut self.content);
+
+        for hunk in hunks.iter().rev() {
+            replace_lines(
+                &mut self.content,
+                hunk.old.clone(),
+                diff::lines(formatted, hunk.new.clone()),
+            );
+        }
+
+        for cursor in &mut self.cursors {
+            cursor.anchor = moved(cursor.anchor);
+            cursor.head = moved(cursor.head);
+        }
+
+        cursors::select(
+            &mut self.content,
+            cursors::Cursor {
+                anchor: moved(primary.anchor),
+                head: moved(primary.head),
+            },
+        );
+
+        self.is_dirty = true;
+        self.refresh();
+        self.restore(&spans);
+    }
+
+    /// The extension of the file, telling its language.
+    fn extension(&self) -> &str {
+        self.path
+            .extension()
+            .and_then(ffi::OsStr::to_str)
+            .unwrap_or("")
+    }
+
+    /// Whether the buffer holds text in its content, rather than a file
+    /// shown read-only.
+    fn is_editable(&self) -> bool {
+        self.document.is_none() && self.hex.is_none()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, kind) => write!(f, "{}: {kind}", path.display()),
+            Error::Encoding(path, error) => write!(f, "{}: {error}", path.display()),
+        }
     }
 }
 
 pub fn title() -> &'static str {
     "Editor"
 }
+
+fn is_markdown(extension: &str) -> bool {
+    matches!(extension, "md" | "markdown")
+}
+
+/// Replaces the given lines of the content with some text, as an edit.
+fn replace_lines(content: &mut text_editor::Content, lines: Range<usize>, text: &str) {
+    // The content has no trailing empty line, which its text adds back.
+    if lines.start >= content.line_count() {
+        content.perform(text_editor::Action::Move(Motion::DocumentEnd));
+
+        if !text.is_empty() {
+            paste(content, format!("\n{}", text.trim_end_matches('\n')));
+        }
+
+        return;
+    }
+
+    content.perform(text_editor::Action::Move(Motion::DocumentStart));
+
+    for _ in 0..lines.start {
+        content.perform(text_editor::Action::Move(Motion::Down));
+    }
+
+    if lines.end < content.line_count() {
+        for _ in lines {
+            content.perform(text_editor::Action::Select(Motion::Down));
+        }
+
+        paste(content, text.to_owned());
+    } else {
+        if text.is_empty() && lines.start > 0 {
+            content.perform(text_editor::Action::Move(Motion::Left));
+        }
+
+        content.perform(text_editor::Action::Select(Motion::DocumentEnd));
+
+        paste(content, text.trim_end_matches('\n').to_owned());
+    }
+}
+
+/// The start of the word before the cursor of a content, and the word.
+fn word_before(content: &text_editor::Content) -> (cursors::Position, String) {
+    let (line, index) = content.cursor_position();
+    let text = content
+        .line(line)
+        .map(|text| text.to_string())
+        .unwrap_or_default();
+    let before = &text[..index.min(text.len())];
+
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| completion::is_word(*c))
+        .last()
+        .map_or(before.len(), |(start, _)| start);
+
+    ((line, start), before[start..].to_owned())
+}
+
+fn paste(content: &mut text_editor::Content, text: String) {
+    let edit = if text.is_empty() {
+        text_editor::Edit::Delete
+    } else {
+        text_editor::Edit::Paste(Arc::new(text))
+    };
+
+    if content.selection().is_some() || !matches!(edit, text_editor::Edit::Delete) {
+        content.perform(text_editor::Action::Edit(edit));
+    }
+}
+
+fn load_head(path: PathBuf) -> Task<Message> {
+    Task::perform(git::head(path.clone()), move |result| {
+        Message::HeadLoaded(path.clone(), result)
+    })
+}
+
+/// Saves a buffer if it is dirty, unformatted, unless its file was not
+/// decoded exactly and saving must be confirmed.
+fn save(buffer: &Buffer) -> Option<Task<Message>> {
+    (buffer.is_dirty && buffer.is_editable() && !buffer.format.lossy).then(|| {
+        Task::perform(
+            save_file(buffer.path.clone(), buffer.text(), buffer.format),
+            Message::Saved,
+        )
+    })
+}
+
+/// Pipes the text of a buffer through a formatter, to save it afterwards in
+/// the given format if asked to.
+fn run_formatter(
+    buffer: &Buffer,
+    formatter: settings::Command,
+    save: Option<encoding::Format>,
+) -> Task<Message> {
+    let path = buffer.path.clone();
+    let text = buffer.text();
+
+    Task::perform(
+        format::format(formatter, path.clone(), text.clone()),
+        move |result| Message::Formatted(path.clone(), text.clone(), result, save),
+    )
+}
+
+fn load_blame(buffer: &Buffer) -> Task<Message> {
+    let path = buffer.path.clone();
+
+    Task::perform(
+        git::blame(path.clone(), buffer.text(), buffer.format),
+        move |result| Message::BlameLoaded(path.clone(), result),
+    )
+}
+
+/// Loads a file, in a rope when it is larger than the given size.
+async fn load_file(path: PathBuf, large: u64) -> Result<(PathBuf, Contents), Error> {
+    let io_error = |path: &PathBuf, error: io::Error| Error::Io(path.clone(), error.kind());
+
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .map_err(|error| io_error(&path, error))?;
+
+    if metadata.len() <= large {
+        let bytes = tokio::fs::read(&path)
+            .await
+            .map_err(|error| io_error(&path, error))?;
+
+        if encoding::is_binary(&bytes) {
+            return Ok((path, Contents::Binary(Arc::new(bytes))));
+        }
+
+        let (contents, format) = encoding::decode(&bytes);
+
+        return Ok((path, Contents::Text(Arc::new(contents), format)));
+    }
+
+    let file = path.clone();
+
+    let binary = tokio::task::spawn_blocking(move || {
+        let mut start = Vec::new();
+
+        std::fs::File::open(&file)
+            .and_then(|file| file.take(encoding::SAMPLE as u64).read_to_end(&mut start))
+            .map(|_| encoding::is_binary(&start))
+    })
+    .await
+    .unwrap_or_else(|error| Err(io::Error::other(error)))
+    .map_err(|error| io_error(&path, error))?;
+
+    if binary {
+        let bytes = tokio::fs::read(&path)
+            .await
+            .map_err(|error| io_error(&path, error))?;
+
+        return Ok((path, Contents::Binary(Arc::new(bytes))));
+    }
+
+    let file = path.clone();
+
+    let rope = tokio::task::spawn_blocking(move || {
+        match std::fs::File::open(&file)
+            .and_then(|file| Rope::from_reader(io::BufReader::new(file)))
+        {
+            Ok(rope) => Ok(rope),
+            // Large logs are not always valid UTF-8.
+            Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                std::fs::read(&file).map(|bytes| Rope::from_str(&String::from_utf8_lossy(&bytes)))
+            }
+            Err(error) => Err(error),
+        }
+    })
+    .await
+    .unwrap_or_else(|error| Err(io::Error::other(error)))
+    .map_err(|error| io_error(&path, error))?;
+
+    Ok((path, Contents::Large(rope)))
+}
+
+/// Saves some text to a file, stored in the given format.
+async fn save_file(
+    path: PathBuf,
+    contents: String,
+    format: encoding::Format,
+) -> Result<(PathBuf, String), Error> {
+    let bytes = encoding::encode(&contents, format)
+        .map_err(|error| Error::Encoding(path.clone(), error))?;
+
+    tokio::fs::write(&path, bytes)
+        .await
+        .map_err(|error| Error::Io(path.clone(), error.kind()))?;
+
+    Ok((path, contents))
+}
@@ -0,0 +1,64 @@
+use iced::widget::{button, column, text};
+use iced::{Element, Length};
+
+use crate::git;
+use crate::panes::style;
+use crate::settings::Settings;
+
+use super::{Buffer, Message};
+
+/// The width of the annotations.
+const WIDTH: f32 = 240.0;
+
+/// Shows the commit that last changed every group of lines, next to them.
+/// Folded lines take no room.
+pub fn view<'a>(
+    buffer: &Buffer,
+    annotations: &'a [git::Annotation],
+    settings: &'a Settings,
+) -> Element<'a, Message> {
+    let line_height = settings.line_height();
+
+    let lines = annotations.iter().filter_map(|annotation| {
+        let lines =
+            buffer.visible_line(annotation.lines.end) - buffer.visible_line(annotation.lines.start);
+
+        (lines > 0).then_some((annotation, lines))
+    });
+
+    column(lines.map(|(annotation, lines)| {
+        let height = lines as f32 * line_height;
+
+        let label = match &annotation.commit {
+            Some(commit) => format!(
+                "{} {} {}",
+                commit.short_id(),
+                commit.time.date(),
+                commit.author
+            ),
+            None => String::from("Not committed yet"),
+        };
+
+        let summary = text(label)
+            .font(settings.font())
+            .size(settings.text_size * 0.85)
+            .line_height(iced::Pixels(line_height))
+            .wrapping(text::Wrapping::None);
+
+        button(summary)
+            .width(WIDTH)
+            .height(Length::Fixed(height))
+            .padding([0, 4])
+            .clip(true)
+            .style(style::annotation)
+            .on_press_maybe(
+                annotation
+                    .commit
+                    .as_ref()
+                    .map(|commit| Message::ShowCommit(commit.id.clone())),
+            )
+            .into()
+    }))
+    .width(WIDTH)
+    .into()
+}
@@ -0,0 +1,118 @@
+use iced::widget::text_editor;
+
+use super::cursors::Position;
+
+/// The brackets, opening and closing.
+const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// The most lines searched for a matching bracket.
+const LIMIT: usize = 2000;
+
+pub fn closing(bracket: char) -> Option<char> {
+    PAIRS
+        .iter()
+        .find(|(open, _)| *open == bracket)
+        .map(|(_, close)| *close)
+}
+
+pub fn is_closing(bracket: char) -> bool {
+    PAIRS.iter().any(|(_, close)| *close == bracket)
+}
+
+/// The quotes closed as they are typed in a language.
+pub fn quotes(extension: &str) -> &'static [char] {
+    match extension {
+        // Single quotes also start lifetimes.
+        "rs" => &['"'],
+        "js" | "jsx" | "mjs" | "ts" | "tsx" | "go" | "sh" | "bash" | "zsh" => &['"', '\'', '`'],
+        "md" | "txt" | "" => &[],
+        _ => &['"', '\''],
+    }
+}
+
+/// The bracket next to the cursor of a content and the one matching it,
+/// looking after the cursor first.
+pub fn matching(content: &text_editor::Content) -> Option<(Position, Position)> {
+    let (line, index) = content.cursor_position();
+    let text = content.line(line)?;
+
+    let after = text[index..].chars().next().map(|c| (index, c));
+    let before = text[..index].char_indices().next_back();
+
+    [after, before]
+        .into_iter()
+        .flatten()
+        .find_map(|(index, bracket)| {
+            let found = if let Some(close) = closing(bracket) {
+                forward(content, (line, index), bracket, close)
+            } else {
+                let (open, _) = PAIRS.iter().find(|(_, close)| *close == bracket)?;
+
+                backward(content, (line, index), *open, bracket)
+            };
+
+            found.map(|other| ((line, index), other))
+        })
+}
+
+/// The closing bracket matching the opening one at a position.
+fn forward(
+    content: &text_editor::Content,
+    (line, index): Position,
+    open: char,
+    close: char,
+) -> Option<Position> {
+    let mut depth = 0;
+
+    for current in line..content.line_count().min(line + LIMIT) {
+        let text = content.line(current)?;
+        let start = if current == line { index } else { 0 };
+
+        for (offset, c) in text[start..].char_indices() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some((current, start + offset));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// The opening bracket matching the closing one at a position.
+fn backward(
+    content: &text_editor::Content,
+    (line, index): Position,
+    open: char,
+    close: char,
+) -> Option<Position> {
+    let mut depth = 0;
+
+    for current in (line.saturating_sub(LIMIT)..=line).rev() {
+        let text = content.line(current)?;
+        let end = if current == line {
+            index + close.len_utf8()
+        } else {
+            text.len()
+        };
+
+        for (offset, c) in text[..end].char_indices().rev() {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some((current, offset));
+                }
+            }
+        }
+    }
+
+    None
+}
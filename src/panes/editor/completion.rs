@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use iced::widget::{Space, button, column, container, row, text};
+use iced::{Element, Length, Padding};
+
+use crate::panes::{project, style};
+use crate::settings::Settings;
+
+use super::{Message, cursors};
+
+/// The characters typed before completions are offered unasked.
+pub const MIN_PREFIX: usize = 2;
+
+/// The items shown at once.
+const SHOWN: usize = 8;
+
+/// The shortest words completed to.
+const MIN_WORD: usize = 3;
+
+/// The project files read for words, at most, and their size limit.
+const MAX_FILES: usize = 1000;
+const MAX_FILE_SIZE: u64 = 128 * 1024;
+
+/// How many times words occur in some files.
+pub type Words = HashMap<String, usize>;
+
+/// Where a completion comes from.
+///
+/// The items of every source are merged into the same list, so that a
+/// language server can be added as a source of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    /// The words of the open files.
+    Buffers,
+    /// The words of the files of the project.
+    Project,
+}
+
+/// Something a prefix can be completed to.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub label: String,
+    pub source: Source,
+    /// How often the candidate occurs, ranking frequent ones first.
+    pub count: usize,
+}
+
+/// The completions offered for the word before the cursor.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    /// Where the completed word starts.
+    pub start: cursors::Position,
+    pub prefix: String,
+    /// The candidates matching the prefix, best first.
+    pub items: Vec<Candidate>,
+    pub selected: usize,
+    candidates: Vec<Candidate>,
+}
+
+impl Completion {
+    /// Offers the candidates, merged by label, matching the word at a
+    /// position. Returns `None` if none do.
+    pub fn new(
+        start: cursors::Position,
+        prefix: String,
+        candidates: impl IntoIterator<Item = Candidate>,
+    ) -> Option<Self> {
+        let mut merged: HashMap<String, Candidate> = HashMap::new();
+
+        for candidate in candidates {
+            match merged.get_mut(&candidate.label) {
+                Some(existing) => {
+                    existing.count += candidate.count;
+                    existing.source = existing.source.min(candidate.source);
+                }
+                None => {
+                    merged.insert(candidate.label.clone(), candidate);
+                }
+            }
+        }
+
+        let mut completion = Self {
+            start,
+            prefix: String::new(),
+            items: Vec::new(),
+            selected: 0,
+            candidates: merged.into_values().collect(),
+        };
+
+        completion.filter(prefix).then_some(completion)
+    }
+
+    /// Ranks the candidates again for a new prefix, returning whether any
+    /// matches.
+    pub fn filter(&mut self, prefix: String) -> bool {
+        let mut scored: Vec<_> = self
+            .candidates
+            .iter()
+            .filter(|candidate| candidate.label != prefix)
+            .filter_map(|candidate| {
+                let score = score(&prefix, &candidate.label)?;
+                let frequency = (candidate.count as f64).ln_1p() * 4.0;
+
+                Some((score as f64 + frequency, candidate))
+            })
+            .collect();
+
+        scored.sort_by(|(a, a_candidate), (b, b_candidate)| {
+            b.total_cmp(a)
+                .then_with(|| a_candidate.source.cmp(&b_candidate.source))
+                .then_with(|| a_candidate.label.len().cmp(&b_candidate.label.len()))
+                .then_with(|| a_candidate.label.cmp(&b_candidate.label))
+        });
+
+        self.items = scored
+            .into_iter()
+            .map(|(_, candidate)| candidate.clone())
+            .collect();
+        self.prefix = prefix;
+        self.selected = 0;
+
+        !self.items.is_empty()
+    }
+
+    /// Moves the selection by some items, wrapping around.
+    pub fn select(&mut self, by: isize) {
+        let count = self.items.len() as isize;
+
+        if count > 0 {
+            self.selected = (self.selected as isize + by).rem_euclid(count) as usize;
+        }
+    }
+
+    /// The list of items, to be stacked over the text editor below the word
+    /// completed.
+    pub fn view<'a>(&'a self, line: &str, settings: &'a Settings) -> Element<'a, Message> {
+        let (start_line, index) = self.start;
+        let left =
+            cursors::columns(&line[..index.min(line.len())]) as f32 * cursors::char_width(settings);
+        let top = (start_line + 1) as f32 * settings.line_height();
+
+        // The selected item is kept in view.
+        let first = self.selected.saturating_sub(SHOWN - 1);
+
+        let items = column(self.items.iter().enumerate().skip(first).take(SHOWN).map(
+            |(index, item)| {
+                let source = match item.source {
+                    Source::Buffers => "open files",
+                    Source::Project => "project",
+                };
+
+                button(
+                    row![
+                        text(&item.label)
+                            .font(settings.font())
+                            .size(settings.text_size),
+                        Space::with_width(Length::Fill),
+                        text(source).size(11),
+                    ]
+                    .spacing(12)
+                    .align_y(iced::Alignment::Center),
+                )
+                .width(Length::Fill)
+                .padding([1, 6])
+                .style(move |theme, status| style::entry(theme, status, index == self.selected))
+                .on_press(Message::CompletionAccepted(index))
+                .into()
+            },
+        ))
+        .width(320);
+
+        container(container(items).padding(2).style(style::popup))
+            .padding(Padding {
+                top,
+                left,
+                ..Padding::ZERO
+            })
+            .into()
+    }
+}
+
+/// Scores how well a pattern fuzzily matches a word, if it does at all.
+///
+/// The characters of the pattern must appear in order, ignoring case, the
+/// first one starting the word or a part of it. Matches at the start of
+/// parts, and runs of matches, score higher.
+pub fn score(pattern: &str, word: &str) -> Option<i64> {
+    let word: Vec<char> = word.chars().collect();
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut position = 0;
+
+    for (index, wanted) in pattern.chars().enumerate() {
+        let found = (position..word.len()).find(|&at| {
+            word[at].to_lowercase().eq(wanted.to_lowercase())
+                && (index > 0 || starts_part(&word, at))
+        })?;
+
+        score += match previous {
+            Some(previous) if previous + 1 == found => 8,
+            _ if starts_part(&word, found) => 6,
+            _ => 0,
+        };
+
+        if found == 0 {
+            score += 12;
+        }
+
+        if word[found] == wanted {
+            score += 1;
+        }
+
+        if let Some(previous) = previous {
+            score -= (found - previous - 1) as i64;
+        }
+
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    // Shorter words are closer to what was typed.
+    Some(score - (word.len() - position) as i64 / 4)
+}
+
+/// Whether a character starts a word or a part of it, after an underscore
+/// or as the capital of a camel case word.
+fn starts_part(word: &[char], at: usize) -> bool {
+    at == 0
+        || !is_word(word[at - 1])
+        || word[at - 1] == '_' && word[at] != '_'
+        || word[at - 1].is_lowercase() && word[at].is_uppercase()
+}
+
+pub fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Counts the words of a text, skipping short ones and numbers.
+pub fn count(text: &str, words: &mut Words) {
+    for word in text.split(|c: char| !is_word(c)) {
+        if word.chars().count() >= MIN_WORD && !word.starts_with(|c: char| c.is_ascii_digit()) {
+            *words.entry(word.to_owned()).or_default() += 1;
+        }
+    }
+}
+
+/// Counts the words of the text files of a project, up to a limit.
+pub async fn index(root: PathBuf, excludes: Vec<String>) -> Arc<Words> {
+    tokio::task::spawn_blocking(move || {
+        let mut words = Words::new();
+        let mut files = 0;
+        let mut directories = vec![root];
+
+        while files < MAX_FILES
+            && let Some(directory) = directories.pop()
+        {
+            let Ok(entries) = std::fs::read_dir(&directory) else {
+                continue;
+            };
+
+            for entry in entries.filter_map(Result::ok) {
+                if files == MAX_FILES {
+                    break;
+                }
+
+                let name = entry.file_name().to_string_lossy().into_owned();
+
+                if name.starts_with('.')
+                    || excludes.iter().any(|pattern| project::glob(pattern, &name))
+                {
+                    continue;
+                }
+
+                let Ok(kind) = entry.file_type() else {
+                    continue;
+                };
+
+                if kind.is_dir() {
+                    directories.push(entry.path());
+                } else if kind.is_file()
+                    && let Some(text) = read(&entry.path())
+                {
+                    count(&text, &mut words);
+                    files += 1;
+                }
+            }
+        }
+
+        Arc::new(words)
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Reads a file if it is small and text.
+fn read(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+
+    if metadata.len() > MAX_FILE_SIZE {
+        return None;
+    }
+
+    let bytes = std::fs::read(path).ok()?;
+
+    if crate::encoding::is_binary(&bytes) {
+        return None;
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_requires_the_pattern_in_order() {
+        assert!(score("fb", "foo_bar").is_some());
+        assert!(score("bf", "foo_bar").is_none());
+        assert!(score("xyz", "foo_bar").is_none());
+    }
+
+    #[test]
+    fn score_starts_at_a_part() {
+        assert!(score("bar", "foo_bar").is_some());
+        assert!(score("Bar", "fooBar").is_some());
+        assert!(score("oo", "foo_bar").is_none());
+    }
+
+    #[test]
+    fn score_ranks_closer_matches_higher() {
+        // A prefix over the start of a later part.
+        assert!(score("bu", "buffer") > score("bu", "open_buffer"));
+        // A run over scattered characters.
+        assert!(score("buf", "buffer") > score("buf", "bulk_fetch"));
+        // The case as typed.
+        assert!(score("Buf", "Buffer") > score("Buf", "buffer"));
+        // A shorter word.
+        assert!(score("buf", "buffer") > score("buf", "buffers_of_the_editor"));
+    }
+
+    #[test]
+    fn count_skips_short_words_and_numbers() {
+        let mut words = Words::new();
+
+        count("if buffer = buffer_2 + 42 + 3rd; buffer", &mut words);
+
+        assert_eq!(words.get("buffer"), Some(&2));
+        assert_eq!(words.get("buffer_2"), Some(&1));
+        assert!(!words.contains_key("if"));
+        assert!(!words.contains_key("3rd"));
+    }
+}
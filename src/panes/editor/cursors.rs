@@ -0,0 +1,406 @@
+use iced::advanced::text::{self as core_text, Paragraph as _};
+use iced::widget::text_editor::{self, Motion};
+use iced::widget::{Space, container, stack};
+use iced::{Color, Element, Padding, Size, alignment};
+
+use crate::settings::Settings;
+
+use super::Message;
+
+/// The columns of a tab, as laid out by the text editor.
+pub const TAB_WIDTH: usize = 8;
+
+/// A line, and a byte index within it.
+pub type Position = (usize, usize);
+
+/// A cursor besides the one of the content, and its selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    /// Where the selection starts, or the head when nothing is selected.
+    pub anchor: Position,
+    pub head: Position,
+}
+
+impl Cursor {
+    pub fn at(position: Position) -> Self {
+        Self {
+            anchor: position,
+            head: position,
+        }
+    }
+
+    /// The ends of the selection, in order.
+    pub fn range(&self) -> (Position, Position) {
+        (self.anchor.min(self.head), self.anchor.max(self.head))
+    }
+}
+
+/// Performs an action at every cursor, the one of the content included.
+pub fn perform(
+    content: &mut text_editor::Content,
+    cursors: &mut Vec<Cursor>,
+    action: text_editor::Action,
+) {
+    if action.is_edit() {
+        edit(content, cursors, |content| content.perform(action.clone()));
+
+        return;
+    }
+
+    if cursors.is_empty() {
+        content.perform(action);
+
+        return;
+    }
+
+    let mut all = std::mem::take(cursors);
+    all.push(primary(content));
+
+    for cursor in &mut all {
+        select(content, *cursor);
+        content.perform(action.clone());
+
+        *cursor = match action {
+            text_editor::Action::Select(_) => Cursor {
+                anchor: cursor.anchor,
+                head: content.cursor_position(),
+            },
+            _ => Cursor::at(content.cursor_position()),
+        };
+    }
+
+    keep(content, cursors, all);
+}
+
+/// Edits the content at every cursor, with its selection made the one of the
+/// content.
+///
+/// Edits are applied from the end of the text backwards, so that the
+/// positions of the cursors left to edit stay valid.
+pub fn edit(
+    content: &mut text_editor::Content,
+    cursors: &mut Vec<Cursor>,
+    mut edit: impl FnMut(&mut text_editor::Content),
+) {
+    if cursors.is_empty() {
+        edit(content);
+
+        return;
+    }
+
+    let mut all = std::mem::take(cursors);
+    all.push(primary(content));
+
+    let mut order: Vec<usize> = (0..all.len()).collect();
+    order.sort_by_key(|&index| std::cmp::Reverse(all[index].range().0));
+
+    // The distance of every edited cursor to the end of the text, which
+    // edits before it do not change.
+    let mut remaining = vec![0; all.len()];
+
+    for &index in &order {
+        select(content, all[index]);
+        edit(content);
+
+        remaining[index] = len(content) - offset(content, content.cursor_position());
+    }
+
+    let len = len(content);
+
+    for (cursor, remaining) in all.iter_mut().zip(remaining) {
+        *cursor = Cursor::at(position(content, len - remaining));
+    }
+
+    keep(content, cursors, all);
+}
+
+/// Keeps the distinct cursors after an action, the last one being the one of
+/// the content.
+fn keep(content: &mut text_editor::Content, cursors: &mut Vec<Cursor>, mut all: Vec<Cursor>) {
+    let Some(current) = all.pop() else {
+        return;
+    };
+
+    for cursor in all {
+        if cursor.head != current.head && !cursors.contains(&cursor) {
+            cursors.push(cursor);
+        }
+    }
+
+    select(content, current);
+}
+
+/// Selects the word at the cursor of the content or, if a text is already
+/// selected, adds a cursor selecting its next occurrence.
+pub fn add_next(content: &mut text_editor::Content, cursors: &mut Vec<Cursor>) {
+    let current = primary(content);
+
+    if current.anchor == current.head {
+        select(content, current);
+        content.perform(text_editor::Action::SelectWord);
+
+        return;
+    }
+
+    let text = content.text();
+    let (start, end) = current.range();
+    let (start, end) = (offset(content, start), offset(content, end));
+    let needle = &text[start..end];
+
+    let taken = |found: usize| {
+        let found = position(content, found);
+
+        found == current.range().0 || cursors.iter().any(|cursor| cursor.range().0 == found)
+    };
+
+    // The next occurrence not selected yet, wrapping around.
+    let next = text[end..]
+        .match_indices(needle)
+        .map(|(index, _)| end + index)
+        .chain(text[..start].match_indices(needle).map(|(index, _)| index))
+        .find(|&found| !taken(found));
+
+    let Some(found) = next else {
+        select(content, current);
+
+        return;
+    };
+
+    cursors.push(current);
+
+    select(
+        content,
+        Cursor {
+            anchor: position(content, found),
+            head: position(content, found + needle.len()),
+        },
+    );
+}
+
+/// Turns the selection of the content, started at an anchor, into a
+/// rectangle of one selection per line.
+pub fn select_column(
+    content: &mut text_editor::Content,
+    cursors: &mut Vec<Cursor>,
+    anchor: Position,
+) {
+    let head = content.cursor_position();
+
+    let column = |(line, index): Position| {
+        content
+            .line(line)
+            .map_or(0, |text| text[..index.min(text.len())].chars().count())
+    };
+
+    let (from, to) = (column(anchor), column(head));
+
+    let block = |line: usize| {
+        let byte = |column: usize| {
+            content.line(line).map_or(0, |text| {
+                text.char_indices()
+                    .nth(column)
+                    .map_or(text.len(), |(index, _)| index)
+            })
+        };
+
+        Cursor {
+            anchor: (line, byte(from)),
+            head: (line, byte(to)),
+        }
+    };
+
+    cursors.clear();
+    cursors.extend(
+        (anchor.0.min(head.0)..=anchor.0.max(head.0))
+            .filter(|&line| line != head.0)
+            .map(block),
+    );
+
+    select(content, block(head.0));
+}
+
+/// Draws the cursors besides the one of the content, and their selections,
+/// to be stacked over the text editor.
+pub fn view<'a>(
+    content: &text_editor::Content,
+    cursors: &[Cursor],
+    settings: &Settings,
+    color: Color,
+) -> Element<'a, Message> {
+    let line_height = settings.line_height();
+    let width = char_width(settings);
+
+    let x = |(line, index): Position| {
+        content.line(line).map_or(0.0, |text| {
+            columns(&text[..index.min(text.len())]) as f32 * width
+        })
+    };
+
+    let rectangle = |line: usize, left: f32, right: f32, color: Color| -> Element<'a, Message> {
+        container(
+            container(Space::new(right - left, line_height)).style(move |_| container::Style {
+                background: Some(color.into()),
+                ..Default::default()
+            }),
+        )
+        .padding(Padding {
+            top: line as f32 * line_height,
+            left,
+            ..Padding::ZERO
+        })
+        .into()
+    };
+
+    let mut layers = Vec::new();
+
+    for cursor in cursors {
+        let (start, end) = cursor.range();
+        let selected = if start == end {
+            0..0
+        } else {
+            start.0..end.0 + 1
+        };
+
+        for line in selected {
+            let left = if line == start.0 { x(start) } else { 0.0 };
+            let right = if line == end.0 {
+                x(end)
+            } else {
+                x((line, usize::MAX)) + width
+            };
+
+            layers.push(rectangle(line, left, right, Color { a: 0.3, ..color }));
+        }
+
+        let left = x(cursor.head);
+
+        layers.push(rectangle(cursor.head.0, left, left + 2.0, color));
+    }
+
+    stack(layers).into()
+}
+
+/// Reads the cursor of the content, collapsing its selection.
+pub fn primary(content: &mut text_editor::Content) -> Cursor {
+    let head = content.cursor_position();
+
+    let Some(selection) = content.selection() else {
+        return Cursor::at(head);
+    };
+
+    content.perform(text_editor::Action::Move(Motion::Left));
+
+    let start = content.cursor_position();
+    let end = position(content, offset(content, start) + selection.len());
+
+    // Words and lines are selected around the cursor, which is then at
+    // neither end.
+    if head == start {
+        Cursor { anchor: end, head }
+    } else {
+        Cursor {
+            anchor: start,
+            head: end,
+        }
+    }
+}
+
+/// Moves the cursor of the content, and selects from its anchor to its head.
+pub fn select(content: &mut text_editor::Content, cursor: Cursor) {
+    place(content, cursor.anchor);
+
+    let (start, end) = cursor.range();
+    let (start, end) = (offset(content, start), offset(content, end));
+
+    let chars = content.text()[start..end].chars().count();
+
+    let motion = if cursor.head < cursor.anchor {
+        Motion::Left
+    } else {
+        Motion::Right
+    };
+
+    for _ in 0..chars {
+        content.perform(text_editor::Action::Select(motion));
+    }
+}
+
+/// Moves the cursor of the content to a position.
+pub fn place(content: &mut text_editor::Content, (line, index): Position) {
+    content.perform(text_editor::Action::Move(Motion::DocumentStart));
+
+    for _ in 0..line.min(content.line_count().saturating_sub(1)) {
+        content.perform(text_editor::Action::Move(Motion::Down));
+    }
+
+    let chars = content
+        .line(line)
+        .map_or(0, |text| text[..floor(&text, index)].chars().count());
+
+    for _ in 0..chars {
+        content.perform(text_editor::Action::Move(Motion::Right));
+    }
+}
+
+/// The byte offset of a position in the text of a content.
+pub fn offset(content: &text_editor::Content, (line, index): Position) -> usize {
+    let before: usize = content.lines().take(line).map(|text| text.len() + 1).sum();
+
+    before + content.line(line).map_or(0, |text| floor(&text, index))
+}
+
+/// The position of a byte offset in the text of a content.
+pub fn position(content: &text_editor::Content, mut offset: usize) -> Position {
+    for (line, text) in content.lines().enumerate() {
+        if offset <= text.len() {
+            return (line, floor(&text, offset));
+        }
+
+        offset -= text.len() + 1;
+    }
+
+    let last = content.line_count().saturating_sub(1);
+
+    (last, content.line(last).map_or(0, |text| text.len()))
+}
+
+fn len(content: &text_editor::Content) -> usize {
+    content.lines().map(|text| text.len() + 1).sum::<usize>() - 1
+}
+
+/// The closest char boundary at or before a byte, within a text.
+fn floor(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
+
+/// The columns taken by a text, with tabs expanded.
+pub fn columns(text: &str) -> usize {
+    text.chars().fold(0, |columns, c| match c {
+        '\t' => (columns / TAB_WIDTH + 1) * TAB_WIDTH,
+        _ => columns + 1,
+    })
+}
+
+/// The advance of a character of the editor font, which is monospace.
+pub fn char_width(settings: &Settings) -> f32 {
+    let paragraph =
+        <iced::Renderer as core_text::Renderer>::Paragraph::with_text(core_text::Text {
+            content: "0000000000",
+            bounds: Size::INFINITY,
+            size: settings.text_size.into(),
+            line_height: core_text::LineHeight::Absolute(settings.line_height().into()),
+            font: settings.font(),
+            horizontal_alignment: alignment::Horizontal::Left,
+            vertical_alignment: alignment::Vertical::Top,
+            shaping: core_text::Shaping::Advanced,
+            wrapping: core_text::Wrapping::None,
+        });
+
+    paragraph.min_width() / 10.0
+}
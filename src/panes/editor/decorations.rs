@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use iced::widget::{Space, column, container, stack, text, vertical_space};
+use iced::{Element, Length, Padding, Pixels, Theme};
+
+use crate::panes::style;
+use crate::settings::Settings;
+
+use super::{Buffer, Message, brackets, cursors};
+
+/// Draws the current line, the brackets matching at the cursor, the rulers,
+/// the indent guides and the whitespace of the lines in view, to be stacked
+/// over the text editor.
+pub fn view<'a>(
+    buffer: &Buffer,
+    lines: Range<usize>,
+    settings: &'a Settings,
+) -> Element<'a, Message> {
+    let line_height = settings.line_height();
+    let width = cursors::char_width(settings);
+    let content = &buffer.content;
+
+    let mut layers = vec![rectangle(
+        0.0,
+        content.cursor_position().0 as f32 * line_height,
+        Length::Fill,
+        line_height,
+        style::current_line,
+    )];
+
+    if let Some((bracket, other)) = brackets::matching(content) {
+        for (line, index) in [bracket, other] {
+            let column = content
+                .line(line)
+                .map_or(0, |text| cursors::columns(&text[..index]));
+
+            layers.push(rectangle(
+                column as f32 * width,
+                line as f32 * line_height,
+                width,
+                line_height,
+                style::matching_bracket,
+            ));
+        }
+    }
+
+    let height = content.line_count() as f32 * line_height;
+
+    layers.extend(
+        settings
+            .rulers
+            .iter()
+            .map(|&column| rectangle(column as f32 * width, 0.0, 1, height, style::ruler)),
+    );
+
+    if settings.indent_guides {
+        for (column, runs) in guides(buffer, lines.clone(), settings.tab_width) {
+            for run in runs {
+                layers.push(rectangle(
+                    column as f32 * width,
+                    run.start as f32 * line_height,
+                    1,
+                    run.len() as f32 * line_height,
+                    style::indent_guide,
+                ));
+            }
+        }
+    }
+
+    if settings.show_whitespace {
+        let marks = lines
+            .clone()
+            .map(|line| {
+                content
+                    .line(line)
+                    .map_or_else(String::new, |text| marks(&text))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        layers.push(
+            column![
+                vertical_space().height(lines.start as f32 * line_height),
+                text(marks)
+                    .font(settings.font())
+                    .size(settings.text_size)
+                    .line_height(Pixels(line_height))
+                    .wrapping(text::Wrapping::None)
+                    .style(style::whitespace),
+            ]
+            .into(),
+        );
+    }
+
+    stack(layers).into()
+}
+
+fn rectangle<'a>(
+    x: f32,
+    y: f32,
+    width: impl Into<Length>,
+    height: f32,
+    style: fn(&Theme) -> container::Style,
+) -> Element<'a, Message> {
+    container(container(Space::new(width, height)).style(style))
+        .padding(Padding {
+            top: y,
+            left: x,
+            ..Padding::ZERO
+        })
+        .into()
+}
+
+/// The runs of lines crossed by an indent guide, by column. Blank lines take
+/// the indentation of the next line with text.
+fn guides(
+    buffer: &Buffer,
+    lines: Range<usize>,
+    tab_width: usize,
+) -> BTreeMap<usize, Vec<Range<usize>>> {
+    let content = &buffer.content;
+    let mut guides: BTreeMap<usize, Vec<Range<usize>>> = BTreeMap::new();
+    let mut open: BTreeMap<usize, usize> = BTreeMap::new();
+
+    for line in lines.clone() {
+        let columns = indentation(content, line, lines.end + 100, tab_width);
+
+        open.retain(|column, start| {
+            let keep = columns.contains(column);
+
+            if !keep {
+                guides.entry(*column).or_default().push(*start..line);
+            }
+
+            keep
+        });
+
+        for column in columns {
+            open.entry(column).or_insert(line);
+        }
+    }
+
+    for (column, start) in open {
+        guides.entry(column).or_default().push(start..lines.end);
+    }
+
+    guides
+}
+
+/// The columns of the indent guides of a line, looking ahead up to `limit`
+/// for a line with text when it is blank.
+fn indentation(
+    content: &iced::widget::text_editor::Content,
+    line: usize,
+    limit: usize,
+    tab_width: usize,
+) -> Vec<usize> {
+    let indent = (line..limit.min(content.line_count()))
+        .filter_map(|line| content.line(line).map(|text| text.to_owned()))
+        .find(|text| !text.trim().is_empty())
+        .unwrap_or_default();
+
+    let blanks = &indent[..indent.len() - indent.trim_start().len()];
+    let step = if blanks.starts_with('\t') {
+        cursors::TAB_WIDTH
+    } else {
+        tab_width.max(1)
+    };
+
+    (0..cursors::columns(blanks)).step_by(step).collect()
+}
+
+/// A line with its spaces and tabs made visible, and everything else blank.
+fn marks(line: &str) -> String {
+    let mut marks = String::new();
+    let mut column = 0;
+
+    for c in line.chars() {
+        match c {
+            ' ' => {
+                marks.push('·');
+                column += 1;
+            }
+            '\t' => {
+                let next = (column / cursors::TAB_WIDTH + 1) * cursors::TAB_WIDTH;
+
+                marks.push('→');
+                marks.extend(std::iter::repeat_n(' ', next - column - 1));
+                column = next;
+            }
+            _ => {
+                marks.push(' ');
+                column += 1;
+            }
+        }
+    }
+
+    marks.trim_end().to_owned()
+}
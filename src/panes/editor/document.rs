@@ -0,0 +1,351 @@
+use std::ops::Range;
+
+use iced::widget::{
+    button, column, container, horizontal_space, mouse_area, responsive, rich_text, row, span,
+    text, text_input, vertical_slider,
+};
+use iced::{Color, Element, Length, Task, mouse};
+use ropey::Rope;
+
+use crate::settings::{Appearance, Settings};
+
+/// The characters of a line shown at most, so that huge lines stay cheap to
+/// lay out.
+const COLUMNS: usize = 512;
+
+/// The lines scrolled by a notch of the mouse wheel.
+const WHEEL_LINES: f32 = 3.0;
+
+/// A file too large to edit, kept in a rope and shown read-only.
+///
+/// Only the lines in view are ever laid out, so opening, scrolling and
+/// searching do not depend on the size of the file.
+pub struct Document {
+    pub rope: Rope,
+    /// The first line in view.
+    pub top: usize,
+    /// The first column in view.
+    pub left: usize,
+    pub query: String,
+    /// The line and byte range of the current match, within its line.
+    pub found: Option<(usize, Range<usize>)>,
+    pub searching: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Scrolled(mouse::ScrollDelta),
+    Jumped(f64),
+    QueryEdited(String),
+    Search,
+    /// The bytes of the match found, if any.
+    Found(Option<Range<usize>>),
+}
+
+impl Document {
+    pub fn new(rope: Rope) -> Self {
+        Self {
+            rope,
+            top: 0,
+            left: 0,
+            query: String::new(),
+            found: None,
+            searching: false,
+        }
+    }
+
+    pub fn update(&mut self, message: Message, settings: &Settings) -> Task<Message> {
+        match message {
+            Message::Scrolled(delta) => {
+                let (x, y) = match delta {
+                    mouse::ScrollDelta::Lines { x, y } => (x * WHEEL_LINES, y * WHEEL_LINES),
+                    mouse::ScrollDelta::Pixels { x, y } => {
+                        let line_height = settings.line_height();
+
+                        (x / line_height, y / line_height)
+                    }
+                };
+
+                self.top = offset(self.top, -y).min(self.last_line());
+                self.left = offset(self.left, -x);
+
+                Task::none()
+            }
+            Message::Jumped(value) => {
+                self.top = self.last_line() - (value as usize).min(self.last_line());
+
+                Task::none()
+            }
+            Message::QueryEdited(query) => {
+                self.query = query;
+                self.found = None;
+
+                Task::none()
+            }
+            Message::Search => {
+                if self.query.is_empty() || self.searching {
+                    return Task::none();
+                }
+
+                self.searching = true;
+
+                // The search starts after the current match, or at the top.
+                let start = match &self.found {
+                    Some((line, range)) => self.rope.line_to_byte(*line) + range.end,
+                    None => self.rope.line_to_byte(self.top),
+                };
+
+                let rope = self.rope.clone();
+                let query = self.query.clone();
+
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            search(&rope, &query, start).map(|offset| offset..offset + query.len())
+                        })
+                        .await
+                        .ok()
+                        .flatten()
+                    },
+                    Message::Found,
+                )
+            }
+            Message::Found(found) => {
+                self.searching = false;
+
+                let Some(found) = found else {
+                    self.found = None;
+
+                    return Task::none();
+                };
+
+                let line = self.rope.byte_to_line(found.start);
+                let start = found.start - self.rope.line_to_byte(line);
+                let end = start + found.len();
+
+                self.found = Some((line, start..end));
+                self.top = line.saturating_sub(3);
+
+                // The match is kept in the visible columns.
+                let column = self.rope.line(line).byte_to_char(start);
+                let width = self.rope.line(line).byte_to_char(end) - column;
+
+                self.left = if column + width < COLUMNS {
+                    0
+                } else {
+                    column.saturating_sub(COLUMNS / 2)
+                };
+
+                Task::none()
+            }
+        }
+    }
+
+    pub fn view<'a>(
+        &'a self,
+        settings: &'a Settings,
+        appearance: &'a Appearance,
+    ) -> Element<'a, Message> {
+        let line_height = settings.line_height();
+        let highlight = Color {
+            a: 0.45,
+            ..appearance.theme.extended_palette().primary.base.color
+        };
+        let last_line = self.last_line();
+
+        let lines = responsive(move |size| {
+            let count = (size.height / line_height).ceil() as usize;
+            let end = (self.top + count).min(self.rope.len_lines());
+            let digits = self.rope.len_lines().to_string().len();
+
+            column((self.top..end).map(|index| {
+                let number = text(format!("{:>digits$}", index + 1))
+                    .font(settings.font())
+                    .size(settings.text_size)
+                    .line_height(iced::Pixels(line_height))
+                    .style(|theme: &iced::Theme| text::Style {
+                        color: Some(theme.extended_palette().background.strong.color),
+                    });
+
+                row![number, self.line(index, settings, highlight)]
+                    .spacing(10)
+                    .into()
+            }))
+            .into()
+        });
+
+        let slider = vertical_slider(
+            0.0..=last_line as f64,
+            (last_line - self.top) as f64,
+            Message::Jumped,
+        )
+        .width(8);
+
+        let search = row![
+            text_input("Search", &self.query)
+                .size(12)
+                .on_input(Message::QueryEdited)
+                .on_submit(Message::Search),
+            button(
+                text(if self.searching {
+                    "Searching…"
+                } else {
+                    "Next"
+                })
+                .size(12)
+            )
+            .padding([1, 6])
+            .on_press_maybe((!self.query.is_empty() && !self.searching).then_some(Message::Search)),
+        ]
+        .spacing(5);
+
+        let status = row![
+            text("Read-only").size(12),
+            horizontal_space(),
+            text(
+                match (&self.found, self.query.is_empty() || self.searching) {
+                    (Some((line, _)), _) => format!("Match on line {}", line + 1),
+                    (None, true) => String::new(),
+                    (None, false) => String::from("No match"),
+                }
+            )
+            .size(12),
+            text(format!(
+                "{} lines, {} bytes",
+                self.rope.len_lines(),
+                self.rope.len_bytes()
+            ))
+            .size(12),
+        ]
+        .spacing(10);
+
+        column![
+            search,
+            row![
+                mouse_area(
+                    container(lines)
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .clip(true)
+                )
+                .on_scroll(Message::Scrolled),
+                slider,
+            ]
+            .spacing(4)
+            .height(Length::Fill),
+            status,
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    /// The visible part of a line, with the current match highlighted.
+    fn line<'a>(
+        &'a self,
+        index: usize,
+        settings: &'a Settings,
+        highlight: Color,
+    ) -> Element<'a, Message> {
+        let line = self.rope.line(index);
+        let start = self.left.min(line.len_chars());
+        let end = (start + COLUMNS).min(line.len_chars());
+
+        let visible: String = line
+            .slice(start..end)
+            .chars()
+            .filter(|c| *c != '\n' && *c != '\r')
+            .collect();
+
+        let mut spans = Vec::new();
+
+        match &self.found {
+            Some((found, range)) if *found == index => {
+                // The match, in bytes of the visible part.
+                let offset = line.char_to_byte(start);
+                let match_start = range.start.saturating_sub(offset).min(visible.len());
+                let match_end = range.end.saturating_sub(offset).min(visible.len());
+
+                let (before, rest) = visible.split_at(floor(&visible, match_start));
+                let (found, after) = rest.split_at(floor(rest, match_end - before.len()));
+
+                spans.push(span(before.to_owned()));
+                spans.push(span(found.to_owned()).background(highlight));
+                spans.push(span(after.to_owned()));
+            }
+            _ => spans.push(span(visible)),
+        }
+
+        rich_text(spans)
+            .font(settings.font())
+            .size(settings.text_size)
+            .line_height(iced::Pixels(settings.line_height()))
+            .wrapping(text::Wrapping::None)
+            .into()
+    }
+
+    fn last_line(&self) -> usize {
+        self.rope.len_lines().saturating_sub(1)
+    }
+}
+
+/// Finds the byte offset of the first occurrence of a text at or after a
+/// char boundary, wrapping around to the start of the rope.
+fn search(rope: &Rope, query: &str, start: usize) -> Option<usize> {
+    find(rope, query, start.min(rope.len_bytes())).or_else(|| find(rope, query, 0))
+}
+
+/// Finds a text after a char boundary, chunk by chunk.
+fn find(rope: &Rope, query: &str, start: usize) -> Option<usize> {
+    let slice = rope.byte_slice(start..);
+
+    // The end of the previous chunks, in case a match spans several of them.
+    let mut window = String::new();
+    let mut window_start = start;
+
+    for chunk in slice.chunks() {
+        window.push_str(chunk);
+
+        if let Some(position) = window.find(query) {
+            return Some(window_start + position);
+        }
+
+        let keep = floor(&window, window.len().saturating_sub(query.len() - 1));
+
+        window_start += keep;
+        window.drain(..keep);
+    }
+
+    None
+}
+
+/// The closest char boundary at or before a byte.
+fn floor(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
+
+fn offset(value: usize, delta: f32) -> usize {
+    (value as f64 + f64::from(delta)).round().max(0.0) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_keep_the_length_of_the_query_searched() {
+        let settings = Settings::default();
+        let mut document = Document::new(Rope::from_str("one\ntwo matches\n"));
+
+        let _ = document.update(Message::QueryEdited(String::from("match")), &settings);
+        let _ = document.update(Message::Search, &settings);
+        let _ = document.update(Message::QueryEdited(String::from("m")), &settings);
+        let _ = document.update(Message::Found(Some(8..13)), &settings);
+
+        assert_eq!(document.found, Some((1, 4..9)));
+        assert!(!document.searching);
+    }
+}
@@ -0,0 +1,766 @@
+use std::ops::Range;
+
+use iced::widget::{self, button, column, text_editor, vertical_space};
+use iced::{Element, alignment};
+
+use crate::panes::style;
+
+use super::cursors::{self, Cursor, Position};
+use super::{Buffer, Message};
+
+/// The width of the fold markers.
+const WIDTH: f32 = 12.0;
+
+/// Lines taken out of the content while folded.
+#[derive(Debug, Clone)]
+pub struct Fold {
+    /// The line of the content the folded lines follow.
+    pub line: usize,
+    pub lines: Vec<String>,
+    /// The spans folded within, relative to the first folded line, to be
+    /// folded again when this one is unfolded.
+    nested: Vec<Span>,
+}
+
+/// Folded lines of the full text, and the spans folded within them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub lines: Range<usize>,
+    pub nested: Vec<Span>,
+}
+
+/// How the blocks of a language are found.
+struct Syntax {
+    comment: &'static str,
+    braces: bool,
+}
+
+impl Buffer {
+    /// The text of the buffer, folded lines included.
+    pub fn text(&self) -> String {
+        text(&self.content, &self.folds)
+    }
+
+    /// The folded spans, in lines of the full text.
+    pub fn spans(&self) -> Vec<Span> {
+        let mut hidden = 0;
+
+        self.folds
+            .iter()
+            .map(|fold| {
+                let start = fold.line + hidden + 1;
+
+                hidden += fold.lines.len();
+
+                Span {
+                    lines: start..start + fold.lines.len(),
+                    nested: moved(&fold.nested, 0, start),
+                }
+            })
+            .collect()
+    }
+
+    /// The line of the full text shown at a line of the content.
+    pub fn full_line(&self, line: usize) -> usize {
+        full_line(&self.folds, line)
+    }
+
+    /// The line of the content at which a line of the full text is shown.
+    /// Folded lines are placed after the line they follow.
+    pub fn visible_line(&self, line: usize) -> usize {
+        self.locate(line).unwrap_or_else(|header| header + 1)
+    }
+
+    /// Folds the range starting at a line of the full text, or else the
+    /// innermost one around it.
+    pub fn fold(&mut self, line: usize) {
+        let Some(range) = self
+            .foldable
+            .iter()
+            .filter(|range| range.start <= line && line < range.end)
+            .max_by_key(|range| range.start)
+            .cloned()
+        else {
+            return;
+        };
+
+        if self.locate(range.start).is_err() {
+            return;
+        }
+
+        // The spans folded within are folded again when it is unfolded.
+        let (nested, mut spans): (Vec<_>, Vec<_>) = self
+            .spans()
+            .into_iter()
+            .partition(|span| range.contains(&span.lines.start));
+
+        let end = nested
+            .iter()
+            .map(|span| span.lines.end)
+            .fold(range.end, usize::max);
+
+        spans.push(Span {
+            lines: range.start + 1..end,
+            nested,
+        });
+        spans.sort_by_key(|span| span.lines.start);
+
+        self.set(spans);
+    }
+
+    /// Unfolds the lines following a line of the full text, or else the
+    /// ones around it.
+    pub fn unfold(&mut self, line: usize) {
+        let mut spans = self.spans();
+
+        let Some(index) = spans
+            .iter()
+            .position(|span| span.lines.start == line + 1)
+            .or_else(|| spans.iter().position(|span| span.lines.contains(&line)))
+        else {
+            return;
+        };
+
+        let span = spans.remove(index);
+
+        spans.extend(span.nested);
+        spans.sort_by_key(|span| span.lines.start);
+
+        self.set(spans);
+    }
+
+    /// Folds or unfolds the lines following a line of the full text.
+    pub fn toggle_fold(&mut self, line: usize) {
+        if self.spans().iter().any(|span| span.lines.start == line + 1) {
+            self.unfold(line);
+        } else {
+            self.fold(line);
+        }
+    }
+
+    pub fn fold_all(&mut self) {
+        self.set(nest(&self.foldable));
+    }
+
+    pub fn unfold_all(&mut self) {
+        if !self.folds.is_empty() {
+            self.set(Vec::new());
+        }
+    }
+
+    /// Folds the spans of a previous session of the file, where they still
+    /// match its contents.
+    pub fn restore(&mut self, spans: &[Span]) {
+        self.set(
+            spans
+                .iter()
+                .filter(|span| {
+                    self.foldable
+                        .contains(&(span.lines.start - 1..span.lines.end))
+                })
+                .cloned()
+                .collect(),
+        );
+    }
+
+    /// Changes the content. A change reaching into folded lines is done
+    /// again with every line unfolded, so that they are never lost.
+    pub fn change(&mut self, mut change: impl FnMut(&mut text_editor::Content, &mut Vec<Cursor>)) {
+        if self.folds.is_empty() {
+            change(&mut self.content, &mut self.cursors);
+
+            return;
+        }
+
+        let before = lines(&self.content);
+        let selection = if self.content.selection().is_some() {
+            let selection = cursors::primary(&mut self.content);
+
+            cursors::select(&mut self.content, selection);
+
+            selection
+        } else {
+            Cursor::at(self.content.cursor_position())
+        };
+        let others = self.cursors.clone();
+
+        change(&mut self.content, &mut self.cursors);
+
+        if shift(&mut self.folds, &before, &lines(&self.content)) {
+            return;
+        }
+
+        self.content = content(&before);
+        self.cursors = others;
+
+        cursors::select(&mut self.content, selection);
+
+        self.unfold_all();
+
+        change(&mut self.content, &mut self.cursors);
+    }
+
+    /// Replaces the folded spans, keeping the cursors on the same text.
+    fn set(&mut self, spans: Vec<Span>) {
+        let lines = self.full_lines();
+
+        let selection = cursors::primary(&mut self.content);
+        let unfold = |(line, index): Position| (full_line(&self.folds, line), index);
+        let selection = Cursor {
+            anchor: unfold(selection.anchor),
+            head: unfold(selection.head),
+        };
+        let others: Vec<_> = self
+            .cursors
+            .iter()
+            .map(|cursor| Cursor {
+                anchor: unfold(cursor.anchor),
+                head: unfold(cursor.head),
+            })
+            .collect();
+
+        let mut visible = Vec::new();
+        let mut folds = Vec::new();
+        let mut next = 0;
+
+        for span in spans {
+            let end = span.lines.end.min(lines.len());
+
+            // The line the span follows stays in view.
+            if span.lines.start <= next || span.lines.start >= end {
+                continue;
+            }
+
+            visible.extend_from_slice(&lines[next..span.lines.start]);
+            folds.push(Fold {
+                line: visible.len() - 1,
+                lines: lines[span.lines.start..end].to_vec(),
+                nested: moved(&span.nested, span.lines.start, 0),
+            });
+
+            next = end;
+        }
+
+        visible.extend_from_slice(&lines[next..]);
+
+        self.folds = folds;
+        self.content = content(&visible);
+
+        let fold = |(line, index): Position| match self.locate(line) {
+            Ok(line) => (line, index),
+            Err(header) => (header, usize::MAX),
+        };
+        let selection = Cursor {
+            anchor: fold(selection.anchor),
+            head: fold(selection.head),
+        };
+        let others: Vec<_> = others
+            .into_iter()
+            .map(|cursor| Cursor {
+                anchor: fold(cursor.anchor),
+                head: fold(cursor.head),
+            })
+            .collect();
+
+        cursors::select(&mut self.content, selection);
+
+        self.cursors = others;
+        self.cursors.dedup();
+    }
+
+    /// The line of the content showing a line of the full text, or the one
+    /// its fold follows if it is folded.
+    fn locate(&self, line: usize) -> Result<usize, usize> {
+        let mut hidden = 0;
+
+        for fold in &self.folds {
+            let start = fold.line + hidden + 1;
+
+            if line < start {
+                break;
+            }
+
+            if line < start + fold.lines.len() {
+                return Err(fold.line);
+            }
+
+            hidden += fold.lines.len();
+        }
+
+        Ok(line - hidden)
+    }
+
+    fn full_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut folds = self.folds.iter().peekable();
+
+        for (index, line) in self.content.lines().enumerate() {
+            lines.push(line.to_owned());
+
+            if let Some(fold) = folds.next_if(|fold| fold.line == index) {
+                lines.extend_from_slice(&fold.lines);
+            }
+        }
+
+        lines
+    }
+}
+
+/// Shows a marker next to the lines starting a range that can be folded.
+pub fn view<'a>(buffer: &'a Buffer, line_height: f32) -> Element<'a, Message> {
+    let mut lines: Vec<(usize, usize, bool)> = buffer
+        .foldable
+        .iter()
+        .filter_map(|range| {
+            let line = buffer.locate(range.start).ok()?;
+
+            Some((line, range.start, false))
+        })
+        .chain(
+            buffer
+                .folds
+                .iter()
+                .map(|fold| (fold.line, buffer.full_line(fold.line), true)),
+        )
+        .collect();
+
+    // The folded marker of a line wins over its foldable one.
+    lines.sort_by_key(|&(line, _, folded)| (line, !folded));
+    lines.dedup_by_key(|(line, _, _)| *line);
+
+    let mut markers = Vec::new();
+    let mut next = 0;
+
+    for (line, start, folded) in lines {
+        if line > next {
+            markers.push(
+                vertical_space()
+                    .height((line - next) as f32 * line_height)
+                    .into(),
+            );
+        }
+
+        markers.push(
+            button(
+                widget::text(if folded { "▸" } else { "▾" })
+                    .size(line_height * 0.7)
+                    .line_height(iced::Pixels(line_height))
+                    .align_x(alignment::Horizontal::Center),
+            )
+            .width(WIDTH)
+            .height(line_height)
+            .padding(0)
+            .style(move |theme, status| style::fold_marker(theme, status, folded))
+            .on_press(Message::ToggleFold(start))
+            .into(),
+        );
+
+        next = line + 1;
+    }
+
+    column(markers).width(WIDTH).into()
+}
+
+/// The text of a content, with its folded lines.
+pub fn text(content: &text_editor::Content, folds: &[Fold]) -> String {
+    if folds.is_empty() {
+        return content.text();
+    }
+
+    let mut text = String::new();
+    let mut folds = folds.iter().peekable();
+
+    for (index, line) in content.lines().enumerate() {
+        if index > 0 {
+            text.push('\n');
+        }
+
+        text.push_str(&line);
+
+        if let Some(fold) = folds.next_if(|fold| fold.line == index) {
+            for line in &fold.lines {
+                text.push('\n');
+                text.push_str(line);
+            }
+        }
+    }
+
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+
+    text
+}
+
+/// The line of the full text shown at a line of a content.
+pub fn full_line(folds: &[Fold], line: usize) -> usize {
+    line + folds
+        .iter()
+        .take_while(|fold| fold.line < line)
+        .map(|fold| fold.lines.len())
+        .sum::<usize>()
+}
+
+/// Finds the ranges of lines that can be folded, from the line staying in
+/// view to the end of the folded lines, in order.
+///
+/// Blocks are found from the brackets of the languages using them, and from
+/// indentation otherwise. Runs of line comments can be folded too.
+///
+/// There is no syntax tree to fold from: the highlighter matches the
+/// grammars of its languages line by line, without nesting them into a
+/// tree, so the brackets are scanned instead, skipping strings and comments.
+pub fn ranges(text: &str, extension: &str) -> Vec<Range<usize>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let syntax = syntax(extension);
+
+    let mut ranges = match &syntax {
+        Some(syntax) if syntax.braces => brackets(text, syntax.comment),
+        _ => indentation(&lines),
+    };
+
+    if let Some(syntax) = &syntax {
+        ranges.extend(comments(&lines, syntax.comment));
+    }
+
+    // The largest range of every line.
+    ranges.retain(|range| range.end > range.start + 1);
+    ranges.sort_by_key(|range| (range.start, std::cmp::Reverse(range.end)));
+    ranges.dedup_by_key(|range| range.start);
+
+    ranges
+}
+
+/// How line comments start in the files with an extension, if known.
+pub fn line_comment(extension: &str) -> Option<&'static str> {
+    syntax(extension).map(|syntax| syntax.comment)
+}
+
+fn syntax(extension: &str) -> Option<Syntax> {
+    let (comment, braces) = match extension {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "java" | "kt" | "scala" | "swift"
+        | "go" | "js" | "jsx" | "mjs" | "ts" | "tsx" | "dart" | "zig" | "php" | "css" | "scss"
+        | "json" => ("//", true),
+        "py" | "sh" | "bash" | "zsh" | "toml" | "yaml" | "yml" | "rb" | "nix" | "r" | "pl" => {
+            ("#", false)
+        }
+        "lua" | "sql" | "hs" => ("--", false),
+        _ => return None,
+    };
+
+    Some(Syntax { comment, braces })
+}
+
+/// The blocks between brackets and the block comments spanning several
+/// lines, skipping strings and comments.
+fn brackets(text: &str, comment: &str) -> Vec<Range<usize>> {
+    enum State {
+        Code,
+        LineComment,
+        BlockComment(usize),
+        Text(char),
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut ranges = Vec::new();
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut state = State::Code;
+    let mut line = 0;
+    // Whether only blanks were seen on the current line.
+    let mut blank = true;
+    let mut index = 0;
+
+    while let Some(&c) = chars.get(index) {
+        let next = chars.get(index + 1).copied();
+
+        if c == '\n' {
+            line += 1;
+            blank = true;
+            index += 1;
+
+            if let State::LineComment = state {
+                state = State::Code;
+            }
+
+            continue;
+        }
+
+        match state {
+            State::Code => match c {
+                '/' if next == Some('/') && comment == "//" => state = State::LineComment,
+                '/' if next == Some('*') => {
+                    state = State::BlockComment(line);
+                    index += 1;
+                }
+                '"' | '`' => state = State::Text(c),
+                // A character, unless a lifetime.
+                '\'' if next == Some('\\') => state = State::Text(c),
+                '\'' if chars.get(index + 2) == Some(&'\'') => index += 2,
+                '{' | '[' | '(' => open.push((c, line)),
+                '}' | ']' | ')' => {
+                    let opening = match c {
+                        '}' => '{',
+                        ']' => '[',
+                        _ => '(',
+                    };
+
+                    if let Some(&(bracket, start)) = open.last()
+                        && bracket == opening
+                    {
+                        open.pop();
+
+                        // A closing line of its own stays in view.
+                        let end = if blank { line } else { line + 1 };
+
+                        ranges.push(start..end);
+                    }
+                }
+                _ => {}
+            },
+            State::LineComment => {}
+            State::BlockComment(start) => {
+                if c == '*' && next == Some('/') {
+                    state = State::Code;
+                    index += 1;
+
+                    ranges.push(start..line + 1);
+                }
+            }
+            State::Text(quote) => {
+                if c == '\\' {
+                    index += 1;
+                } else if c == quote {
+                    state = State::Code;
+                }
+            }
+        }
+
+        if !c.is_whitespace() {
+            blank = false;
+        }
+
+        index += 1;
+    }
+
+    ranges
+}
+
+/// The lines followed by more indented ones.
+fn indentation(lines: &[&str]) -> Vec<Range<usize>> {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let is_blank = |line: &str| line.trim().is_empty();
+
+    let mut ranges = Vec::new();
+
+    for (start, line) in lines.iter().enumerate() {
+        if is_blank(line) {
+            continue;
+        }
+
+        let level = indent(line);
+        let mut last = start;
+
+        for (index, line) in lines.iter().enumerate().skip(start + 1) {
+            if is_blank(line) {
+                continue;
+            }
+
+            if indent(line) <= level {
+                break;
+            }
+
+            last = index;
+        }
+
+        if last > start {
+            ranges.push(start..last + 1);
+        }
+    }
+
+    ranges
+}
+
+/// The runs of line comments.
+fn comments(lines: &[&str], comment: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let is_comment = line.trim_start().starts_with(comment);
+
+        match (start, is_comment) {
+            (None, true) => start = Some(index),
+            (Some(first), false) => {
+                ranges.push(first..index);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(first) = start {
+        ranges.push(first..lines.len());
+    }
+
+    ranges
+}
+
+/// Arranges ranges that can be folded into spans, each holding the ones
+/// within it.
+fn nest(ranges: &[Range<usize>]) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut index = 0;
+
+    while let Some(range) = ranges.get(index) {
+        let within = ranges[index + 1..]
+            .iter()
+            .take_while(|other| other.start < range.end)
+            .count();
+
+        let inner: Vec<_> = ranges[index + 1..index + 1 + within]
+            .iter()
+            .filter(|other| other.end <= range.end)
+            .cloned()
+            .collect();
+
+        spans.push(Span {
+            lines: range.start + 1..range.end,
+            nested: nest(&inner),
+        });
+
+        index += 1 + within;
+    }
+
+    spans
+}
+
+/// Moves spans from lines counted from one line to lines counted from
+/// another.
+fn moved(spans: &[Span], from: usize, to: usize) -> Vec<Span> {
+    spans
+        .iter()
+        .map(|span| Span {
+            lines: span.lines.start - from + to..span.lines.end - from + to,
+            nested: moved(&span.nested, from, to),
+        })
+        .collect()
+}
+
+/// Moves the folds after the lines of the content changed, returning whether
+/// the change left the folded lines and the lines they follow alone.
+fn shift(folds: &mut [Fold], before: &[String], after: &[String]) -> bool {
+    if before == after {
+        return true;
+    }
+
+    let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let changed = before.len() - suffix;
+    // A single line changed, without breaking or joining lines.
+    let in_place = changed == prefix + 1 && after.len() - suffix == prefix + 1;
+
+    let untouched = |line: usize| {
+        line + 1 < prefix
+            || (line >= changed && line + 1 < before.len())
+            || (in_place && line == prefix)
+    };
+
+    if !folds.iter().all(|fold| untouched(fold.line)) {
+        return false;
+    }
+
+    for fold in folds {
+        if fold.line >= changed {
+            fold.line = fold.line + after.len() - before.len();
+        }
+    }
+
+    true
+}
+
+fn lines(content: &text_editor::Content) -> Vec<String> {
+    content.lines().map(|line| line.to_owned()).collect()
+}
+
+/// A content made of the given lines.
+fn content(lines: &[String]) -> text_editor::Content {
+    // A trailing line break is dropped, which would take an empty last line.
+    text_editor::Content::with_text(&format!("{}\n", lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The bounds of the ranges that can be folded.
+    fn bounds(text: &str, extension: &str) -> Vec<(usize, usize)> {
+        ranges(text, extension)
+            .into_iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    #[test]
+    fn ranges_of_brackets() {
+        let text = "\
+fn main() {
+    let v = [
+        1,
+    ];
+    call(a,
+        b);
+}
+";
+
+        assert_eq!(bounds(text, "rs"), [(0, 6), (1, 3), (4, 6)]);
+    }
+
+    #[test]
+    fn brackets_in_strings_and_comments_are_skipped() {
+        let text = "\
+fn f() {
+    let s = \"{\";
+    // {
+    let c = '{';
+    let l: &'a str = s;
+}
+";
+
+        assert_eq!(bounds(text, "rs"), [(0, 5)]);
+    }
+
+    #[test]
+    fn ranges_of_block_comments() {
+        let text = "/*\n * a\n */\nfn f() {}\n";
+
+        assert_eq!(bounds(text, "rs"), [(0, 3)]);
+    }
+
+    #[test]
+    fn ranges_of_indentation() {
+        let text = "\
+def f():
+    if x:
+        pass
+
+    return 1
+g()
+";
+
+        assert_eq!(bounds(text, "py"), [(0, 5), (1, 3)]);
+    }
+
+    #[test]
+    fn ranges_of_line_comments() {
+        let text = "# one\n# two\nx = 1\n";
+
+        assert_eq!(bounds(text, "py"), [(0, 2)]);
+        assert_eq!(bounds(text, "txt"), []);
+    }
+}
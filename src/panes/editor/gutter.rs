@@ -0,0 +1,98 @@
+use iced::widget::{button, column, container, row, text, tooltip, vertical_space};
+use iced_aw::DropDown;
+use iced_aw::drop_down;
+
+use crate::diff;
+use crate::panes::style;
+
+use super::{Buffer, Message};
+
+/// The width of the gutter markers.
+const WIDTH: f32 = 4.0;
+
+/// The height of the markers of deleted lines, drawn between two lines.
+const DELETED: f32 = 6.0;
+
+/// Shows the changes of a buffer since `HEAD` next to its lines. Changes to
+/// folded lines are shown on the line they follow.
+pub fn view<'a>(
+    buffer: &'a Buffer,
+    expanded: Option<usize>,
+    line_height: f32,
+) -> iced::Element<'a, Message> {
+    let mut markers = Vec::new();
+    let mut y = 0.0;
+
+    for (index, hunk) in buffer.hunks.iter().enumerate() {
+        let kind = hunk.kind();
+
+        let start = buffer.visible_line(hunk.new.start);
+        let end = buffer.visible_line(hunk.new.end);
+
+        let (top, height) = match kind {
+            diff::Kind::Added | diff::Kind::Modified if end > start => (
+                start as f32 * line_height,
+                (end - start) as f32 * line_height,
+            ),
+            _ => (
+                (start as f32 * line_height - DELETED / 2.0).max(0.0),
+                DELETED,
+            ),
+        };
+
+        if top > y {
+            markers.push(vertical_space().height(top - y).into());
+        }
+
+        y = top + height;
+
+        let marker = button(vertical_space().height(height))
+            .width(WIDTH)
+            .padding(0)
+            .style(move |theme, status| style::hunk(theme, status, kind))
+            .on_press(Message::ToggleHunk(index));
+
+        let marker = tooltip(
+            marker,
+            container(text(preview(buffer, hunk)).size(12)).padding(5),
+            tooltip::Position::Right,
+        )
+        .style(style::popup);
+
+        let actions = container(
+            row![
+                button(text("Revert").size(12))
+                    .padding([2, 6])
+                    .on_press(Message::RevertHunk(index)),
+                button(text("Stage").size(12))
+                    .padding([2, 6])
+                    .on_press(Message::StageHunk(index)),
+            ]
+            .spacing(5),
+        )
+        .padding(5)
+        .style(style::popup);
+
+        markers.push(
+            DropDown::new(marker, actions, expanded == Some(index))
+                .alignment(drop_down::Alignment::End)
+                .on_dismiss(Message::ToggleHunk(index))
+                .into(),
+        );
+    }
+
+    column(markers).width(WIDTH).into()
+}
+
+/// The original lines of a hunk, or a summary of the added lines.
+fn preview(buffer: &Buffer, hunk: &diff::Hunk) -> String {
+    match (hunk.kind(), &buffer.head) {
+        (diff::Kind::Added, _) | (_, None) => match hunk.new.len() {
+            1 => String::from("1 line added"),
+            lines => format!("{lines} lines added"),
+        },
+        (_, Some(head)) => diff::lines(head, hunk.old.clone())
+            .trim_end_matches('\n')
+            .to_owned(),
+    }
+}
@@ -0,0 +1,401 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use iced::widget::text::Span;
+use iced::widget::{
+    button, column, container, horizontal_space, mouse_area, responsive, rich_text, row, span,
+    text, text_input, vertical_slider,
+};
+use iced::{Color, Element, Length, Task, mouse};
+
+use crate::settings::{Appearance, Settings};
+
+/// The bytes shown on a row.
+const WIDTH: usize = 16;
+
+/// The rows scrolled by a notch of the mouse wheel.
+const WHEEL_ROWS: f32 = 3.0;
+
+/// A binary file, shown read-only as rows of offsets, bytes in hexadecimal
+/// and their ASCII characters.
+///
+/// Only the rows in view are ever laid out, like for large documents.
+pub struct Hex {
+    pub bytes: Arc<Vec<u8>>,
+    /// The first row in view.
+    pub top: usize,
+    pub query: String,
+    /// The offset typed to go to.
+    pub offset: String,
+    /// The bytes highlighted: the current match, or the byte gone to.
+    pub selected: Option<Range<usize>>,
+    pub searching: bool,
+    /// Whether the last search or offset found nothing.
+    pub missed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Scrolled(mouse::ScrollDelta),
+    Jumped(f64),
+    QueryEdited(String),
+    Search,
+    /// The bytes of the match found, if any.
+    Found(Option<Range<usize>>),
+    OffsetEdited(String),
+    GoTo,
+}
+
+impl Hex {
+    pub fn new(bytes: Arc<Vec<u8>>) -> Self {
+        Self {
+            bytes,
+            top: 0,
+            query: String::new(),
+            offset: String::new(),
+            selected: None,
+            searching: false,
+            missed: false,
+        }
+    }
+
+    pub fn update(&mut self, message: Message, settings: &Settings) -> Task<Message> {
+        match message {
+            Message::Scrolled(delta) => {
+                let y = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y * WHEEL_ROWS,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / settings.line_height(),
+                };
+
+                self.top = ((self.top as f64 - f64::from(y)).round().max(0.0) as usize)
+                    .min(self.last_row());
+
+                Task::none()
+            }
+            Message::Jumped(value) => {
+                self.top = self.last_row() - (value as usize).min(self.last_row());
+
+                Task::none()
+            }
+            Message::QueryEdited(query) => {
+                self.query = query;
+                self.missed = false;
+
+                Task::none()
+            }
+            Message::Search => {
+                let Some(pattern) = pattern(&self.query) else {
+                    return Task::none();
+                };
+
+                if self.searching {
+                    return Task::none();
+                }
+
+                self.searching = true;
+
+                // The search starts after the current match, or at the top.
+                let start = match &self.selected {
+                    Some(selected) => selected.start + 1,
+                    None => self.top * WIDTH,
+                };
+
+                let bytes = self.bytes.clone();
+
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            search(&bytes, &pattern, start)
+                                .map(|offset| offset..offset + pattern.len())
+                        })
+                        .await
+                        .ok()
+                        .flatten()
+                    },
+                    Message::Found,
+                )
+            }
+            Message::Found(found) => {
+                self.searching = false;
+
+                match found {
+                    Some(found) => self.show(found),
+                    None => {
+                        self.selected = None;
+                        self.missed = true;
+                    }
+                }
+
+                Task::none()
+            }
+            Message::OffsetEdited(offset) => {
+                self.offset = offset;
+                self.missed = false;
+
+                Task::none()
+            }
+            Message::GoTo => {
+                match parse_offset(&self.offset).filter(|offset| *offset < self.bytes.len()) {
+                    Some(offset) => self.show(offset..offset + 1),
+                    None => self.missed = true,
+                }
+
+                Task::none()
+            }
+        }
+    }
+
+    pub fn view<'a>(
+        &'a self,
+        settings: &'a Settings,
+        appearance: &'a Appearance,
+    ) -> Element<'a, Message> {
+        let line_height = settings.line_height();
+        let highlight = Color {
+            a: 0.45,
+            ..appearance.theme.extended_palette().primary.base.color
+        };
+        let last_row = self.last_row();
+
+        let rows = responsive(move |size| {
+            let count = (size.height / line_height).ceil() as usize;
+            let end = (self.top + count).min(self.rows());
+
+            column((self.top..end).map(|index| self.row(index, settings, highlight))).into()
+        });
+
+        let slider = vertical_slider(
+            0.0..=last_row as f64,
+            (last_row - self.top) as f64,
+            Message::Jumped,
+        )
+        .width(8);
+
+        let can_search = pattern(&self.query).is_some() && !self.searching;
+
+        let tools = row![
+            text_input("Search bytes, as hex or \"text\"", &self.query)
+                .size(12)
+                .on_input(Message::QueryEdited)
+                .on_submit(Message::Search),
+            button(
+                text(if self.searching {
+                    "Searching…"
+                } else {
+                    "Next"
+                })
+                .size(12)
+            )
+            .padding([1, 6])
+            .on_press_maybe(can_search.then_some(Message::Search)),
+            text_input("Offset", &self.offset)
+                .size(12)
+                .width(120)
+                .on_input(Message::OffsetEdited)
+                .on_submit(Message::GoTo),
+            button(text("Go").size(12))
+                .padding([1, 6])
+                .on_press_maybe((!self.offset.trim().is_empty()).then_some(Message::GoTo)),
+        ]
+        .spacing(5);
+
+        let status = row![
+            text("Binary, read-only").size(12),
+            horizontal_space(),
+            text(match (&self.selected, self.missed) {
+                (_, true) => String::from("Not found"),
+                (Some(selected), false) => format!("At {:#x}", selected.start),
+                (None, false) => String::new(),
+            })
+            .size(12),
+            text(format!("{} bytes", self.bytes.len())).size(12),
+        ]
+        .spacing(10);
+
+        column![
+            tools,
+            row![
+                mouse_area(
+                    container(rows)
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .clip(true)
+                )
+                .on_scroll(Message::Scrolled),
+                slider,
+            ]
+            .spacing(4)
+            .height(Length::Fill),
+            status,
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    /// A row of bytes: their offset, in hexadecimal, and as ASCII, with the
+    /// selected ones highlighted.
+    fn row<'a>(
+        &'a self,
+        index: usize,
+        settings: &'a Settings,
+        highlight: Color,
+    ) -> Element<'a, Message> {
+        let start = index * WIDTH;
+        let end = (start + WIDTH).min(self.bytes.len());
+
+        let offset = text(format!("{start:08x}"))
+            .font(settings.font())
+            .size(settings.text_size)
+            .line_height(iced::Pixels(settings.line_height()))
+            .style(|theme: &iced::Theme| text::Style {
+                color: Some(theme.extended_palette().background.strong.color),
+            });
+
+        let mut spans = Vec::new();
+
+        for position in start..start + WIDTH {
+            let cell = match self.bytes.get(position) {
+                Some(byte) => format!("{byte:02x}"),
+                None => String::from("  "),
+            };
+
+            spans.push(self.span(cell, position, highlight));
+
+            // The gap after a byte is highlighted within a selection.
+            let gap = span(if position + 1 == start + WIDTH / 2 {
+                "  "
+            } else {
+                " "
+            });
+
+            spans.push(
+                if self.is_selected(position) && self.is_selected(position + 1) {
+                    gap.background(highlight)
+                } else {
+                    gap
+                },
+            );
+        }
+
+        spans.push(span("  "));
+
+        for position in start..end {
+            let byte = self.bytes[position];
+            let character = if byte.is_ascii_graphic() || byte == b' ' {
+                char::from(byte)
+            } else {
+                '.'
+            };
+
+            spans.push(self.span(character.to_string(), position, highlight));
+        }
+
+        row![
+            offset,
+            rich_text(spans)
+                .font(settings.font())
+                .size(settings.text_size)
+                .line_height(iced::Pixels(settings.line_height()))
+                .wrapping(text::Wrapping::None),
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn span<'a>(&self, text: String, position: usize, highlight: Color) -> Span<'a, Message> {
+        let span = span(text);
+
+        if self.is_selected(position) {
+            span.background(highlight)
+        } else {
+            span
+        }
+    }
+
+    fn is_selected(&self, position: usize) -> bool {
+        self.selected
+            .as_ref()
+            .is_some_and(|selected| selected.contains(&position))
+    }
+
+    /// Highlights some bytes, scrolling to them.
+    fn show(&mut self, selected: Range<usize>) {
+        self.top = (selected.start / WIDTH).saturating_sub(3);
+        self.selected = Some(selected);
+        self.missed = false;
+    }
+
+    fn rows(&self) -> usize {
+        self.bytes.len().div_ceil(WIDTH).max(1)
+    }
+
+    fn last_row(&self) -> usize {
+        self.rows() - 1
+    }
+}
+
+/// The bytes searched for a query: the text between double quotes, pairs of
+/// hexadecimal digits, or the text itself if it is not hexadecimal.
+fn pattern(query: &str) -> Option<Vec<u8>> {
+    let query = query.trim();
+
+    if let Some(text) = query
+        .strip_prefix('"')
+        .and_then(|query| query.strip_suffix('"'))
+    {
+        return (!text.is_empty()).then(|| text.as_bytes().to_vec());
+    }
+
+    if query.is_empty() {
+        return None;
+    }
+
+    let digits: Vec<u8> = query
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+
+    let hex = digits
+        .len()
+        .is_multiple_of(2)
+        .then(|| {
+            digits
+                .chunks(2)
+                .map(|pair| {
+                    std::str::from_utf8(pair)
+                        .ok()
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                })
+                .collect::<Option<Vec<u8>>>()
+        })
+        .flatten();
+
+    Some(hex.unwrap_or_else(|| query.as_bytes().to_vec()))
+}
+
+/// An offset, in hexadecimal after `0x`, or in decimal.
+fn parse_offset(offset: &str) -> Option<usize> {
+    let offset = offset.trim();
+
+    match offset
+        .strip_prefix("0x")
+        .or_else(|| offset.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => offset.parse().ok(),
+    }
+}
+
+/// Finds the first occurrence of some bytes at or after an offset, wrapping
+/// around to the start.
+fn search(bytes: &[u8], pattern: &[u8], start: usize) -> Option<usize> {
+    let find = |start: usize| {
+        bytes
+            .get(start..)?
+            .windows(pattern.len())
+            .position(|window| window == pattern)
+            .map(|position| start + position)
+    };
+
+    find(start).or_else(|| find(0))
+}
@@ -0,0 +1,226 @@
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use iced::Font;
+use iced::advanced::text::highlighter::{self, Format};
+use iced::font;
+use syntect::highlighting::{self, FontStyle, ScopeSelectors, ThemeSet};
+use syntect::parsing::{self, ParseState, ScopeStack, ScopeStackOp, SyntaxSet};
+
+use crate::settings::theme::Syntax;
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_nonewlines);
+
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// The scopes colored by each override of a [`Syntax`], in its order.
+static SELECTORS: LazyLock<[ScopeSelectors; 6]> = LazyLock::new(|| {
+    [
+        "comment",
+        "string",
+        "constant.numeric",
+        "keyword, storage",
+        "entity.name.function, support.function, variable.function",
+        "entity.name.type, entity.name.class, entity.name.struct, entity.name.enum, support.type, \
+         support.class",
+    ]
+    .map(|selectors| selectors.parse().expect("selectors are valid"))
+});
+
+/// The parse state is kept every this many lines, to resume from after an
+/// edit.
+const LINES_PER_SNAPSHOT: usize = 50;
+
+/// What the highlighting of a buffer depends on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// The extension of the file, choosing its syntax.
+    pub token: String,
+    pub is_dark: bool,
+    /// The colors of the theme overriding those of the highlighting theme.
+    pub syntax: Syntax,
+}
+
+/// A syntax highlighter with the colors of the highlighting theme matching
+/// the brightness of the editor theme, and those of the `[syntax]` table of
+/// a custom theme over them.
+///
+/// The one of iced takes nothing but a highlighting theme, so its parsing
+/// is repeated here.
+pub struct Highlighter {
+    syntax: &'static parsing::SyntaxReference,
+    highlighter: highlighting::Highlighter<'static>,
+    overrides: Syntax,
+    snapshots: Vec<(ParseState, ScopeStack)>,
+    current_line: usize,
+}
+
+impl highlighter::Highlighter for Highlighter {
+    type Settings = Settings;
+    type Highlight = Format<Font>;
+    type Iterator<'a> = Box<dyn Iterator<Item = (Range<usize>, Format<Font>)> + 'a>;
+
+    fn new(settings: &Settings) -> Self {
+        let syntax = syntax(&settings.token);
+
+        Self {
+            syntax,
+            highlighter: theme(settings.is_dark),
+            overrides: settings.syntax,
+            snapshots: vec![(ParseState::new(syntax), ScopeStack::new())],
+            current_line: 0,
+        }
+    }
+
+    fn update(&mut self, settings: &Settings) {
+        self.syntax = syntax(&settings.token);
+        self.highlighter = theme(settings.is_dark);
+        self.overrides = settings.syntax;
+
+        self.change_line(0);
+    }
+
+    fn change_line(&mut self, line: usize) {
+        let snapshot = line / LINES_PER_SNAPSHOT;
+
+        if snapshot <= self.snapshots.len() {
+            self.snapshots.truncate(snapshot);
+            self.current_line = snapshot * LINES_PER_SNAPSHOT;
+        } else {
+            self.snapshots.truncate(1);
+            self.current_line = 0;
+        }
+
+        let snapshot = self
+            .snapshots
+            .last()
+            .cloned()
+            .unwrap_or_else(|| (ParseState::new(self.syntax), ScopeStack::new()));
+
+        self.snapshots.push(snapshot);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        if self.current_line / LINES_PER_SNAPSHOT >= self.snapshots.len()
+            && let Some(snapshot) = self.snapshots.last().cloned()
+        {
+            self.snapshots.push(snapshot);
+        }
+
+        self.current_line += 1;
+
+        let Some((parser, stack)) = self.snapshots.last_mut() else {
+            return Box::new(std::iter::empty());
+        };
+
+        let operations = parser.parse_line(line, &SYNTAXES).unwrap_or_default();
+        let highlighter = &self.highlighter;
+        let overrides = &self.overrides;
+
+        Box::new(
+            ranges(operations, line.len()).filter_map(move |(range, operation)| {
+                let _ = stack.apply(&operation);
+
+                (!range.is_empty()).then(|| {
+                    let style = highlighter.style_mod_for_stack(&stack.scopes);
+
+                    (range, format(style, overrides, &stack.scopes))
+                })
+            }),
+        )
+    }
+
+    fn current_line(&self) -> usize {
+        self.current_line
+    }
+}
+
+fn syntax(token: &str) -> &'static parsing::SyntaxReference {
+    SYNTAXES
+        .find_syntax_by_token(token)
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text())
+}
+
+fn theme(is_dark: bool) -> highlighting::Highlighter<'static> {
+    highlighting::Highlighter::new(
+        &THEMES.themes[if is_dark {
+            "base16-mocha.dark"
+        } else {
+            "InspiredGitHub"
+        }],
+    )
+}
+
+/// The format of a token, colored by the override whose scopes match it
+/// best, if any.
+fn format(
+    style: highlighting::StyleModifier,
+    overrides: &Syntax,
+    scopes: &[parsing::Scope],
+) -> Format<Font> {
+    let colors = [
+        overrides.comment,
+        overrides.string,
+        overrides.number,
+        overrides.keyword,
+        overrides.function,
+        overrides.r#type,
+    ];
+
+    let color = SELECTORS
+        .iter()
+        .zip(colors)
+        .filter_map(|(selectors, color)| Some((selectors.does_match(scopes)?, color?)))
+        .max_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(_, color)| color)
+        .or_else(|| {
+            style.foreground.map(|color| {
+                iced::Color::from_rgba8(color.r, color.g, color.b, f32::from(color.a) / 255.0)
+            })
+        });
+
+    let font = style.font_style.and_then(|style| {
+        let bold = style.contains(FontStyle::BOLD);
+        let italic = style.contains(FontStyle::ITALIC);
+
+        (bold || italic).then_some(Font {
+            weight: if bold {
+                font::Weight::Bold
+            } else {
+                font::Weight::Normal
+            },
+            style: if italic {
+                font::Style::Italic
+            } else {
+                font::Style::Normal
+            },
+            ..Font::MONOSPACE
+        })
+    });
+
+    Format { color, font }
+}
+
+/// The ranges of a line between the operations parsed from it, each with
+/// the operation applying before it.
+fn ranges(
+    operations: Vec<(usize, ScopeStackOp)>,
+    length: usize,
+) -> impl Iterator<Item = (Range<usize>, ScopeStackOp)> {
+    let ends: Vec<usize> = operations
+        .iter()
+        .map(|(index, _)| *index)
+        .chain(std::iter::once(length))
+        .collect();
+    let operations =
+        std::iter::once(ScopeStackOp::Noop).chain(operations.into_iter().map(|(_, op)| op));
+
+    ends.into_iter()
+        .scan(0, |start, end| {
+            let range = *start..end;
+            *start = end;
+
+            Some(range)
+        })
+        .zip(operations)
+}
@@ -0,0 +1,193 @@
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text};
+use iced::{Element, Length};
+
+use crate::merge::{self, Resolution, Segment};
+use crate::panes::style;
+use crate::settings::Settings;
+
+/// The resolution of the conflicts of a buffer.
+pub struct Merge {
+    pub segments: Vec<Segment>,
+    /// The resolution of every conflict, in order.
+    pub resolutions: Vec<Option<Resolution>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Accept(usize, Resolution),
+    Reset(usize),
+    Write { stage: bool },
+    Cancel,
+}
+
+impl Merge {
+    pub fn new(text: &str, ancestor: Option<&str>) -> Self {
+        let mut segments = merge::parse(text);
+
+        if let Some(ancestor) = ancestor {
+            merge::fill_bases(&mut segments, ancestor);
+        }
+
+        let conflicts = segments
+            .iter()
+            .filter(|segment| matches!(segment, Segment::Conflict(_)))
+            .count();
+
+        Self {
+            segments,
+            resolutions: vec![None; conflicts],
+        }
+    }
+
+    /// Records the resolution of a conflict.
+    pub fn update(&mut self, message: &Message) {
+        match *message {
+            Message::Accept(index, resolution) => {
+                if let Some(slot) = self.resolutions.get_mut(index) {
+                    *slot = Some(resolution);
+                }
+            }
+            Message::Reset(index) => {
+                if let Some(slot) = self.resolutions.get_mut(index) {
+                    *slot = None;
+                }
+            }
+            Message::Write { .. } | Message::Cancel => {}
+        }
+    }
+
+    /// The contents of the file with the current resolutions.
+    pub fn result(&self) -> String {
+        merge::resolve(&self.segments, &self.resolutions)
+    }
+
+    pub fn view<'a>(&'a self, settings: &'a Settings) -> Element<'a, Message> {
+        let conflicts = self.segments.iter().filter_map(|segment| match segment {
+            Segment::Conflict(conflict) => Some(conflict),
+            Segment::Common(_) => None,
+        });
+
+        let side = |title: String, contents: Option<&'a str>, selected: bool| {
+            container(
+                column![
+                    text(title).size(12),
+                    text(contents.unwrap_or("(unknown)").trim_end_matches('\n'))
+                        .font(settings.font())
+                        .size(settings.text_size),
+                ]
+                .spacing(4),
+            )
+            .padding(5)
+            .width(Length::FillPortion(1))
+            .style(move |theme| style::merge_side(theme, selected))
+        };
+
+        let conflicts = conflicts.enumerate().map(|(index, conflict)| {
+            let resolution = self.resolutions[index];
+            let is =
+                |expected| resolution == Some(expected) || resolution == Some(Resolution::Both);
+
+            let action = |label, message| {
+                button(text(label).size(12))
+                    .padding([1, 6])
+                    .on_press(message)
+            };
+
+            column![
+                row![
+                    text(format!(
+                        "Conflict {} of {}{}",
+                        index + 1,
+                        self.resolutions.len(),
+                        match resolution {
+                            Some(Resolution::Ours) => " — ours accepted",
+                            Some(Resolution::Theirs) => " — theirs accepted",
+                            Some(Resolution::Both) => " — both accepted",
+                            None => "",
+                        }
+                    ))
+                    .size(12),
+                    horizontal_space(),
+                    action("Accept ours", Message::Accept(index, Resolution::Ours)),
+                    action("Accept theirs", Message::Accept(index, Resolution::Theirs)),
+                    action("Accept both", Message::Accept(index, Resolution::Both)),
+                    button(text("Reset").size(12))
+                        .padding([1, 6])
+                        .on_press_maybe(resolution.map(|_| Message::Reset(index))),
+                ]
+                .spacing(5),
+                row![
+                    side(
+                        label("Ours", &conflict.ours_label),
+                        Some(&conflict.ours),
+                        is(Resolution::Ours),
+                    ),
+                    side(String::from("Base"), conflict.base.as_deref(), false),
+                    side(
+                        label("Theirs", &conflict.theirs_label),
+                        Some(&conflict.theirs),
+                        is(Resolution::Theirs),
+                    ),
+                ]
+                .spacing(5),
+            ]
+            .spacing(5)
+            .into()
+        });
+
+        let resolved = self
+            .resolutions
+            .iter()
+            .filter(|slot| slot.is_some())
+            .count();
+
+        let actions = row![
+            text(format!(
+                "{resolved} of {} conflicts resolved",
+                self.resolutions.len()
+            ))
+            .size(12),
+            horizontal_space(),
+            button(text("Cancel").size(12))
+                .padding([1, 6])
+                .on_press(Message::Cancel),
+            button(text("Write").size(12))
+                .padding([1, 6])
+                .on_press(Message::Write { stage: false }),
+            button(text("Write and stage").size(12))
+                .padding([1, 6])
+                .on_press_maybe(
+                    (resolved == self.resolutions.len()).then_some(Message::Write { stage: true }),
+                ),
+        ]
+        .spacing(5);
+
+        column![
+            scrollable(column(conflicts).spacing(15)).height(Length::FillPortion(3)),
+            text("Result").size(12),
+            container(
+                scrollable(
+                    text(self.result())
+                        .font(settings.font())
+                        .size(settings.text_size)
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+            )
+            .padding(5)
+            .height(Length::FillPortion(2))
+            .style(|theme| style::merge_side(theme, false)),
+            actions,
+        ]
+        .spacing(5)
+        .into()
+    }
+}
+
+fn label(side: &str, label: &str) -> String {
+    if label.is_empty() {
+        side.to_owned()
+    } else {
+        format!("{side} ({label})")
+    }
+}
@@ -0,0 +1,260 @@
+use std::ops::Range;
+
+use iced::widget::{
+    Space, button, column, container, mouse_area, responsive, row, stack, vertical_space,
+};
+use iced::{Element, Length, Padding, Theme};
+
+use crate::diff;
+use crate::panes::style;
+use crate::settings::Settings;
+
+use super::{Buffer, Message, cursors, search};
+
+/// The width of the minimap, markers included.
+const WIDTH: f32 = 80.0;
+
+/// The width of the markers along the edge of the minimap.
+const MARKER_WIDTH: f32 = 6.0;
+
+/// The least height of a marker, to keep it clickable in long files.
+const MARKER_HEIGHT: f32 = 3.0;
+
+/// The most height given to a line, for short files.
+const LINE_HEIGHT: f32 = 2.0;
+
+/// The width given to a column of text.
+const COLUMN_WIDTH: f32 = 0.6;
+
+/// What a marker along the minimap points to.
+#[derive(Clone, Copy)]
+enum Mark {
+    Change(diff::Kind),
+    Conflict,
+    /// A line containing a match of the search, or the selected text.
+    Match,
+    Cursor,
+}
+
+/// Shows the whole content of a buffer zoomed out, with the lines in view
+/// highlighted, and markers for its changes, conflicts, matches of the
+/// search, or of the selection when not searching, and cursors. Only the
+/// markers are shown when the minimap is off.
+pub fn view<'a>(
+    buffer: &'a Buffer,
+    lines: Range<usize>,
+    query: Option<&'a str>,
+    settings: &'a Settings,
+) -> Element<'a, Message> {
+    let minimap = responsive(move |size| {
+        let count = buffer.content.line_count().max(1);
+        let scale = (size.height / count as f32).min(LINE_HEIGHT);
+
+        let mut layers = Vec::new();
+
+        if settings.minimap {
+            layers.push(text(buffer, scale));
+        }
+
+        layers.push(rectangle(
+            lines.start as f32 * scale,
+            lines.len() as f32 * scale,
+            style::minimap_viewport,
+        ));
+
+        let (cursors, others) = marks(buffer, query)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, mark)| matches!(mark, Mark::Cursor));
+
+        layers.push(markers(others, scale));
+        layers.push(markers(cursors, scale));
+
+        mouse_area(stack(layers).width(Length::Fill).height(Length::Fill))
+            .on_move(move |point| Message::MinimapMoved((point.y / scale) as usize))
+            .on_press(Message::MinimapPressed)
+            .on_release(Message::MinimapReleased)
+            .on_exit(Message::MinimapReleased)
+            .into()
+    });
+
+    container(minimap)
+        .width(if settings.minimap {
+            WIDTH
+        } else {
+            MARKER_WIDTH
+        })
+        .height(Length::Fill)
+        .into()
+}
+
+/// The extent of the text of every line, merged into rows at least a pixel
+/// high.
+fn text<'a>(buffer: &Buffer, scale: f32) -> Element<'a, Message> {
+    let height = scale.max(1.0);
+    let lines_per_row = height / scale;
+    let most = ((WIDTH - MARKER_WIDTH) / COLUMN_WIDTH) as usize;
+
+    let mut rows = Vec::new();
+    let mut gap = 0.0;
+    let mut current = 0;
+    let mut extent: Option<Range<usize>> = None;
+
+    let mut flush = |extent: Option<Range<usize>>, rows: &mut Vec<Element<'a, Message>>| {
+        let Some(extent) = extent else {
+            gap += height;
+            return;
+        };
+
+        if gap > 0.0 {
+            rows.push(vertical_space().height(gap).into());
+            gap = 0.0;
+        }
+
+        rows.push(
+            row![
+                Space::with_width(extent.start as f32 * COLUMN_WIDTH),
+                container(Space::new(extent.len() as f32 * COLUMN_WIDTH, height))
+                    .style(style::minimap),
+            ]
+            .into(),
+        );
+    };
+
+    for (line, text) in buffer.content.lines().enumerate() {
+        let index = (line as f32 / lines_per_row) as usize;
+
+        if index != current {
+            flush(extent.take(), &mut rows);
+            current = index;
+        }
+
+        let trimmed = text.trim_start();
+        let start = cursors::columns(&text[..text.len() - trimmed.len()]).min(most);
+        let end = cursors::columns(text.trim_end()).min(most);
+
+        if end > start {
+            extent = Some(match extent {
+                Some(extent) => extent.start.min(start)..extent.end.max(end),
+                None => start..end,
+            });
+        }
+    }
+
+    flush(extent, &mut rows);
+
+    column(rows).into()
+}
+
+/// The lines of a buffer worth a marker, in order.
+fn marks(buffer: &Buffer, query: Option<&str>) -> Vec<(Range<usize>, Mark)> {
+    let content = &buffer.content;
+    let mut marks = Vec::new();
+
+    for hunk in &buffer.hunks {
+        let start = buffer.visible_line(hunk.new.start);
+        let end = buffer.visible_line(hunk.new.end).max(start + 1);
+
+        marks.push((start..end, Mark::Change(hunk.kind())));
+    }
+
+    for (line, text) in content.lines().enumerate() {
+        if text.starts_with("<<<<<<<") {
+            marks.push((line..line + 1, Mark::Conflict));
+        }
+    }
+
+    let selection = content
+        .selection()
+        .filter(|selection| !selection.trim().is_empty() && !selection.contains('\n'));
+
+    let text = content.text();
+
+    let matches = match (query, selection) {
+        (Some(query), _) => search::matches(&text, query),
+        (None, Some(selection)) => text
+            .match_indices(selection.as_str())
+            .map(|(start, found)| start..start + found.len())
+            .collect(),
+        (None, None) => Vec::new(),
+    };
+
+    // The lines of the matches are counted along, once per line.
+    let mut line = 0;
+    let mut counted = 0;
+    let mut last = None;
+
+    for found in matches {
+        line += text[counted..found.start].matches('\n').count();
+        counted = found.start;
+
+        if last != Some(line) {
+            marks.push((line..line + 1, Mark::Match));
+            last = Some(line);
+        }
+    }
+
+    let lines = buffer
+        .cursors
+        .iter()
+        .map(|cursor| cursor.head.0)
+        .chain([content.cursor_position().0]);
+
+    marks.extend(lines.map(|line| (line..line + 1, Mark::Cursor)));
+    marks.sort_by_key(|(lines, _)| lines.start);
+
+    marks
+}
+
+/// Buttons jumping to the marked lines, on the edge of the minimap. Markers
+/// hidden by the ones before them are left out.
+fn markers<'a>(marks: Vec<(Range<usize>, Mark)>, scale: f32) -> Element<'a, Message> {
+    let mut markers = Vec::new();
+    let mut y = 0.0;
+
+    for (lines, mark) in marks {
+        let top = (lines.start as f32 * scale).max(y);
+        let bottom = (lines.start as f32 * scale + MARKER_HEIGHT).max(lines.end as f32 * scale);
+
+        if bottom <= top {
+            continue;
+        }
+
+        if top > y {
+            markers.push(vertical_space().height(top - y).into());
+        }
+
+        y = bottom;
+
+        markers.push(
+            button(Space::new(MARKER_WIDTH, bottom - top))
+                .padding(0)
+                .style(move |theme, status| self::style(theme, status, mark))
+                .on_press(Message::JumpTo(lines.start))
+                .into(),
+        );
+    }
+
+    container(column(markers)).align_right(Length::Fill).into()
+}
+
+fn style(theme: &Theme, status: button::Status, mark: Mark) -> button::Style {
+    match mark {
+        Mark::Change(kind) => style::hunk(theme, status, kind),
+        Mark::Conflict => style::conflict_mark(theme, status),
+        Mark::Match => style::match_mark(theme, status),
+        Mark::Cursor => style::cursor_mark(theme, status),
+    }
+}
+
+fn rectangle<'a>(
+    y: f32,
+    height: f32,
+    style: fn(&Theme) -> container::Style,
+) -> Element<'a, Message> {
+    container(container(Space::new(Length::Fill, height)).style(style))
+        .padding(Padding {
+            top: y,
+            ..Padding::ZERO
+        })
+        .into()
+}
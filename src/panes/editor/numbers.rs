@@ -0,0 +1,68 @@
+use std::ops::Range;
+
+use iced::widget::{column, text, vertical_space};
+use iced::{Element, Pixels};
+
+use crate::panes::style;
+use crate::settings::{LineNumbers, Settings};
+
+use super::{Buffer, Message, cursors};
+
+/// Numbers the lines of the content in view, unless numbers are off.
+/// Folded lines are skipped, and the current line stands out.
+pub fn view<'a>(
+    buffer: &Buffer,
+    lines: Range<usize>,
+    settings: &'a Settings,
+) -> Option<Element<'a, Message>> {
+    if settings.line_numbers == LineNumbers::Off {
+        return None;
+    }
+
+    let line_height = settings.line_height();
+    let current = buffer.content.cursor_position().0;
+    let count = buffer.full_line(buffer.content.line_count().saturating_sub(1)) + 1;
+    let digits = count.to_string().len().max(3);
+
+    let numbers = |range: Range<usize>| {
+        range
+            .map(|line| {
+                let number = match settings.line_numbers {
+                    LineNumbers::Relative if line != current => line.abs_diff(current),
+                    _ => buffer.full_line(line) + 1,
+                };
+
+                format!("{number:>digits$}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let label = |range: Range<usize>, is_current: bool| {
+        (!range.is_empty()).then(|| {
+            text(numbers(range))
+                .font(settings.font())
+                .size(settings.text_size)
+                .line_height(Pixels(line_height))
+                .wrapping(text::Wrapping::None)
+                .style(move |theme| style::line_number(theme, is_current))
+        })
+    };
+
+    let before = lines.start..current.clamp(lines.start, lines.end);
+    let after = (current + 1).clamp(lines.start, lines.end)..lines.end;
+    let current = if lines.contains(&current) {
+        current..current + 1
+    } else {
+        0..0
+    };
+
+    Some(
+        column![vertical_space().height(lines.start as f32 * line_height)]
+            .push_maybe(label(before, false))
+            .push_maybe(label(current, true))
+            .push_maybe(label(after, false))
+            .width(digits as f32 * cursors::char_width(settings))
+            .into(),
+    )
+}
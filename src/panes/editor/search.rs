@@ -0,0 +1,127 @@
+use std::ops::Range;
+
+use iced::widget::text_input;
+
+/// The text searched for in the active buffer, while the find bar is open.
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub query: String,
+    pub input: text_input::Id,
+}
+
+impl Search {
+    pub fn new(query: String) -> Self {
+        Self {
+            query,
+            input: text_input::Id::unique(),
+        }
+    }
+}
+
+/// The byte ranges of the occurrences of a query in a text, ignoring case
+/// when the query has no capitals.
+pub fn matches(text: &str, query: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    if query.chars().any(char::is_uppercase) {
+        return text
+            .match_indices(query)
+            .map(|(start, found)| start..start + found.len())
+            .collect();
+    }
+
+    // Lowercasing may change the length of some characters, so the text is
+    // compared a character at a time.
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let mut matches = Vec::new();
+    let mut from = 0;
+
+    while from < text.len() {
+        let mut wanted = query.iter();
+        let mut end = from;
+
+        for (index, c) in text[from..].char_indices() {
+            if wanted.len() == 0 {
+                break;
+            }
+
+            if !c.to_lowercase().all(|c| wanted.next() == Some(&c)) {
+                break;
+            }
+
+            end = from + index + c.len_utf8();
+        }
+
+        if wanted.len() == 0 && end > from {
+            matches.push(from..end);
+            from = end;
+        } else {
+            from += text[from..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    matches
+}
+
+/// The match after an offset, or the last one before it, wrapping around.
+pub fn next(matches: &[Range<usize>], offset: usize, backward: bool) -> Option<Range<usize>> {
+    let found = if backward {
+        matches
+            .iter()
+            .rev()
+            .find(|found| found.end <= offset)
+            .or(matches.last())
+    } else {
+        matches
+            .iter()
+            .find(|found| found.start >= offset)
+            .or(matches.first())
+    };
+
+    found.cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The bounds of the matches of a query.
+    fn bounds(text: &str, query: &str) -> Vec<(usize, usize)> {
+        matches(text, query)
+            .into_iter()
+            .map(|found| (found.start, found.end))
+            .collect()
+    }
+
+    #[test]
+    fn matches_ignore_case_without_capitals() {
+        assert_eq!(bounds("Foo foo FOO", "foo"), [(0, 3), (4, 7), (8, 11)]);
+        assert_eq!(bounds("Foo foo FOO", "Foo"), [(0, 3)]);
+        assert_eq!(bounds("Foo", ""), []);
+    }
+
+    #[test]
+    fn matches_do_not_overlap() {
+        assert_eq!(bounds("aaaa", "aa"), [(0, 2), (2, 4)]);
+    }
+
+    #[test]
+    fn matches_are_byte_ranges() {
+        assert_eq!(bounds("élan ÉLAN", "élan"), [(0, 5), (6, 11)]);
+        // Lowercasing `İ` takes two characters.
+        assert_eq!(bounds("İx", "i̇x"), [(0, 3)]);
+    }
+
+    #[test]
+    fn next_wraps_around() {
+        let matches = [0..2, 5..7, 10..12];
+
+        assert_eq!(next(&matches, 3, false), Some(5..7));
+        assert_eq!(next(&matches, 11, false), Some(0..2));
+        assert_eq!(next(&matches, 8, true), Some(5..7));
+        assert_eq!(next(&matches, 1, true), Some(10..12));
+        assert_eq!(next(&[], 0, false), None);
+    }
+}
@@ -0,0 +1,710 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+use iced::widget::text_editor;
+
+use crate::date::DateTime;
+use crate::settings::Snippet;
+
+use super::{cursors, fold, paste};
+
+/// The body of a snippet, expanded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    pub text: String,
+    /// The tab stops, in the order they are visited, the final one last.
+    pub stops: Vec<Stop>,
+}
+
+/// A tab stop, and the places it mirrors its text to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stop {
+    /// The ranges of the stop in the text, the first one being edited.
+    pub ranges: Vec<Range<usize>>,
+    /// The values offered, if the stop is a choice.
+    pub choices: Vec<String>,
+}
+
+/// What the variables of a snippet are resolved from.
+pub struct Context<'a> {
+    pub path: &'a Path,
+    pub selected: &'a str,
+    pub line: &'a str,
+    pub word: &'a str,
+    /// The line of the cursor, from 0.
+    pub line_index: usize,
+}
+
+/// A snippet being filled in, its stops visited with Tab and Shift+Tab.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// The stops, with their ranges in the text of the content.
+    stops: Vec<Stop>,
+    current: usize,
+    /// The length of the text when the current stop was entered, which
+    /// only the edits of the stop may change.
+    length: usize,
+}
+
+/// Expands the body of a snippet, with the syntax of VS Code.
+///
+/// The lines after the first are indented like the line it is inserted on,
+/// and tabs are replaced with the indentation unit of the file. Variables
+/// are resolved with a function, unknown ones being left as their name.
+pub fn expand(
+    body: &str,
+    indent: &str,
+    unit: &str,
+    variable: impl Fn(&str) -> Option<String>,
+) -> Expansion {
+    let mut parser = Parser {
+        chars: body.chars().collect(),
+        index: 0,
+        text: String::new(),
+        stops: BTreeMap::new(),
+        defaults: BTreeMap::new(),
+        indent,
+        unit,
+        variable,
+    };
+
+    parser.any(false);
+
+    let end = parser.text.len();
+    let mut stops = parser.stops;
+
+    // The final stop is at the end unless placed.
+    let last = stops.remove(&0).unwrap_or_else(|| Stop {
+        ranges: std::iter::once(end..end).collect(),
+        choices: Vec::new(),
+    });
+
+    Expansion {
+        text: parser.text,
+        stops: stops.into_values().chain([last]).collect(),
+    }
+}
+
+/// Finds the snippet with the longest prefix ending some text, and the
+/// length of the prefix.
+///
+/// A prefix starting with a word character must also start a word.
+pub fn find<'a>(
+    snippets: impl Iterator<Item = &'a Snippet>,
+    before: &str,
+) -> Option<(&'a Snippet, usize)> {
+    snippets
+        .flat_map(|snippet| snippet.prefixes.iter().map(move |prefix| (snippet, prefix)))
+        .filter(|(_, prefix)| !prefix.is_empty() && before.ends_with(prefix.as_str()))
+        .filter(|(_, prefix)| {
+            let starts_word = prefix.chars().next().is_some_and(is_word);
+            let after_word = before[..before.len() - prefix.len()]
+                .chars()
+                .next_back()
+                .is_some_and(is_word);
+
+            !(starts_word && after_word)
+        })
+        .max_by_key(|(_, prefix)| prefix.len())
+        .map(|(snippet, prefix)| (snippet, prefix.len()))
+}
+
+impl Session {
+    /// Replaces the selection of the content with an expansion, and enters
+    /// its first stop.
+    ///
+    /// There is nothing to fill in when the expansion only has its final
+    /// stop, which the cursor is then moved to.
+    pub fn start(
+        content: &mut text_editor::Content,
+        cursors: &mut Vec<cursors::Cursor>,
+        expansion: Expansion,
+    ) -> Option<Self> {
+        let selection = cursors::primary(content);
+        let start = cursors::offset(content, selection.anchor.min(selection.head));
+
+        cursors.clear();
+        cursors::select(content, selection);
+        paste(content, expansion.text);
+
+        let stops = expansion
+            .stops
+            .into_iter()
+            .map(|stop| Stop {
+                ranges: stop
+                    .ranges
+                    .into_iter()
+                    .map(|range| start + range.start..start + range.end)
+                    .collect(),
+                ..stop
+            })
+            .collect();
+
+        let mut session = Self {
+            stops,
+            current: 0,
+            length: 0,
+        };
+
+        session.enter(content, cursors);
+
+        (session.stops.len() > 1).then_some(session)
+    }
+
+    /// The values offered at the current stop.
+    pub fn choices(&self) -> &[String] {
+        &self.stops[self.current].choices
+    }
+
+    /// Moves to the next stop, or the previous one, taking in what was typed
+    /// at the current one.
+    ///
+    /// Returns whether the session goes on, which it does not once the final
+    /// stop is reached or if the text was edited outside of the stop.
+    pub fn advance(
+        &mut self,
+        content: &mut text_editor::Content,
+        cursors: &mut Vec<cursors::Cursor>,
+        backward: bool,
+    ) -> bool {
+        let cursor = cursors::primary(content);
+        let head = cursors::offset(content, cursor.anchor.max(cursor.head));
+
+        let mut ranges = self.stops[self.current].ranges.clone();
+        ranges.sort_by_key(|range| range.start);
+
+        let Some(first) = self.stops[self.current].ranges.first().cloned() else {
+            return false;
+        };
+
+        if head < first.start {
+            return false;
+        }
+
+        let delta = (head - first.start) as isize - first.len() as isize;
+        let length = content.text().len();
+
+        if length as isize != self.length as isize + delta * ranges.len() as isize {
+            return false;
+        }
+
+        let shift = |position: usize| {
+            let before = ranges.iter().filter(|range| range.end <= position).count();
+
+            position.saturating_add_signed(delta * before as isize)
+        };
+
+        for (index, stop) in self.stops.iter_mut().enumerate() {
+            if index == self.current {
+                for range in &mut stop.ranges {
+                    let start = shift(range.start);
+                    let start = if range.start == range.end {
+                        // Not shifted by its own edit.
+                        start.saturating_add_signed(-delta)
+                    } else {
+                        start
+                    };
+
+                    *range = start..start.saturating_add_signed(range.len() as isize + delta);
+                }
+
+                continue;
+            }
+
+            // The stops within a stop are gone once its text is replaced.
+            if delta != 0 {
+                stop.ranges
+                    .retain(|range| !ranges.iter().any(|outer| is_within(range, outer)));
+            }
+
+            for range in &mut stop.ranges {
+                *range = shift(range.start)..shift(range.end);
+            }
+        }
+
+        loop {
+            if backward {
+                if self.current == 0 {
+                    break;
+                }
+
+                self.current -= 1;
+            } else {
+                self.current += 1;
+            }
+
+            if !self.stops[self.current].ranges.is_empty() {
+                break;
+            }
+
+            if self.current + 1 == self.stops.len() {
+                break;
+            }
+        }
+
+        self.enter(content, cursors);
+
+        self.current + 1 < self.stops.len()
+    }
+
+    /// Selects the ranges of the current stop, the first one with the cursor
+    /// of the content.
+    fn enter(&mut self, content: &mut text_editor::Content, cursors: &mut Vec<cursors::Cursor>) {
+        cursors.clear();
+
+        let mut ranges = self.stops[self.current].ranges.iter().map(|range| {
+            let anchor = cursors::position(content, range.start);
+            let head = cursors::position(content, range.end);
+
+            cursors::Cursor { anchor, head }
+        });
+
+        let Some(primary) = ranges.next() else {
+            return;
+        };
+
+        cursors.extend(ranges);
+        cursors::select(content, primary);
+
+        self.length = content.text().len();
+    }
+}
+
+impl Context<'_> {
+    /// The value of a variable, if known.
+    ///
+    /// Dates and times are in the local time zone.
+    pub fn variable(&self, name: &str) -> Option<String> {
+        let file =
+            |path: Option<&std::ffi::OsStr>| path.map(|path| path.to_string_lossy().into_owned());
+
+        let value = match name {
+            "TM_FILENAME" => file(self.path.file_name())?,
+            "TM_FILENAME_BASE" => file(self.path.file_stem())?,
+            "TM_DIRECTORY" => file(self.path.parent().map(Path::as_os_str))?,
+            "TM_FILEPATH" => self.path.display().to_string(),
+            "TM_SELECTED_TEXT" => self.selected.to_owned(),
+            "TM_CURRENT_LINE" => self.line.to_owned(),
+            "TM_CURRENT_WORD" => self.word.to_owned(),
+            "TM_LINE_INDEX" => self.line_index.to_string(),
+            "TM_LINE_NUMBER" => (self.line_index + 1).to_string(),
+            "LINE_COMMENT" => {
+                let extension = self.path.extension()?.to_str()?;
+
+                fold::line_comment(extension)?.to_owned()
+            }
+            _ => return date(name),
+        };
+
+        Some(value)
+    }
+}
+
+/// The value of a date or time variable, for the current time.
+fn date(name: &str) -> Option<String> {
+    let now = DateTime::now();
+
+    Some(match name {
+        "CURRENT_YEAR" => now.year.to_string(),
+        "CURRENT_YEAR_SHORT" => format!("{:02}", now.year % 100),
+        "CURRENT_MONTH" => format!("{:02}", now.month),
+        "CURRENT_MONTH_NAME" => now.month_name().to_owned(),
+        "CURRENT_MONTH_NAME_SHORT" => now.month_name()[..3].to_owned(),
+        "CURRENT_DATE" => format!("{:02}", now.day),
+        "CURRENT_DAY_NAME" => now.day_name().to_owned(),
+        "CURRENT_DAY_NAME_SHORT" => now.day_name()[..3].to_owned(),
+        "CURRENT_HOUR" => format!("{:02}", now.hour),
+        "CURRENT_MINUTE" => format!("{:02}", now.minute),
+        "CURRENT_SECOND" => format!("{:02}", now.second),
+        "CURRENT_SECONDS_UNIX" => now.seconds.to_string(),
+        _ => return None,
+    })
+}
+
+/// Whether a range is nested in another, rather than next to it.
+fn is_within(range: &Range<usize>, outer: &Range<usize>) -> bool {
+    if range.is_empty() {
+        outer.start < range.start && range.start < outer.end
+    } else {
+        outer.start <= range.start && range.end <= outer.end
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+struct Parser<'a, F> {
+    chars: Vec<char>,
+    index: usize,
+    text: String,
+    stops: BTreeMap<usize, Stop>,
+    /// The text of the placeholders, which their bare tab stops mirror.
+    defaults: BTreeMap<usize, String>,
+    indent: &'a str,
+    unit: &'a str,
+    variable: F,
+}
+
+impl<F: Fn(&str) -> Option<String>> Parser<'_, F> {
+    /// Parses anything up to the end of the body or, when nested, up to the
+    /// closing brace.
+    fn any(&mut self, nested: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                '}' if nested => return,
+                '\\' => {
+                    self.index += 1;
+
+                    match self.peek() {
+                        Some(escaped @ ('$' | '}' | '\\')) => {
+                            self.index += 1;
+                            self.push(escaped);
+                        }
+                        _ => self.push('\\'),
+                    }
+                }
+                '$' => self.dollar(),
+                c => {
+                    self.index += 1;
+                    self.push(c);
+                }
+            }
+        }
+    }
+
+    /// Parses a tab stop, a placeholder, a choice or a variable, or a dollar
+    /// sign taken literally.
+    fn dollar(&mut self) {
+        let dollar = self.index;
+        self.index += 1;
+
+        if let Some(number) = self.number() {
+            return self.tab_stop(number, self.text.len());
+        }
+
+        if let Some(name) = self.name() {
+            return self.variable(&name);
+        }
+
+        if self.peek() == Some('{') {
+            self.index += 1;
+
+            if let Some(number) = self.number() {
+                let start = self.text.len();
+
+                match self.peek() {
+                    Some('}') => {
+                        self.index += 1;
+                        self.tab_stop(number, start);
+                    }
+                    Some(':') => {
+                        self.index += 1;
+                        self.any(true);
+                        self.index += 1;
+                        self.placeholder(number, start);
+                    }
+                    Some('|') => {
+                        self.index += 1;
+                        self.choice(number, start);
+                    }
+                    // Transformations are not applied.
+                    _ => {
+                        self.skip();
+                        self.tab_stop(number, start);
+                    }
+                }
+
+                return;
+            }
+
+            if let Some(name) = self.name() {
+                match self.peek() {
+                    Some('}') => {
+                        self.index += 1;
+                        self.variable(&name);
+                    }
+                    Some(':') => {
+                        self.index += 1;
+
+                        let start = self.text.len();
+                        self.any(true);
+                        self.index += 1;
+
+                        if let Some(value) =
+                            (self.variable)(&name).filter(|value| !value.is_empty())
+                        {
+                            self.text.truncate(start);
+                            self.push_value(&value);
+                        }
+                    }
+                    _ => {
+                        self.skip();
+                        self.variable(&name);
+                    }
+                }
+
+                return;
+            }
+        }
+
+        self.index = dollar + 1;
+        self.push('$');
+    }
+
+    fn tab_stop(&mut self, number: usize, start: usize) {
+        if let Some(default) = self.defaults.get(&number).cloned() {
+            self.text.push_str(&default);
+        }
+
+        self.add(number, start..self.text.len(), Vec::new());
+    }
+
+    fn placeholder(&mut self, number: usize, start: usize) {
+        self.defaults
+            .entry(number)
+            .or_insert_with(|| self.text[start..].to_owned());
+
+        self.add(number, start..self.text.len(), Vec::new());
+    }
+
+    /// Parses the values of a choice, inserting the first one.
+    fn choice(&mut self, number: usize, start: usize) {
+        let mut choices = vec![String::new()];
+
+        while let Some(c) = self.peek() {
+            self.index += 1;
+
+            match c {
+                '\\' => {
+                    if let Some(escaped) = self.peek() {
+                        self.index += 1;
+
+                        if let Some(choice) = choices.last_mut() {
+                            choice.push(escaped);
+                        }
+                    }
+                }
+                ',' => choices.push(String::new()),
+                '|' if self.peek() == Some('}') => {
+                    self.index += 1;
+                    break;
+                }
+                c => {
+                    if let Some(choice) = choices.last_mut() {
+                        choice.push(c);
+                    }
+                }
+            }
+        }
+
+        self.text.push_str(&choices[0]);
+        self.defaults
+            .entry(number)
+            .or_insert_with(|| choices[0].clone());
+        self.add(number, start..self.text.len(), choices);
+    }
+
+    fn variable(&mut self, name: &str) {
+        match (self.variable)(name) {
+            Some(value) => self.push_value(&value),
+            None => self.text.push_str(name),
+        }
+    }
+
+    fn add(&mut self, number: usize, range: Range<usize>, choices: Vec<String>) {
+        let stop = self.stops.entry(number).or_insert_with(|| Stop {
+            ranges: Vec::new(),
+            choices: Vec::new(),
+        });
+
+        stop.ranges.push(range);
+
+        if stop.choices.is_empty() {
+            stop.choices = choices;
+        }
+    }
+
+    /// Skips to the closing brace of a construct, past nested ones.
+    fn skip(&mut self) {
+        let mut depth = 0;
+
+        while let Some(c) = self.peek() {
+            self.index += 1;
+
+            match c {
+                '\\' => self.index += 1,
+                '{' => depth += 1,
+                '}' if depth == 0 => return,
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.index;
+
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.index += 1;
+        }
+
+        self.chars[start..self.index]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn name(&mut self) -> Option<String> {
+        if !self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            return None;
+        }
+
+        let start = self.index;
+
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.index += 1;
+        }
+
+        Some(self.chars[start..self.index].iter().collect())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    /// Adds a character of the body, indenting new lines.
+    fn push(&mut self, c: char) {
+        match c {
+            '\n' => {
+                self.text.push('\n');
+                self.text.push_str(self.indent);
+            }
+            '\t' => self.text.push_str(self.unit),
+            c => self.text.push(c),
+        }
+    }
+
+    /// Adds the value of a variable, indenting its new lines.
+    fn push_value(&mut self, value: &str) {
+        self.text
+            .push_str(&value.replace('\n', &format!("\n{}", self.indent)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(body: &str) -> Expansion {
+        super::expand(body, "  ", "    ", |name| {
+            (name == "TM_FILENAME").then(|| String::from("main.rs"))
+        })
+    }
+
+    /// The bounds of the ranges of every stop.
+    fn ranges(expansion: &Expansion) -> Vec<Vec<(usize, usize)>> {
+        expansion
+            .stops
+            .iter()
+            .map(|stop| {
+                stop.ranges
+                    .iter()
+                    .map(|range| (range.start, range.end))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stops_are_visited_in_order_the_final_one_last() {
+        let expansion = expand("fn $2($1) {$0}");
+
+        assert_eq!(expansion.text, "fn () {}");
+        assert_eq!(
+            ranges(&expansion),
+            [vec![(4, 4)], vec![(3, 3)], vec![(7, 7)]]
+        );
+    }
+
+    #[test]
+    fn the_final_stop_defaults_to_the_end() {
+        let expansion = expand("${1:name}: ");
+
+        assert_eq!(expansion.text, "name: ");
+        assert_eq!(ranges(&expansion), [vec![(0, 4)], vec![(6, 6)]]);
+    }
+
+    #[test]
+    fn placeholders_are_mirrored() {
+        let expansion = expand("${1:a} = $1 + ${2:${1:a}}");
+
+        assert_eq!(expansion.text, "a = a + a");
+        assert_eq!(
+            ranges(&expansion),
+            [vec![(0, 1), (4, 5), (8, 9)], vec![(8, 9)], vec![(9, 9)]]
+        );
+    }
+
+    #[test]
+    fn choices_insert_the_first() {
+        let expansion = expand("${1|one,t\\,wo|}");
+
+        assert_eq!(expansion.text, "one");
+        assert_eq!(expansion.stops[0].choices, ["one", "t,wo"]);
+    }
+
+    #[test]
+    fn variables_resolve_or_fall_back() {
+        assert_eq!(expand("$TM_FILENAME").text, "main.rs");
+        assert_eq!(expand("${TM_FILENAME:x}").text, "main.rs");
+        assert_eq!(expand("${UNKNOWN:default}").text, "default");
+        assert_eq!(expand("$UNKNOWN").text, "UNKNOWN");
+    }
+
+    #[test]
+    fn escapes_and_lone_dollars_are_literal() {
+        assert_eq!(expand("\\$1 \\} \\\\ $ ${").text, "$1 } \\ $ ${");
+    }
+
+    #[test]
+    fn lines_are_indented() {
+        assert_eq!(expand("{\n\t$0\n}").text, "{\n      \n  }");
+    }
+
+    #[test]
+    fn transformations_are_skipped() {
+        let expansion = expand("${1/(.*)/${1:/upcase}/}x");
+
+        assert_eq!(expansion.text, "x");
+    }
+
+    #[test]
+    fn find_the_longest_prefix_starting_a_word() {
+        let snippet = |prefix: &str| Snippet {
+            name: prefix.to_owned(),
+            prefixes: vec![prefix.to_owned()],
+            body: String::new(),
+            description: String::new(),
+            extensions: Vec::new(),
+        };
+        let snippets = [snippet("fn"), snippet("pfn"), snippet("->")];
+
+        let found = |before| {
+            find(snippets.iter(), before).map(|(snippet, length)| (snippet.name.as_str(), length))
+        };
+
+        assert_eq!(found("  pfn"), Some(("pfn", 3)));
+        assert_eq!(found("  fn"), Some(("fn", 2)));
+        assert_eq!(found("xfn"), None);
+        assert_eq!(found("x->"), Some(("->", 2)));
+    }
+}
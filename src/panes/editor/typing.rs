@@ -0,0 +1,291 @@
+use iced::widget::text_editor::{self, Action, Edit, Motion};
+
+use super::{brackets, cursors, paste};
+
+/// The lines looked at to tell whether a file is indented with tabs.
+const SAMPLE: usize = 100;
+
+/// What one level of indentation is made of in a content: a tab when its
+/// lines are indented with tabs, spaces otherwise.
+pub fn unit(content: &text_editor::Content, tab_width: usize) -> String {
+    let tabs = content
+        .lines()
+        .take(SAMPLE)
+        .any(|line| line.starts_with('\t'));
+
+    if tabs {
+        String::from("\t")
+    } else {
+        " ".repeat(tab_width.max(1))
+    }
+}
+
+/// Types a character at the cursor of a content.
+///
+/// Brackets and quotes are closed as they are opened, or wrapped around the
+/// selection, and typing the character after the cursor steps over it.
+/// A closing bracket typed on a blank line is outdented.
+pub fn insert(content: &mut text_editor::Content, c: char, extension: &str, unit: &str) {
+    let closing = brackets::closing(c).or(brackets::quotes(extension).contains(&c).then_some(c));
+
+    if let Some(selection) = content.selection() {
+        match closing {
+            Some(close) => paste(content, format!("{c}{selection}{close}")),
+            None => content.perform(Action::Edit(Edit::Insert(c))),
+        }
+
+        return;
+    }
+
+    let (line, index) = content.cursor_position();
+    let text = content
+        .line(line)
+        .map(|text| text.to_owned())
+        .unwrap_or_default();
+    let (before, after) = text.split_at(index);
+
+    let is_closing = brackets::is_closing(c) || brackets::quotes(extension).contains(&c);
+
+    if is_closing && after.starts_with(c) {
+        content.perform(Action::Move(Motion::Right));
+
+        return;
+    }
+
+    if let Some(close) = closing
+        && closes(before, after, c, close)
+    {
+        content.perform(Action::Edit(Edit::Insert(c)));
+        content.perform(Action::Edit(Edit::Insert(close)));
+        content.perform(Action::Move(Motion::Left));
+
+        return;
+    }
+
+    if brackets::is_closing(c) && !before.is_empty() && before.trim().is_empty() {
+        let outdented = shallower(before, unit);
+
+        for _ in 0..before.chars().count() {
+            content.perform(Action::Select(Motion::Left));
+        }
+
+        paste(content, format!("{outdented}{c}"));
+
+        return;
+    }
+
+    content.perform(Action::Edit(Edit::Insert(c)));
+}
+
+/// Breaks the line at the cursor of a content, indenting the new line like
+/// the one broken, one level deeper after the start of a block, and one
+/// level shallower after leaving a block in indented languages.
+pub fn enter(content: &mut text_editor::Content, extension: &str, unit: &str) {
+    if content.selection().is_some() {
+        content.perform(Action::Edit(Edit::Backspace));
+    }
+
+    let (line, index) = content.cursor_position();
+    let text = content
+        .line(line)
+        .map(|text| text.to_owned())
+        .unwrap_or_default();
+    let (before, after) = text.split_at(index);
+
+    let indent = &before[..before.len() - before.trim_start().len()];
+    let code = before.trim_end();
+
+    let last = code.chars().next_back();
+    let opens = last.is_some_and(|c| brackets::closing(c).is_some())
+        || (extension == "py" && code.ends_with(':'));
+    let closed = last
+        .and_then(brackets::closing)
+        .is_some_and(|close| after.trim_start().starts_with(close));
+
+    if opens && closed {
+        paste(content, format!("\n{indent}{unit}\n{indent}"));
+        content.perform(Action::Move(Motion::Up));
+        content.perform(Action::Move(Motion::End));
+    } else if opens {
+        paste(content, format!("\n{indent}{unit}"));
+    } else if extension == "py" && leaves_block(code.trim_start()) {
+        paste(content, format!("\n{}", shallower(indent, unit)));
+    } else {
+        paste(content, format!("\n{indent}"));
+    }
+}
+
+/// Deletes the character before the cursor of a content, along with the
+/// closing bracket or quote right after it, or back to the previous level
+/// of indentation within the leading spaces of a line.
+pub fn backspace(content: &mut text_editor::Content, extension: &str, unit: &str) {
+    if content.selection().is_some() {
+        content.perform(Action::Edit(Edit::Backspace));
+
+        return;
+    }
+
+    let (line, index) = content.cursor_position();
+    let text = content
+        .line(line)
+        .map(|text| text.to_owned())
+        .unwrap_or_default();
+    let (before, after) = text.split_at(index);
+
+    let pair = before.chars().next_back().and_then(|c| {
+        brackets::closing(c).or(brackets::quotes(extension).contains(&c).then_some(c))
+    });
+
+    if let Some(close) = pair
+        && after.starts_with(close)
+    {
+        content.perform(Action::Edit(Edit::Delete));
+    } else if !before.is_empty() && before.chars().all(|c| c == ' ') && unit != "\t" {
+        for _ in 0..(before.len() - 1) % unit.len() + 1 {
+            content.perform(Action::Select(Motion::Left));
+        }
+    }
+
+    content.perform(Action::Edit(Edit::Backspace));
+}
+
+/// Indents the lines of the selection of a content, keeping them selected,
+/// or indents the cursor to the next level when nothing is selected.
+pub fn indent(content: &mut text_editor::Content, unit: &str) {
+    if content.selection().is_none() {
+        let (line, index) = content.cursor_position();
+        let text = content
+            .line(line)
+            .map(|text| text.to_owned())
+            .unwrap_or_default();
+
+        let indent = if unit == "\t" {
+            String::from("\t")
+        } else {
+            let columns = cursors::columns(&text[..index]);
+
+            " ".repeat(unit.len() - columns % unit.len())
+        };
+
+        paste(content, indent);
+
+        return;
+    }
+
+    change_lines(content, |line| {
+        if line.trim().is_empty() {
+            line.to_owned()
+        } else {
+            format!("{unit}{line}")
+        }
+    });
+}
+
+/// Outdents the lines of the selection of a content by a level, or the line
+/// of its cursor when nothing is selected.
+pub fn outdent(content: &mut text_editor::Content, unit: &str) {
+    change_lines(content, |line| {
+        let indent = &line[..line.len() - line.trim_start().len()];
+
+        format!("{}{}", shallower(indent, unit), line.trim_start())
+    });
+}
+
+/// Changes every line touched by the selection of a content, then selects
+/// them whole. A selection ending at the start of a line leaves it alone, and
+/// a cursor without selection stays on the same text.
+fn change_lines(content: &mut text_editor::Content, change: impl Fn(&str) -> String) {
+    let cursor = cursors::primary(content);
+    let (start, end) = (
+        cursor.anchor.min(cursor.head),
+        cursor.anchor.max(cursor.head),
+    );
+    let last = if end.1 == 0 && end.0 > start.0 {
+        end.0 - 1
+    } else {
+        end.0
+    };
+
+    let original: Vec<String> = (start.0..=last)
+        .map(|line| {
+            content
+                .line(line)
+                .map(|text| text.to_owned())
+                .unwrap_or_default()
+        })
+        .collect();
+    let lines: Vec<String> = original.iter().map(|line| change(line)).collect();
+
+    if lines == original {
+        cursors::select(content, cursor);
+
+        return;
+    }
+
+    cursors::select(
+        content,
+        cursors::Cursor {
+            anchor: (start.0, 0),
+            head: (last, original.last().map_or(0, String::len)),
+        },
+    );
+
+    paste(content, lines.join("\n"));
+
+    if cursor.anchor == cursor.head {
+        let shift = lines[0].len() as isize - original[0].len() as isize;
+
+        cursors::place(content, (start.0, start.1.saturating_add_signed(shift)));
+
+        return;
+    }
+
+    cursors::select(
+        content,
+        cursors::Cursor {
+            anchor: (start.0, 0),
+            head: (last, lines.last().map_or(0, String::len)),
+        },
+    );
+}
+
+/// An indentation less one level.
+fn shallower(indent: &str, unit: &str) -> String {
+    if let Some(rest) = indent.strip_prefix('\t') {
+        return rest.to_owned();
+    }
+
+    let spaces = indent.len() - indent.trim_start_matches(' ').len();
+
+    indent[spaces.min(unit.len())..].to_owned()
+}
+
+/// Whether a bracket or quote typed between two parts of a line should be
+/// closed right away: before blanks or closers, and for quotes, outside of
+/// words and strings.
+fn closes(before: &str, after: &str, open: char, close: char) -> bool {
+    let next = after.chars().next();
+
+    if !next.is_none_or(|c| c.is_whitespace() || brackets::is_closing(c) || ",;:".contains(c)) {
+        return false;
+    }
+
+    if open != close {
+        return true;
+    }
+
+    let previous = before.chars().next_back();
+
+    !previous.is_some_and(|c| c.is_alphanumeric() || c == '\\' || c == open)
+        && before.matches(open).count().is_multiple_of(2)
+}
+
+/// Whether a line of Python leaves the block it is in.
+fn leaves_block(line: &str) -> bool {
+    ["return", "pass", "break", "continue", "raise"]
+        .iter()
+        .any(|keyword| {
+            line.strip_prefix(keyword)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+        })
+}
@@ -0,0 +1,111 @@
+use iced::widget::text_editor;
+
+use super::cursors::{self, Position};
+use super::fold::{self, Fold};
+
+/// The steps kept at most.
+const LIMIT: usize = 200;
+
+/// The past and undone states of a buffer.
+#[derive(Default)]
+pub struct Undo {
+    undone: Vec<Snapshot>,
+    done: Vec<Snapshot>,
+    /// Whether the last step is a run of typed characters, which the next
+    /// typed character joins.
+    typing: bool,
+}
+
+struct Snapshot {
+    text: String,
+    cursor: Position,
+}
+
+impl Undo {
+    /// Records the content and its folded lines before an edit. Typed
+    /// characters are grouped into a single step until something else
+    /// happens.
+    pub fn record(
+        &mut self,
+        content: &text_editor::Content,
+        folds: &[Fold],
+        edit: Option<&text_editor::Edit>,
+    ) {
+        let typing = matches!(edit, Some(text_editor::Edit::Insert(c)) if !c.is_whitespace());
+
+        if !(typing && self.typing) {
+            let snapshot = Snapshot::new(content, folds);
+
+            self.push(snapshot.text, snapshot.cursor);
+        }
+
+        self.typing = typing;
+    }
+
+    /// Records a text and cursor as a step of their own, e.g. before a
+    /// command changing the content.
+    pub fn push(&mut self, text: String, cursor: Position) {
+        self.done.push(Snapshot { text, cursor });
+
+        if self.done.len() > LIMIT {
+            self.done.remove(0);
+        }
+
+        self.typing = false;
+        self.undone.clear();
+    }
+
+    /// Ends the current run of typed characters.
+    pub fn interrupt(&mut self) {
+        self.typing = false;
+    }
+
+    /// Restores the content before the last step, unfolded, returning
+    /// whether there was one.
+    pub fn undo(&mut self, content: &mut text_editor::Content, folds: &mut Vec<Fold>) -> bool {
+        let Some(snapshot) = self.done.pop() else {
+            return false;
+        };
+
+        self.undone.push(Snapshot::new(content, folds));
+        self.typing = false;
+
+        folds.clear();
+        snapshot.restore(content);
+
+        true
+    }
+
+    /// Restores the content before the last undo, unfolded, returning
+    /// whether there was one.
+    pub fn redo(&mut self, content: &mut text_editor::Content, folds: &mut Vec<Fold>) -> bool {
+        let Some(snapshot) = self.undone.pop() else {
+            return false;
+        };
+
+        self.done.push(Snapshot::new(content, folds));
+        self.typing = false;
+
+        folds.clear();
+        snapshot.restore(content);
+
+        true
+    }
+}
+
+impl Snapshot {
+    fn new(content: &text_editor::Content, folds: &[Fold]) -> Self {
+        let (line, index) = content.cursor_position();
+
+        Self {
+            text: fold::text(content, folds),
+            cursor: (fold::full_line(folds, line), index),
+        }
+    }
+
+    fn restore(self, content: &mut text_editor::Content) {
+        *content = text_editor::Content::with_text(&self.text);
+
+        cursors::place(content, self.cursor);
+    }
+}
@@ -0,0 +1,1359 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use iced::keyboard;
+use iced::widget::text_editor::{self, Edit};
+
+use super::cursors::{self, Cursor};
+
+mod motion;
+
+use motion::{Kind, Motion};
+
+/// A modal editing layer over the content of a buffer.
+#[derive(Clone, Default)]
+pub struct Vim {
+    pub mode: Mode,
+    /// The keys of the command being typed.
+    pending: Vec<Key>,
+    /// The ex command being typed, after `:`.
+    pub command: Option<String>,
+    registers: BTreeMap<char, Register>,
+    /// The keys of the last change, repeated by `.`.
+    last_change: Vec<Key>,
+    /// The keys of the change in progress, while it continues in insert mode.
+    change: Option<Vec<Key>>,
+    /// The ends of the visual selection, both included.
+    anchor: usize,
+    head: usize,
+    /// The column kept when moving up and down.
+    column: Option<usize>,
+    /// The last `f`, `t`, `F` or `T`, repeated by `;` and `,`.
+    find: Option<Motion>,
+    replaying: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Escape,
+    Enter,
+    Backspace,
+    Control(char),
+}
+
+/// What the editor should do after a key, besides editing the content.
+pub enum Effect {
+    None,
+    Save,
+    /// The buffer should be closed, even if unsaved when forced.
+    Quit {
+        force: bool,
+    },
+    SaveAndQuit,
+    Undo(usize),
+    Redo(usize),
+    /// The text should be written to the clipboard.
+    Copy(String),
+    /// The clipboard should be put into the content.
+    Paste {
+        before: bool,
+        count: usize,
+    },
+    Error(String),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Register {
+    text: String,
+    linewise: bool,
+}
+
+struct Command {
+    register: Option<char>,
+    count: Option<usize>,
+    verb: Verb,
+}
+
+enum Verb {
+    Move(Motion),
+    Operate(Operator, Object),
+    /// A command of a single key, e.g. `p` or `i`.
+    Single(char),
+    /// `r`, replacing characters with another one.
+    Replace(char),
+    /// A text object selected in visual mode.
+    Select(char, bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+    ToggleCase,
+}
+
+#[derive(Clone, Copy)]
+enum Object {
+    Motion(Motion),
+    /// A text object, e.g. `w` for `iw` or `aw`.
+    Text(char, bool),
+    /// The lines of the cursor, e.g. for `dd`.
+    Lines,
+    /// The visual selection.
+    Selection,
+}
+
+enum Parse<T> {
+    Pending,
+    Invalid,
+    Done(T),
+}
+
+impl Mode {
+    pub fn label(self) -> &'static str {
+        match self {
+            Mode::Normal => "NORMAL",
+            Mode::Insert => "INSERT",
+            Mode::Visual => "VISUAL",
+            Mode::VisualLine => "VISUAL LINE",
+        }
+    }
+
+    fn is_visual(self) -> bool {
+        matches!(self, Mode::Visual | Mode::VisualLine)
+    }
+}
+
+impl Key {
+    /// The key to be handled by Vim, if any. Keys left to the text editor
+    /// keep their usual bindings, e.g. typing in insert mode.
+    pub fn from_key_press(key_press: &text_editor::KeyPress, mode: Mode) -> Option<Self> {
+        let modifiers = key_press.modifiers;
+
+        match key_press.key.as_ref() {
+            keyboard::Key::Named(keyboard::key::Named::Escape) => Some(Key::Escape),
+            keyboard::Key::Character("[") if modifiers.control() => Some(Key::Escape),
+            _ if mode == Mode::Insert => None,
+            keyboard::Key::Character("r") if modifiers.control() => Some(Key::Control('r')),
+            _ if modifiers.command() || modifiers.alt() => None,
+            keyboard::Key::Named(keyboard::key::Named::Enter) => Some(Key::Enter),
+            keyboard::Key::Named(keyboard::key::Named::Backspace) => Some(Key::Backspace),
+            keyboard::Key::Named(keyboard::key::Named::Space) => Some(Key::Char(' ')),
+            _ => key_press
+                .text
+                .as_deref()?
+                .chars()
+                .next()
+                .filter(|c| !c.is_control())
+                .map(Key::Char),
+        }
+    }
+}
+
+impl Vim {
+    /// The mode, and the keys of the command being typed.
+    pub fn status(&self) -> String {
+        if let Some(command) = &self.command {
+            return format!(":{command}");
+        }
+
+        let pending: String = self
+            .pending
+            .iter()
+            .map(|key| match key {
+                Key::Char(c) => *c,
+                _ => ' ',
+            })
+            .collect();
+
+        format!("-- {} -- {pending}", self.mode.label())
+            .trim_end()
+            .to_owned()
+    }
+
+    /// Forgets the state tied to the text of a buffer, when another one is
+    /// edited, keeping the registers and the last change.
+    pub fn reset(&mut self) {
+        *self = Self {
+            registers: std::mem::take(&mut self.registers),
+            last_change: std::mem::take(&mut self.last_change),
+            find: self.find.take(),
+            ..Self::default()
+        };
+    }
+
+    /// Handles a key, with `indent` as the unit of indentation.
+    pub fn key(&mut self, key: Key, content: &mut text_editor::Content, indent: &str) -> Effect {
+        if let Some(command) = &mut self.command {
+            match key {
+                Key::Char(c) => command.push(c),
+                Key::Backspace => {
+                    if command.pop().is_none() {
+                        self.command = None;
+                    }
+                }
+                Key::Enter => {
+                    let command = self.command.take().unwrap_or_default();
+
+                    return self.ex(&command, content);
+                }
+                Key::Escape | Key::Control(_) => self.command = None,
+            }
+
+            return Effect::None;
+        }
+
+        if self.mode == Mode::Insert {
+            match key {
+                Key::Escape => self.escape(content),
+                Key::Char(c) => content.perform(text_editor::Action::Edit(Edit::Insert(c))),
+                Key::Enter => content.perform(text_editor::Action::Edit(Edit::Enter)),
+                Key::Backspace => content.perform(text_editor::Action::Edit(Edit::Backspace)),
+                Key::Control(_) => {}
+            }
+
+            return Effect::None;
+        }
+
+        if key == Key::Escape {
+            self.pending.clear();
+
+            if self.mode.is_visual() {
+                self.mode = Mode::Normal;
+                place(content, self.head);
+            }
+
+            return Effect::None;
+        }
+
+        self.pending.push(key);
+
+        let keys = self.pending.clone();
+
+        match self.parse(&keys) {
+            Parse::Pending => Effect::None,
+            Parse::Invalid => {
+                self.pending.clear();
+
+                Effect::None
+            }
+            Parse::Done(command) => {
+                self.pending.clear();
+
+                // `;` and `,` repeat the last search, but are not one.
+                if let Verb::Move(motion) | Verb::Operate(_, Object::Motion(motion)) = command.verb
+                    && let Motion::Find { .. } = motion
+                    && let [.., Key::Char('f' | 't' | 'F' | 'T'), _] = keys[..]
+                {
+                    self.find = Some(motion);
+                }
+
+                let was_visual = self.mode.is_visual();
+                let is_change = command.is_change();
+                let effect = self.execute(command, content, indent);
+
+                if is_change && !was_visual && !self.replaying {
+                    if self.mode == Mode::Insert {
+                        self.change = Some(keys);
+                    } else {
+                        self.last_change = keys;
+                    }
+                }
+
+                effect
+            }
+        }
+    }
+
+    /// Records an edit typed in insert mode, to be repeated by `.`.
+    pub fn record(&mut self, edit: &Edit) {
+        let Some(change) = &mut self.change else {
+            return;
+        };
+
+        match edit {
+            Edit::Insert(c) => change.push(Key::Char(*c)),
+            Edit::Paste(text) => change.extend(text.chars().map(|c| match c {
+                '\n' => Key::Enter,
+                c => Key::Char(c),
+            })),
+            Edit::Enter => change.push(Key::Enter),
+            Edit::Backspace => change.push(Key::Backspace),
+            Edit::Delete => {}
+        }
+    }
+
+    /// Puts a text, e.g. from the clipboard, after or before the cursor.
+    pub fn paste(
+        &mut self,
+        content: &mut text_editor::Content,
+        text: String,
+        before: bool,
+        count: usize,
+    ) {
+        let linewise = text.ends_with('\n');
+
+        self.put(content, &Register { text, linewise }, before, count);
+    }
+
+    fn parse(&self, keys: &[Key]) -> Parse<Command> {
+        let mut rest = keys;
+        let mut register = None;
+
+        if let [Key::Char('"'), tail @ ..] = rest {
+            match tail {
+                [] => return Parse::Pending,
+                [Key::Char(name), tail @ ..] => {
+                    register = Some(*name);
+                    rest = tail;
+                }
+                _ => return Parse::Invalid,
+            }
+        }
+
+        let (count, rest) = count(rest);
+
+        let [first, tail @ ..] = rest else {
+            return Parse::Pending;
+        };
+
+        let done = |verb| {
+            Parse::Done(Command {
+                register,
+                count,
+                verb,
+            })
+        };
+
+        let operator = match first {
+            Key::Char('d') => Some(Operator::Delete),
+            Key::Char('c') => Some(Operator::Change),
+            Key::Char('y') => Some(Operator::Yank),
+            Key::Char('>') => Some(Operator::Indent),
+            Key::Char('<') => Some(Operator::Outdent),
+            Key::Char('~') if self.mode.is_visual() => Some(Operator::ToggleCase),
+            _ => None,
+        };
+
+        if let Some(operator) = operator {
+            if self.mode.is_visual() {
+                return done(Verb::Operate(operator, Object::Selection));
+            }
+
+            let (inner, tail) = self::count(tail);
+
+            let count = match (count, inner) {
+                (Some(count), Some(inner)) => Some(count.saturating_mul(inner)),
+                (count, inner) => count.or(inner),
+            };
+
+            let done = |verb| {
+                Parse::Done(Command {
+                    register,
+                    count,
+                    verb,
+                })
+            };
+
+            return match tail {
+                [] => Parse::Pending,
+                [key, ..] if key == first => done(Verb::Operate(operator, Object::Lines)),
+                [Key::Char('i' | 'a')] => Parse::Pending,
+                [Key::Char(around @ ('i' | 'a')), Key::Char(object), ..] => done(Verb::Operate(
+                    operator,
+                    Object::Text(*object, *around == 'a'),
+                )),
+                keys => match self.motion(keys) {
+                    Parse::Pending => Parse::Pending,
+                    Parse::Invalid => Parse::Invalid,
+                    Parse::Done(motion) => done(Verb::Operate(operator, Object::Motion(motion))),
+                },
+            };
+        }
+
+        let delete = |motion| Verb::Operate(Operator::Delete, Object::Motion(motion));
+        let change = |motion| Verb::Operate(Operator::Change, Object::Motion(motion));
+
+        match (first, tail) {
+            (Key::Char('r'), []) => Parse::Pending,
+            (Key::Char('r'), [Key::Char(c), ..]) => done(Verb::Replace(*c)),
+            (Key::Char('i' | 'a'), []) if self.mode.is_visual() => Parse::Pending,
+            (Key::Char(around @ ('i' | 'a')), [Key::Char(object), ..]) if self.mode.is_visual() => {
+                done(Verb::Select(*object, *around == 'a'))
+            }
+            (Key::Char('x'), _) if !self.mode.is_visual() => done(delete(Motion::Right)),
+            (Key::Char('X'), _) if !self.mode.is_visual() => done(delete(Motion::Left)),
+            (Key::Char('s'), _) if !self.mode.is_visual() => done(change(Motion::Right)),
+            (Key::Char('D'), _) if !self.mode.is_visual() => done(delete(Motion::LineEnd)),
+            (Key::Char('C'), _) if !self.mode.is_visual() => done(change(Motion::LineEnd)),
+            (Key::Char('S'), _) if !self.mode.is_visual() => {
+                done(Verb::Operate(Operator::Change, Object::Lines))
+            }
+            (Key::Char('Y'), _) if !self.mode.is_visual() => {
+                done(Verb::Operate(Operator::Yank, Object::Lines))
+            }
+            (Key::Char('x' | 'X' | 'D'), _) => {
+                done(Verb::Operate(Operator::Delete, Object::Selection))
+            }
+            (Key::Char('s' | 'S' | 'C'), _) => {
+                done(Verb::Operate(Operator::Change, Object::Selection))
+            }
+            (Key::Char('Y'), _) => done(Verb::Operate(Operator::Yank, Object::Selection)),
+            (
+                Key::Char(
+                    c @ ('p' | 'P' | 'J' | '~' | 'u' | 'U' | 'i' | 'a' | 'I' | 'A' | 'o' | 'O'
+                    | 'v' | 'V' | '.' | ':'),
+                ),
+                _,
+            ) => done(Verb::Single(*c)),
+            (Key::Control('r'), _) => done(Verb::Single('R')),
+            _ => match self.motion(rest) {
+                Parse::Pending => Parse::Pending,
+                Parse::Invalid => Parse::Invalid,
+                Parse::Done(motion) => done(Verb::Move(motion)),
+            },
+        }
+    }
+
+    fn motion(&self, keys: &[Key]) -> Parse<Motion> {
+        let motion = match keys {
+            [] | [Key::Char('g' | 'f' | 't' | 'F' | 'T')] => return Parse::Pending,
+            [Key::Char('h') | Key::Backspace, ..] => Motion::Left,
+            [Key::Char('l' | ' '), ..] => Motion::Right,
+            [Key::Char('j'), ..] => Motion::Down,
+            [Key::Char('k'), ..] => Motion::Up,
+            [Key::Char('w'), ..] => Motion::WordStart { big: false },
+            [Key::Char('W'), ..] => Motion::WordStart { big: true },
+            [Key::Char('e'), ..] => Motion::WordEnd { big: false },
+            [Key::Char('E'), ..] => Motion::WordEnd { big: true },
+            [Key::Char('b'), ..] => Motion::WordBack { big: false },
+            [Key::Char('B'), ..] => Motion::WordBack { big: true },
+            [Key::Char('0'), ..] => Motion::LineStart,
+            [Key::Char('^'), ..] => Motion::FirstNonBlank,
+            [Key::Char('$'), ..] => Motion::LineEnd,
+            [Key::Enter | Key::Char('+'), ..] => Motion::NextLine,
+            [Key::Char('-'), ..] => Motion::PreviousLine,
+            [Key::Char('G'), ..] => Motion::LastLine,
+            [Key::Char('g'), Key::Char('g'), ..] => Motion::FirstLine,
+            [Key::Char('%'), ..] => Motion::Bracket,
+            [Key::Char('}'), ..] => Motion::ParagraphForward,
+            [Key::Char('{'), ..] => Motion::ParagraphBack,
+            [
+                Key::Char(kind @ ('f' | 't' | 'F' | 'T')),
+                Key::Char(target),
+                ..,
+            ] => Motion::Find {
+                target: *target,
+                backward: kind.is_ascii_uppercase(),
+                before: kind.eq_ignore_ascii_case(&'t'),
+            },
+            [Key::Char(repeat @ (';' | ',')), ..] => match self.find {
+                Some(Motion::Find {
+                    target,
+                    backward,
+                    before,
+                }) => Motion::Find {
+                    target,
+                    backward: backward != (*repeat == ','),
+                    before,
+                },
+                _ => return Parse::Invalid,
+            },
+            _ => return Parse::Invalid,
+        };
+
+        Parse::Done(motion)
+    }
+
+    fn execute(
+        &mut self,
+        command: Command,
+        content: &mut text_editor::Content,
+        indent: &str,
+    ) -> Effect {
+        let text = text(content);
+        let offset = if self.mode.is_visual() {
+            self.head
+        } else {
+            cursors::offset(content, content.cursor_position())
+        };
+
+        let count = command.count.unwrap_or(1);
+
+        match command.verb {
+            Verb::Move(motion) => {
+                let column = self.column.unwrap_or_else(|| motion::column(&text, offset));
+
+                let Some(target) = motion::apply(&text, offset, motion, command.count, column)
+                else {
+                    return Effect::None;
+                };
+
+                self.column = match motion {
+                    Motion::LineEnd => Some(usize::MAX),
+                    motion if motion.is_horizontal() => None,
+                    _ => Some(column),
+                };
+
+                self.go(content, &text, target.offset);
+
+                Effect::None
+            }
+            Verb::Operate(operator, object) => {
+                self.column = None;
+
+                let Some((range, linewise)) =
+                    self.range(&text, offset, operator, object, command.count)
+                else {
+                    return Effect::None;
+                };
+
+                let keep = matches!(object, Object::Lines) && operator == Operator::Yank;
+
+                self.operate(
+                    content,
+                    &text,
+                    operator,
+                    range,
+                    linewise,
+                    command.register,
+                    keep,
+                    indent,
+                )
+            }
+            Verb::Replace(c) => {
+                if self.mode.is_visual() {
+                    let (range, _) = self.selection(&text);
+                    let replaced: String = text[range.clone()]
+                        .chars()
+                        .map(|current| if current == '\n' { '\n' } else { c })
+                        .collect();
+
+                    self.mode = Mode::Normal;
+                    replace(content, range.clone(), &replaced);
+                    place(content, range.start);
+
+                    return Effect::None;
+                }
+
+                let end = motion::line_end(&text, offset);
+                let chars: Vec<_> = text[offset..end].char_indices().take(count).collect();
+
+                if chars.len() < count {
+                    return Effect::None;
+                }
+
+                let last = offset + chars[count - 1].0;
+                let range = offset..motion::next(&text, last);
+
+                replace(content, range, &c.to_string().repeat(count));
+                place(content, offset + (count - 1) * c.len_utf8());
+
+                Effect::None
+            }
+            Verb::Select(object, around) => {
+                let Some((range, linewise)) = motion::object(&text, offset, object, around) else {
+                    return Effect::None;
+                };
+
+                if linewise {
+                    self.mode = Mode::VisualLine;
+                }
+
+                self.anchor = range.start;
+                self.head = motion::previous(&text, range.end).max(range.start);
+                self.show(content, &text);
+
+                Effect::None
+            }
+            Verb::Single(c) => self.single(c, content, &text, offset, &command, indent),
+        }
+    }
+
+    /// The text taken by an operator, and whether it is made of whole lines.
+    fn range(
+        &mut self,
+        text: &str,
+        offset: usize,
+        operator: Operator,
+        object: Object,
+        count: Option<usize>,
+    ) -> Option<(Range<usize>, bool)> {
+        match object {
+            Object::Selection => Some(self.selection(text)),
+            Object::Lines => {
+                let line = motion::line_of(text, offset);
+                let last = line
+                    .saturating_add(count.unwrap_or(1) - 1)
+                    .min(motion::line_count(text) - 1);
+
+                Some((
+                    motion::line_offset(text, line)
+                        ..motion::line_end(text, motion::line_offset(text, last)),
+                    true,
+                ))
+            }
+            Object::Text(object, around) => motion::object(text, offset, object, around),
+            Object::Motion(motion) => {
+                let column = motion::column(text, offset);
+
+                // `cw` changes to the end of the word, as `ce`.
+                let motion = match motion {
+                    Motion::WordStart { big }
+                        if operator == Operator::Change
+                            && text[offset..]
+                                .chars()
+                                .next()
+                                .is_some_and(|c| !c.is_whitespace()) =>
+                    {
+                        Motion::WordEnd { big }
+                    }
+                    motion => motion,
+                };
+
+                let mut target = motion::apply(text, offset, motion, count, column)?;
+
+                // `cw` on the last character of a word stays on it.
+                if let Motion::WordEnd { .. } = motion
+                    && operator == Operator::Change
+                    && count.is_none()
+                    && word_ends_at(text, offset)
+                {
+                    target.offset = offset;
+                }
+
+                // `dw` on the last word of a line stops at its end.
+                if let Motion::WordStart { .. } = motion
+                    && motion::line_of(text, target.offset) > motion::line_of(text, offset)
+                {
+                    target.offset = motion::line_end(text, offset).max(offset);
+                }
+
+                let (start, end) = (offset.min(target.offset), offset.max(target.offset));
+
+                Some(match target.kind {
+                    Kind::Exclusive => (start..end, false),
+                    Kind::Inclusive => {
+                        let end = match text[end..].chars().next() {
+                            Some(c) if c != '\n' => end + c.len_utf8(),
+                            _ => end,
+                        };
+
+                        (start..end, false)
+                    }
+                    Kind::Linewise => (
+                        motion::line_start(text, start)..motion::line_end(text, end),
+                        true,
+                    ),
+                })
+            }
+        }
+    }
+
+    /// The visual selection, and whether it is made of whole lines.
+    fn selection(&self, text: &str) -> (Range<usize>, bool) {
+        let (start, end) = (self.anchor.min(self.head), self.anchor.max(self.head));
+
+        if self.mode == Mode::VisualLine {
+            return (
+                motion::line_start(text, start)..motion::line_end(text, end),
+                true,
+            );
+        }
+
+        (start..motion::next(text, end), false)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn operate(
+        &mut self,
+        content: &mut text_editor::Content,
+        text: &str,
+        operator: Operator,
+        range: Range<usize>,
+        linewise: bool,
+        register: Option<char>,
+        keep: bool,
+        indent: &str,
+    ) -> Effect {
+        self.mode = Mode::Normal;
+
+        let taken = if linewise {
+            format!("{}\n", &text[range.clone()])
+        } else {
+            text[range.clone()].to_owned()
+        };
+
+        match operator {
+            Operator::Yank => {
+                let effect = self.write(register, taken, linewise, true);
+
+                if !keep {
+                    place(content, range.start);
+                }
+
+                effect
+            }
+            Operator::Delete => {
+                let effect = self.write(register, taken, linewise, false);
+
+                if linewise {
+                    // The line break before the last line goes with it.
+                    let range = if range.end < text.len() {
+                        range.start..range.end + 1
+                    } else {
+                        range.start.saturating_sub(1)..range.end
+                    };
+
+                    replace(content, range.clone(), "");
+
+                    let text = self::text(content);
+                    let start = motion::line_start(&text, range.start.min(text.len()));
+
+                    place(content, motion::first_non_blank(&text, start));
+                } else {
+                    replace(content, range.clone(), "");
+
+                    let text = self::text(content);
+
+                    place(content, rest(&text, range.start));
+                }
+
+                effect
+            }
+            Operator::Change => {
+                let effect = self.write(register, taken, linewise, false);
+
+                // Lines keep their indentation.
+                let start = if linewise {
+                    motion::first_non_blank(text, range.start)
+                } else {
+                    range.start
+                };
+
+                replace(content, start..range.end, "");
+                place(content, start);
+
+                self.mode = Mode::Insert;
+
+                effect
+            }
+            Operator::Indent | Operator::Outdent => {
+                let start = motion::line_start(text, range.start);
+                let end = motion::line_end(text, range.end.max(range.start));
+
+                let lines: Vec<String> = text[start..end]
+                    .split('\n')
+                    .map(|line| {
+                        if operator == Operator::Indent {
+                            if line.is_empty() {
+                                String::new()
+                            } else {
+                                format!("{indent}{line}")
+                            }
+                        } else {
+                            let blanks = line.len() - line.trim_start_matches(' ').len();
+
+                            match line.strip_prefix('\t') {
+                                Some(rest) => rest.to_owned(),
+                                None => line[blanks.min(indent.len())..].to_owned(),
+                            }
+                        }
+                    })
+                    .collect();
+
+                replace(content, start..end, &lines.join("\n"));
+
+                let text = self::text(content);
+
+                place(content, motion::first_non_blank(&text, start));
+
+                Effect::None
+            }
+            Operator::ToggleCase => {
+                let toggled: String = text[range.clone()].chars().map(toggle_case).collect();
+
+                replace(content, range.clone(), &toggled);
+                place(content, range.start);
+
+                Effect::None
+            }
+        }
+    }
+
+    fn single(
+        &mut self,
+        c: char,
+        content: &mut text_editor::Content,
+        text: &str,
+        offset: usize,
+        command: &Command,
+        indent: &str,
+    ) -> Effect {
+        let count = command.count.unwrap_or(1);
+
+        match c {
+            'p' | 'P' => {
+                let before = c == 'P';
+
+                if self.mode.is_visual() {
+                    let (range, _) = self.selection(text);
+
+                    self.mode = Mode::Normal;
+
+                    let Some(register) = self.read(command.register) else {
+                        return Effect::None;
+                    };
+
+                    replace(content, range.clone(), register.text.trim_end_matches('\n'));
+                    place(content, range.start);
+
+                    return Effect::None;
+                }
+
+                if matches!(command.register, Some('+' | '*')) {
+                    return Effect::Paste { before, count };
+                }
+
+                if let Some(register) = self.read(command.register) {
+                    self.put(content, &register, before, count);
+                }
+
+                Effect::None
+            }
+            'J' => {
+                let (start, lines) = if self.mode.is_visual() {
+                    let (range, _) = self.selection(text);
+
+                    self.mode = Mode::Normal;
+
+                    let lines = text[range.clone()].matches('\n').count().max(1);
+
+                    (range.start, lines)
+                } else {
+                    (offset, count.saturating_sub(1).max(1))
+                };
+
+                let mut cursor = start;
+
+                for _ in 0..lines {
+                    let text = self::text(content);
+                    let end = motion::line_end(&text, cursor);
+
+                    if end >= text.len() {
+                        break;
+                    }
+
+                    let next = motion::first_non_blank(&text, end + 1);
+                    let before = text[..end].trim_end_matches([' ', '\t']).len();
+                    let separator = if before == motion::line_start(&text, end)
+                        || text[next..].starts_with(')')
+                        || next == motion::line_end(&text, next)
+                    {
+                        ""
+                    } else {
+                        " "
+                    };
+
+                    replace(content, before..next, separator);
+                    cursor = before;
+                }
+
+                place(content, cursor);
+
+                Effect::None
+            }
+            '~' => {
+                let end = motion::line_end(text, offset);
+                let range = offset
+                    ..text[offset..end]
+                        .char_indices()
+                        .nth(count)
+                        .map_or(end, |(index, _)| offset + index);
+
+                let toggled: String = text[range.clone()].chars().map(toggle_case).collect();
+
+                replace(content, range.clone(), &toggled);
+
+                let text = self::text(content);
+
+                place(content, rest(&text, range.end));
+
+                Effect::None
+            }
+            'u' | 'U' if self.mode.is_visual() => {
+                let (range, _) = self.selection(text);
+                let changed = if c == 'u' {
+                    text[range.clone()].to_lowercase()
+                } else {
+                    text[range.clone()].to_uppercase()
+                };
+
+                self.mode = Mode::Normal;
+                replace(content, range.clone(), &changed);
+                place(content, range.start);
+
+                Effect::None
+            }
+            'u' => Effect::Undo(count),
+            'U' => Effect::None,
+            'R' => Effect::Redo(count),
+            'i' | 'a' | 'I' | 'A' | 'o' | 'O' if self.mode.is_visual() => Effect::None,
+            'i' => self.insert(content, offset),
+            'a' => {
+                let offset = match text[offset..].chars().next() {
+                    Some(c) if c != '\n' => offset + c.len_utf8(),
+                    _ => offset,
+                };
+
+                self.insert(content, offset)
+            }
+            'I' => {
+                let start = motion::line_start(text, offset);
+
+                self.insert(content, motion::first_non_blank(text, start))
+            }
+            'A' => self.insert(content, motion::line_end(text, offset)),
+            'o' => {
+                let end = motion::line_end(text, offset);
+                let indentation = leading(text, offset);
+
+                replace(content, end..end, &format!("\n{indentation}"));
+
+                self.insert(content, end + 1 + indentation.len())
+            }
+            'O' => {
+                let start = motion::line_start(text, offset);
+                let indentation = leading(text, offset);
+
+                replace(content, start..start, &format!("{indentation}\n"));
+
+                self.insert(content, start + indentation.len())
+            }
+            'v' | 'V' => {
+                let mode = if c == 'v' {
+                    Mode::Visual
+                } else {
+                    Mode::VisualLine
+                };
+
+                if self.mode == mode {
+                    self.mode = Mode::Normal;
+                    place(content, self.head);
+
+                    return Effect::None;
+                }
+
+                if !self.mode.is_visual() {
+                    self.anchor = offset;
+                    self.head = offset;
+                }
+
+                self.mode = mode;
+                self.show(content, text);
+
+                Effect::None
+            }
+            '.' => {
+                let change = self.last_change.clone();
+
+                self.replaying = true;
+
+                for _ in 0..count {
+                    for key in &change {
+                        let _ = self.key(*key, content, indent);
+                    }
+                }
+
+                self.replaying = false;
+
+                Effect::None
+            }
+            ':' => {
+                self.command = Some(String::new());
+
+                Effect::None
+            }
+            _ => Effect::None,
+        }
+    }
+
+    /// Moves the cursor, or the head of the visual selection.
+    fn go(&mut self, content: &mut text_editor::Content, text: &str, offset: usize) {
+        if self.mode.is_visual() {
+            self.head = offset.min(text.len());
+            self.show(content, text);
+        } else {
+            place(content, rest(text, offset));
+        }
+    }
+
+    /// Selects the visual selection in the content.
+    fn show(&self, content: &mut text_editor::Content, text: &str) {
+        let (range, _) = self.selection(text);
+
+        let (anchor, head) = if self.head < self.anchor {
+            (range.end, range.start)
+        } else {
+            (range.start, range.end)
+        };
+
+        cursors::select(
+            content,
+            Cursor {
+                anchor: cursors::position(content, anchor),
+                head: cursors::position(content, head),
+            },
+        );
+    }
+
+    fn insert(&mut self, content: &mut text_editor::Content, offset: usize) -> Effect {
+        place(content, offset);
+        self.mode = Mode::Insert;
+
+        Effect::None
+    }
+
+    /// Leaves insert mode, stepping back onto the last inserted character.
+    fn escape(&mut self, content: &mut text_editor::Content) {
+        self.mode = Mode::Normal;
+
+        if let Some(mut change) = self.change.take() {
+            change.push(Key::Escape);
+
+            if !self.replaying {
+                self.last_change = change;
+            }
+        }
+
+        let text = text(content);
+        let offset = cursors::offset(content, content.cursor_position());
+
+        if offset > motion::line_start(&text, offset) {
+            place(content, motion::previous(&text, offset));
+        }
+    }
+
+    fn put(
+        &mut self,
+        content: &mut text_editor::Content,
+        register: &Register,
+        before: bool,
+        count: usize,
+    ) {
+        let text = text(content);
+        let offset = cursors::offset(content, content.cursor_position());
+        let pasted = register.text.repeat(count);
+
+        if register.linewise {
+            let pasted = if pasted.ends_with('\n') {
+                pasted
+            } else {
+                format!("{pasted}\n")
+            };
+
+            let start = if before {
+                motion::line_start(&text, offset)
+            } else {
+                motion::line_end(&text, offset) + 1
+            };
+
+            if start > text.len() {
+                // After the last line, which has no line break.
+                replace(
+                    content,
+                    text.len()..text.len(),
+                    &format!("\n{}", pasted.trim_end_matches('\n')),
+                );
+            } else {
+                replace(content, start..start, &pasted);
+            }
+
+            let text = self::text(content);
+
+            place(
+                content,
+                motion::first_non_blank(&text, start.min(text.len())),
+            );
+        } else {
+            let start = match text[offset..].chars().next() {
+                Some(c) if !before && c != '\n' => offset + c.len_utf8(),
+                _ => offset,
+            };
+
+            replace(content, start..start, &pasted);
+
+            let text = self::text(content);
+
+            place(
+                content,
+                motion::previous(&text, start + pasted.len()).max(start),
+            );
+        }
+    }
+
+    fn write(
+        &mut self,
+        register: Option<char>,
+        text: String,
+        linewise: bool,
+        yank: bool,
+    ) -> Effect {
+        let value = Register { text, linewise };
+
+        let effect = match register {
+            Some('_') => return Effect::None,
+            Some(name @ 'a'..='z') => {
+                self.registers.insert(name, value.clone());
+
+                Effect::None
+            }
+            Some(name @ 'A'..='Z') => {
+                let register = self.registers.entry(name.to_ascii_lowercase()).or_default();
+
+                register.text.push_str(&value.text);
+                register.linewise |= value.linewise;
+
+                Effect::None
+            }
+            Some('+' | '*') => Effect::Copy(value.text.clone()),
+            _ => Effect::None,
+        };
+
+        if yank {
+            self.registers.insert('0', value.clone());
+        }
+
+        self.registers.insert('"', value);
+
+        effect
+    }
+
+    fn read(&self, register: Option<char>) -> Option<Register> {
+        let name = register.unwrap_or('"').to_ascii_lowercase();
+
+        self.registers.get(&name).cloned()
+    }
+
+    fn ex(&mut self, command: &str, content: &mut text_editor::Content) -> Effect {
+        match command.trim() {
+            "" => Effect::None,
+            "w" => Effect::Save,
+            "q" => Effect::Quit { force: false },
+            "q!" => Effect::Quit { force: true },
+            "wq" | "x" => Effect::SaveAndQuit,
+            command => match command.parse::<usize>() {
+                Ok(line) => {
+                    let text = text(content);
+                    let line = line.saturating_sub(1).min(motion::line_count(&text) - 1);
+
+                    place(
+                        content,
+                        motion::first_non_blank(&text, motion::line_offset(&text, line)),
+                    );
+
+                    Effect::None
+                }
+                Err(_) => Effect::Error(format!("Not an editor command: {command}")),
+            },
+        }
+    }
+}
+
+impl Command {
+    /// Whether the command changes the text, to be repeated by `.`.
+    fn is_change(&self) -> bool {
+        match &self.verb {
+            Verb::Move(_) | Verb::Select(..) => false,
+            Verb::Operate(operator, _) => *operator != Operator::Yank,
+            Verb::Replace(_) => true,
+            Verb::Single(c) => "pPJ~iaIAoO".contains(*c),
+        }
+    }
+}
+
+/// Splits a count off the start of some keys.
+fn count(keys: &[Key]) -> (Option<usize>, &[Key]) {
+    let digits = keys
+        .iter()
+        .enumerate()
+        .take_while(|(index, key)| match key {
+            Key::Char('0') => *index > 0,
+            Key::Char(c) => c.is_ascii_digit(),
+            _ => false,
+        })
+        .count();
+
+    if digits == 0 {
+        return (None, keys);
+    }
+
+    let count = keys[..digits]
+        .iter()
+        .filter_map(|key| match key {
+            Key::Char(c) => c.to_digit(10),
+            _ => None,
+        })
+        .fold(0usize, |count, digit| {
+            count.saturating_mul(10).saturating_add(digit as usize)
+        });
+
+    (Some(count.max(1)), &keys[digits..])
+}
+
+/// The text of a content, as its lines joined.
+fn text(content: &text_editor::Content) -> String {
+    content
+        .lines()
+        .map(|line| line.to_owned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Where the cursor rests in normal mode at or before an offset: never on
+/// the line break of a line with characters.
+fn rest(text: &str, offset: usize) -> usize {
+    let offset = offset.min(text.len());
+    let start = motion::line_start(text, offset);
+
+    if offset >= motion::line_end(text, offset) && offset > start {
+        motion::previous(text, offset)
+    } else {
+        offset
+    }
+}
+
+fn leading(text: &str, offset: usize) -> &str {
+    let start = motion::line_start(text, offset);
+    let end = motion::line_end(text, offset);
+    let line = &text[start..end];
+
+    &line[..line.len() - line.trim_start().len()]
+}
+
+fn word_ends_at(text: &str, offset: usize) -> bool {
+    let next = motion::next(text, offset);
+
+    match (text[offset..].chars().next(), text[next..].chars().next()) {
+        (Some(c), Some(following)) => {
+            c.is_alphanumeric() != following.is_alphanumeric() || following.is_whitespace()
+        }
+        _ => true,
+    }
+}
+
+fn toggle_case(c: char) -> char {
+    if c.is_uppercase() {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c.to_uppercase().next().unwrap_or(c)
+    }
+}
+
+fn place(content: &mut text_editor::Content, offset: usize) {
+    let position = cursors::position(content, offset);
+
+    cursors::place(content, position);
+}
+
+/// Replaces a range of the text of a content.
+fn replace(content: &mut text_editor::Content, range: Range<usize>, text: &str) {
+    cursors::select(
+        content,
+        Cursor {
+            anchor: cursors::position(content, range.start),
+            head: cursors::position(content, range.end),
+        },
+    );
+
+    if !text.is_empty() {
+        content.perform(text_editor::Action::Edit(Edit::Paste(Arc::new(
+            text.to_owned(),
+        ))));
+    } else if range.start != range.end {
+        content.perform(text_editor::Action::Edit(Edit::Delete));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keys: &str) -> Vec<Key> {
+        keys.chars().map(Key::Char).collect()
+    }
+
+    #[test]
+    fn count_splits_leading_digits() {
+        let keys = keys("12dw");
+
+        assert_eq!(count(&keys), (Some(12), &keys[2..]));
+    }
+
+    #[test]
+    fn count_does_not_start_with_zero() {
+        let keys = keys("0w");
+
+        assert_eq!(count(&keys), (None, &keys[..]));
+        assert_eq!(count(&self::keys("10")).0, Some(10));
+    }
+
+    #[test]
+    fn count_saturates() {
+        let keys = keys("99999999999999999999999x");
+
+        assert_eq!(count(&keys), (Some(usize::MAX), &keys[23..]));
+    }
+
+    #[test]
+    fn counts_of_operators_and_motions_multiply() {
+        let vim = Vim::default();
+
+        let Parse::Done(command) = vim.parse(&keys("2d3w")) else {
+            panic!("2d3w is a command");
+        };
+
+        assert_eq!(command.count, Some(6));
+        assert!(matches!(
+            command.verb,
+            Verb::Operate(
+                Operator::Delete,
+                Object::Motion(Motion::WordStart { big: false })
+            )
+        ));
+    }
+
+    #[test]
+    fn parse_waits_for_the_rest_of_a_command() {
+        let vim = Vim::default();
+
+        assert!(matches!(vim.parse(&keys("\"a2d")), Parse::Pending));
+        assert!(matches!(vim.parse(&keys("di")), Parse::Pending));
+        assert!(matches!(vim.parse(&keys("dq")), Parse::Invalid));
+
+        let Parse::Done(command) = vim.parse(&keys("\"a2dd")) else {
+            panic!("\"a2dd is a command");
+        };
+
+        assert_eq!(command.register, Some('a'));
+        assert_eq!(command.count, Some(2));
+        assert!(matches!(
+            command.verb,
+            Verb::Operate(Operator::Delete, Object::Lines)
+        ));
+    }
+}
@@ -0,0 +1,736 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `w`, or `W` when big.
+    WordStart {
+        big: bool,
+    },
+    /// `e`, or `E` when big.
+    WordEnd {
+        big: bool,
+    },
+    /// `b`, or `B` when big.
+    WordBack {
+        big: bool,
+    },
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    /// The first non-blank of the next line, as `Enter` and `+`.
+    NextLine,
+    /// The first non-blank of the previous line, as `-`.
+    PreviousLine,
+    /// `gg`, to the given line or the first one.
+    FirstLine,
+    /// `G`, to the given line or the last one.
+    LastLine,
+    /// `f`, `t`, `F` and `T`.
+    Find {
+        target: char,
+        backward: bool,
+        before: bool,
+    },
+    /// `%`.
+    Bracket,
+    ParagraphForward,
+    ParagraphBack,
+}
+
+/// How the text between the cursor and a target is taken by an operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Exclusive,
+    Inclusive,
+    Linewise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub offset: usize,
+    pub kind: Kind,
+}
+
+impl Motion {
+    /// Whether the motion moves within lines, forgetting the column kept
+    /// when moving up and down.
+    pub fn is_horizontal(self) -> bool {
+        !matches!(
+            self,
+            Motion::Up | Motion::Down | Motion::FirstLine | Motion::LastLine
+        )
+    }
+}
+
+/// Moves from an offset, `count` times. `column` is the column kept while
+/// moving up and down.
+pub fn apply(
+    text: &str,
+    offset: usize,
+    motion: Motion,
+    count: Option<usize>,
+    column: usize,
+) -> Option<Target> {
+    let times = count.unwrap_or(1);
+
+    let exclusive = |offset| {
+        Some(Target {
+            offset,
+            kind: Kind::Exclusive,
+        })
+    };
+
+    let inclusive = |offset| {
+        Some(Target {
+            offset,
+            kind: Kind::Inclusive,
+        })
+    };
+
+    let linewise = |offset| {
+        Some(Target {
+            offset,
+            kind: Kind::Linewise,
+        })
+    };
+
+    match motion {
+        Motion::Left => {
+            let start = line_start(text, offset);
+            let mut offset = offset;
+
+            for _ in 0..times {
+                if offset <= start {
+                    break;
+                }
+
+                offset = previous(text, offset);
+            }
+
+            exclusive(offset)
+        }
+        Motion::Right => {
+            let end = line_end(text, offset);
+            let mut offset = offset;
+
+            for _ in 0..times {
+                if offset >= end {
+                    break;
+                }
+
+                offset = next(text, offset);
+            }
+
+            exclusive(offset)
+        }
+        Motion::Up | Motion::Down => {
+            let line = line_of(text, offset);
+
+            let line = if motion == Motion::Up {
+                line.checked_sub(times)?
+            } else {
+                let line = line.saturating_add(times);
+
+                (line < line_count(text)).then_some(line)?
+            };
+
+            linewise(at_column(text, line_offset(text, line), column))
+        }
+        Motion::WordStart { big } => {
+            let mut offset = offset;
+
+            for _ in 0..times {
+                let next = word_start(text, offset, big);
+
+                if next == offset {
+                    break;
+                }
+
+                offset = next;
+            }
+
+            exclusive(offset)
+        }
+        Motion::WordEnd { big } => {
+            let mut offset = offset;
+
+            for _ in 0..times {
+                let next = word_end(text, offset, big);
+
+                if next == offset {
+                    break;
+                }
+
+                offset = next;
+            }
+
+            inclusive(offset)
+        }
+        Motion::WordBack { big } => {
+            let mut offset = offset;
+
+            for _ in 0..times {
+                let next = word_back(text, offset, big);
+
+                if next == offset {
+                    break;
+                }
+
+                offset = next;
+            }
+
+            exclusive(offset)
+        }
+        Motion::LineStart => exclusive(line_start(text, offset)),
+        Motion::FirstNonBlank => exclusive(first_non_blank(text, line_start(text, offset))),
+        Motion::LineEnd => {
+            let line = line_of(text, offset)
+                .saturating_add(times - 1)
+                .min(line_count(text) - 1);
+            let start = line_offset(text, line);
+            let end = line_end(text, start);
+
+            inclusive(if end > start {
+                previous(text, end)
+            } else {
+                end
+            })
+        }
+        Motion::NextLine | Motion::PreviousLine => {
+            let line = line_of(text, offset);
+
+            let line = if motion == Motion::PreviousLine {
+                line.checked_sub(times)?
+            } else {
+                let line = line.saturating_add(times);
+
+                (line < line_count(text)).then_some(line)?
+            };
+
+            linewise(first_non_blank(text, line_offset(text, line)))
+        }
+        Motion::FirstLine | Motion::LastLine => {
+            let last = line_count(text) - 1;
+
+            let line = match (count, motion) {
+                (Some(count), _) => count.saturating_sub(1).min(last),
+                (None, Motion::FirstLine) => 0,
+                (None, _) => last,
+            };
+
+            linewise(first_non_blank(text, line_offset(text, line)))
+        }
+        Motion::Find {
+            target,
+            backward,
+            before,
+        } => {
+            let mut offset = offset;
+
+            for _ in 0..times {
+                offset = find(text, offset, target, backward)?;
+            }
+
+            if before {
+                offset = if backward {
+                    next(text, offset)
+                } else {
+                    previous(text, offset)
+                };
+            }
+
+            if backward {
+                exclusive(offset)
+            } else {
+                inclusive(offset)
+            }
+        }
+        Motion::Bracket => {
+            let end = line_end(text, offset);
+
+            let (position, bracket) = text[offset..end]
+                .char_indices()
+                .find(|(_, c)| "()[]{}".contains(*c))?;
+
+            inclusive(matching(text, offset + position, bracket)?)
+        }
+        Motion::ParagraphForward => {
+            let mut line = line_of(text, offset);
+            let count = line_count(text);
+
+            for _ in 0..times {
+                if line >= count {
+                    break;
+                }
+
+                line += 1;
+
+                while line < count && !is_blank_line(text, line) {
+                    line += 1;
+                }
+            }
+
+            exclusive(if line >= count {
+                text.len()
+            } else {
+                line_offset(text, line)
+            })
+        }
+        Motion::ParagraphBack => {
+            let mut line = line_of(text, offset);
+
+            for _ in 0..times {
+                if line == 0 {
+                    break;
+                }
+
+                line -= 1;
+
+                while line > 0 && !is_blank_line(text, line) {
+                    line -= 1;
+                }
+            }
+
+            exclusive(line_offset(text, line))
+        }
+    }
+}
+
+/// Finds the text object around an offset, e.g. `w` for `iw` and `aw`.
+/// Returns its range and whether it is made of whole lines.
+pub fn object(
+    text: &str,
+    offset: usize,
+    object: char,
+    around: bool,
+) -> Option<(Range<usize>, bool)> {
+    match object {
+        'w' | 'W' => Some((word(text, offset, object == 'W', around), false)),
+        '"' | '\'' | '`' => quoted(text, offset, object, around).map(|range| (range, false)),
+        '(' | ')' | 'b' => bracketed(text, offset, '(', ')', around).map(|range| (range, false)),
+        '{' | '}' | 'B' => bracketed(text, offset, '{', '}', around).map(|range| (range, false)),
+        '[' | ']' => bracketed(text, offset, '[', ']', around).map(|range| (range, false)),
+        '<' | '>' => bracketed(text, offset, '<', '>', around).map(|range| (range, false)),
+        'p' => Some((paragraph(text, offset, around), true)),
+        _ => None,
+    }
+}
+
+pub fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+/// The offset of the line break ending the line of an offset, or the end of
+/// the text.
+pub fn line_end(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .find('\n')
+        .map_or(text.len(), |index| offset + index)
+}
+
+pub fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count()
+}
+
+pub fn line_count(text: &str) -> usize {
+    text.matches('\n').count() + 1
+}
+
+pub fn line_offset(text: &str, line: usize) -> usize {
+    if line == 0 {
+        return 0;
+    }
+
+    text.match_indices('\n')
+        .nth(line - 1)
+        .map_or(text.len(), |(index, _)| index + 1)
+}
+
+pub fn first_non_blank(text: &str, start: usize) -> usize {
+    let end = line_end(text, start);
+
+    text[start..end]
+        .find(|c: char| !c.is_whitespace())
+        .map_or(end, |index| start + index)
+}
+
+/// The column of an offset, in characters.
+pub fn column(text: &str, offset: usize) -> usize {
+    text[line_start(text, offset)..offset].chars().count()
+}
+
+/// The offset at a column of a line, or at its last character.
+pub fn at_column(text: &str, start: usize, column: usize) -> usize {
+    let end = line_end(text, start);
+
+    text[start..end]
+        .char_indices()
+        .nth(column)
+        .map_or(last_char(text, start), |(index, _)| start + index)
+}
+
+/// The offset of the last character of the line at `start`, where the
+/// cursor rests in normal mode.
+pub fn last_char(text: &str, start: usize) -> usize {
+    let end = line_end(text, start);
+
+    if end > start {
+        previous(text, end)
+    } else {
+        end
+    }
+}
+
+pub fn next(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .chars()
+        .next()
+        .map_or(offset, |c| offset + c.len_utf8())
+}
+
+pub fn previous(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .chars()
+        .next_back()
+        .map_or(offset, |c| offset - c.len_utf8())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+fn class(c: char, big: bool) -> Class {
+    if c.is_whitespace() {
+        Class::Blank
+    } else if big || c.is_alphanumeric() || c == '_' {
+        Class::Word
+    } else {
+        Class::Punctuation
+    }
+}
+
+fn char_at(text: &str, offset: usize) -> Option<char> {
+    text[offset..].chars().next()
+}
+
+fn word_start(text: &str, mut offset: usize, big: bool) -> usize {
+    let Some(first) = char_at(text, offset) else {
+        return offset;
+    };
+
+    let start = class(first, big);
+
+    if start != Class::Blank {
+        while let Some(c) = char_at(text, offset) {
+            if class(c, big) != start {
+                break;
+            }
+
+            offset = next(text, offset);
+        }
+    }
+
+    // Empty lines are words of their own.
+    while let Some(c) = char_at(text, offset) {
+        if !c.is_whitespace() {
+            break;
+        }
+
+        if c == '\n' && char_at(text, offset + 1) == Some('\n') {
+            return offset + 1;
+        }
+
+        offset = next(text, offset);
+    }
+
+    offset
+}
+
+fn word_end(text: &str, offset: usize, big: bool) -> usize {
+    let mut offset = next(text, offset);
+
+    while let Some(c) = char_at(text, offset) {
+        if !c.is_whitespace() {
+            break;
+        }
+
+        offset = next(text, offset);
+    }
+
+    let Some(first) = char_at(text, offset) else {
+        return previous(text, offset);
+    };
+
+    let run = class(first, big);
+
+    loop {
+        let following = next(text, offset);
+
+        match char_at(text, following) {
+            Some(c) if class(c, big) == run => offset = following,
+            _ => return offset,
+        }
+    }
+}
+
+fn word_back(text: &str, offset: usize, big: bool) -> usize {
+    let mut offset = previous(text, offset);
+
+    while let Some(c) = char_at(text, offset) {
+        if !c.is_whitespace() || offset == 0 {
+            break;
+        }
+
+        if c == '\n' && offset > 0 && text.as_bytes()[offset - 1] == b'\n' {
+            return offset;
+        }
+
+        offset = previous(text, offset);
+    }
+
+    let Some(last) = char_at(text, offset) else {
+        return offset;
+    };
+
+    let run = class(last, big);
+
+    while offset > 0 {
+        let before = previous(text, offset);
+
+        match char_at(text, before) {
+            Some(c) if class(c, big) == run && c != '\n' => offset = before,
+            _ => break,
+        }
+    }
+
+    offset
+}
+
+fn find(text: &str, from: usize, target: char, backward: bool) -> Option<usize> {
+    let start = line_start(text, from);
+    let end = line_end(text, from);
+
+    if backward {
+        text[start..from].rfind(target).map(|index| start + index)
+    } else {
+        let from = next(text, from).min(end);
+
+        text[from..end].find(target).map(|index| from + index)
+    }
+}
+
+/// The offset of the bracket matching the one at an offset.
+fn matching(text: &str, offset: usize, bracket: char) -> Option<usize> {
+    let (open, close, forward) = match bracket {
+        '(' => ('(', ')', true),
+        '[' => ('[', ']', true),
+        '{' => ('{', '}', true),
+        ')' => ('(', ')', false),
+        ']' => ('[', ']', false),
+        '}' => ('{', '}', false),
+        '<' => ('<', '>', true),
+        '>' => ('<', '>', false),
+        _ => return None,
+    };
+
+    let mut depth = 0usize;
+
+    if forward {
+        for (index, c) in text[offset..].char_indices() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(offset + index);
+                }
+            }
+        }
+    } else {
+        for (index, c) in text[..=offset].char_indices().rev() {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn is_blank_line(text: &str, line: usize) -> bool {
+    let start = line_offset(text, line);
+
+    text[start..line_end(text, start)].trim().is_empty()
+}
+
+fn word(text: &str, offset: usize, big: bool, around: bool) -> Range<usize> {
+    let start_of_line = line_start(text, offset);
+    let end_of_line = line_end(text, offset);
+
+    let Some(c) = char_at(text, offset).filter(|c| *c != '\n') else {
+        return offset..offset;
+    };
+
+    let run = class(c, big);
+    let same = |c: char| c != '\n' && class(c, big) == run;
+
+    let mut start = offset;
+
+    while start > start_of_line && char_at(text, previous(text, start)).is_some_and(same) {
+        start = previous(text, start);
+    }
+
+    let mut end = offset;
+
+    while end < end_of_line && char_at(text, end).is_some_and(same) {
+        end = next(text, end);
+    }
+
+    if !around {
+        return start..end;
+    }
+
+    // The blanks after the word, or else before it.
+    let blank = |c: char| c != '\n' && c.is_whitespace();
+    let mut after = end;
+
+    while after < end_of_line && char_at(text, after).is_some_and(blank) {
+        after = next(text, after);
+    }
+
+    if after > end || run == Class::Blank {
+        return start..after;
+    }
+
+    while start > start_of_line && char_at(text, previous(text, start)).is_some_and(blank) {
+        start = previous(text, start);
+    }
+
+    start..end
+}
+
+fn quoted(text: &str, offset: usize, quote: char, around: bool) -> Option<Range<usize>> {
+    let start = line_start(text, offset);
+    let end = line_end(text, offset);
+
+    let mut quotes = Vec::new();
+    let mut escaped = false;
+
+    for (index, c) in text[start..end].char_indices() {
+        if c == quote && !escaped {
+            quotes.push(start + index);
+        }
+
+        escaped = c == '\\' && !escaped;
+    }
+
+    // The pair around the offset, or else the first one after it.
+    let (open, close) = quotes
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|&(open, close)| open <= offset && offset <= close)
+        .or_else(|| {
+            quotes
+                .chunks_exact(2)
+                .map(|pair| (pair[0], pair[1]))
+                .find(|&(open, _)| open > offset)
+        })?;
+
+    Some(if around {
+        open..close + quote.len_utf8()
+    } else {
+        open + quote.len_utf8()..close
+    })
+}
+
+fn bracketed(
+    text: &str,
+    offset: usize,
+    open: char,
+    close: char,
+    around: bool,
+) -> Option<Range<usize>> {
+    let start = if char_at(text, offset) == Some(open) {
+        offset
+    } else {
+        let mut depth = 0usize;
+        let mut found = None;
+
+        for (index, c) in text[..offset].char_indices().rev() {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                if depth == 0 {
+                    found = Some(index);
+                    break;
+                }
+
+                depth -= 1;
+            }
+        }
+
+        found?
+    };
+
+    let end = matching(text, start, open)?;
+
+    if around {
+        return Some(start..end + close.len_utf8());
+    }
+
+    let mut inner = start + open.len_utf8()..end;
+
+    // Blocks spanning lines keep their line breaks and indentation.
+    if text[inner.clone()].starts_with('\n') {
+        inner.start += 1;
+
+        let last = line_start(text, end);
+
+        if text[last..end].trim().is_empty() && last > inner.start {
+            inner.end = last;
+        }
+    }
+
+    Some(inner)
+}
+
+fn paragraph(text: &str, offset: usize, around: bool) -> Range<usize> {
+    let count = line_count(text);
+    let line = line_of(text, offset);
+    let blank = is_blank_line(text, line);
+
+    let mut first = line;
+
+    while first > 0 && is_blank_line(text, first - 1) == blank {
+        first -= 1;
+    }
+
+    let mut last = line;
+
+    while last + 1 < count && is_blank_line(text, last + 1) == blank {
+        last += 1;
+    }
+
+    // The blank lines after a paragraph, or the paragraph after blank lines.
+    if around {
+        while last + 1 < count && is_blank_line(text, last + 1) != blank {
+            last += 1;
+        }
+    }
+
+    line_offset(text, first)..line_end(text, line_offset(text, last))
+}
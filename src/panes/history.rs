@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+
+use iced::widget::{self, button, column, horizontal_space, row, text, text_input};
+use iced::{Element, Length, Task};
+
+use crate::git;
+use crate::settings::Settings;
+
+use super::style;
+
+/// The commits listed at most.
+const LIMIT: usize = 1000;
+
+pub struct History {
+    pub root: PathBuf,
+    pub commits: Vec<git::Commit>,
+    /// The path the log is filtered by, as typed.
+    pub filter: String,
+    pub selected: Option<String>,
+    refreshing: bool,
+    outdated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Loaded(Result<Vec<git::Commit>, git::Error>),
+    FilterEdited(String),
+    FilterSubmitted,
+    FilterCleared,
+    Select(String),
+    ShowDiff(String),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    /// The changes of a commit should be shown.
+    ShowDiff(String),
+    Error(String),
+}
+
+impl History {
+    pub fn new(root: PathBuf) -> (Self, Task<Message>) {
+        let mut history = Self {
+            root,
+            commits: Vec::new(),
+            filter: String::new(),
+            selected: None,
+            refreshing: false,
+            outdated: false,
+        };
+
+        let task = history.refresh();
+
+        (history, task)
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::Loaded(result) => {
+                self.refreshing = false;
+
+                let action = match result {
+                    Ok(commits) => {
+                        self.commits = commits;
+
+                        Action::None
+                    }
+                    Err(error) => Action::Error(error.to_string()),
+                };
+
+                if self.outdated {
+                    return Action::Run(self.refresh());
+                }
+
+                action
+            }
+            Message::FilterEdited(filter) => {
+                self.filter = filter;
+
+                Action::None
+            }
+            Message::FilterSubmitted => Action::Run(self.refresh()),
+            Message::FilterCleared => {
+                self.filter.clear();
+
+                Action::Run(self.refresh())
+            }
+            Message::Select(id) => {
+                self.selected = (self.selected.as_ref() != Some(&id)).then_some(id);
+
+                Action::None
+            }
+            Message::ShowDiff(id) => Action::ShowDiff(id),
+        }
+    }
+
+    /// Shows the commits changing a path.
+    pub fn filter(&mut self, path: PathBuf) -> Task<Message> {
+        self.filter = path
+            .strip_prefix(&self.root)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+
+        self.refresh()
+    }
+
+    /// Reloads the log, once at a time.
+    pub fn refresh(&mut self) -> Task<Message> {
+        if self.refreshing {
+            self.outdated = true;
+
+            return Task::none();
+        }
+
+        self.refreshing = true;
+        self.outdated = false;
+
+        let filter = self.filter.trim();
+        let path = (!filter.is_empty()).then(|| self.root.join(filter));
+
+        Task::perform(git::log(self.root.clone(), path, LIMIT), Message::Loaded)
+    }
+
+    pub fn view<'a>(&'a self, settings: &'a Settings) -> Element<'a, Message> {
+        let filter = row![
+            text_input("Filter by path", &self.filter)
+                .size(12)
+                .on_input(Message::FilterEdited)
+                .on_submit(Message::FilterSubmitted),
+            button(text("Clear").size(12))
+                .on_press_maybe((!self.filter.is_empty()).then_some(Message::FilterCleared)),
+        ]
+        .spacing(5);
+
+        let rows = self.commits.iter().map(|commit| {
+            let selected = self.selected.as_ref() == Some(&commit.id);
+
+            let summary = button(
+                row![
+                    text(&commit.graph)
+                        .font(settings.font())
+                        .size(12)
+                        .style(text::primary),
+                    text(commit.short_id()).font(settings.font()).size(12),
+                    text(commit.summary()).size(12).width(Length::Fill),
+                    text(&commit.author).size(12),
+                    text(commit.time.date_time()).size(12),
+                ]
+                .spacing(8),
+            )
+            .width(Length::Fill)
+            .padding([1, 4])
+            .style(move |theme, status| style::entry(theme, status, selected))
+            .on_press(Message::Select(commit.id.clone()));
+
+            if !selected {
+                return summary.into();
+            }
+
+            let details = column![
+                text(commit.message.trim_end())
+                    .font(settings.font())
+                    .size(12),
+                row![
+                    text(format!("{} · {}", commit.id, commit.author)).size(12),
+                    horizontal_space(),
+                    button(text("Show diff").size(12))
+                        .padding([1, 6])
+                        .on_press(Message::ShowDiff(commit.id.clone())),
+                ]
+                .spacing(5),
+            ]
+            .spacing(5)
+            .padding(iced::Padding::default().left(16).bottom(5));
+
+            column![summary, details].into()
+        });
+
+        let log: Element<'a, Message> = if self.commits.is_empty() {
+            text("No commits.").size(12).into()
+        } else {
+            widget::scrollable(column(rows))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+        };
+
+        column![filter, log].spacing(5).into()
+    }
+}
+
+pub fn title() -> &'static str {
+    "History"
+}
@@ -1,7 +1,20 @@
-use iced::widget::text_editor::{self};
+use iced::widget::{self, text};
+
+use crate::settings::Settings;
 
 pub struct Hub {
-    pub content: text_editor::Content,
+    pub entries: Vec<Entry>,
+}
+
+pub struct Entry {
+    pub level: Level,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Error,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -10,7 +23,7 @@ pub enum Message {}
 impl Hub {
     pub fn new() -> Self {
         Self {
-            content: text_editor::Content::new(),
+            entries: Vec::new(),
         }
     }
 
@@ -18,8 +31,42 @@ impl Hub {
         match message {}
     }
 
-    pub fn view(&self) -> iced::Element<Message> {
-        iced::widget::text("Welcome to the hub!").into()
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.entries.push(Entry {
+            level: Level::Info,
+            text: text.into(),
+        });
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.entries.push(Entry {
+            level: Level::Error,
+            text: text.into(),
+        });
+    }
+
+    pub fn view(&self, settings: &Settings) -> iced::Element<'_, Message> {
+        if self.entries.is_empty() {
+            return text("Welcome to the hub!").into();
+        }
+
+        widget::scrollable(
+            widget::column(self.entries.iter().map(|entry| {
+                text(&entry.text)
+                    .font(settings.font())
+                    .size(settings.text_size)
+                    .style(match entry.level {
+                        Level::Info => text::default,
+                        Level::Error => text::danger,
+                    })
+                    .into()
+            }))
+            .spacing(2),
+        )
+        .anchor_bottom()
+        .width(iced::Length::Fill)
+        .height(iced::Length::Fill)
+        .into()
     }
 }
 
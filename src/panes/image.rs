@@ -0,0 +1,391 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use iced::widget::{
+    self, button, column, container, horizontal_space, mouse_area, responsive, row, scrollable,
+    stack, svg, text,
+};
+use iced::{ContentFit, Element, Length, Point, Size, Task, mouse};
+
+use crate::settings::Appearance;
+
+use super::style;
+
+/// The factor the zoom changes by, per button press or notch of the wheel.
+const ZOOM_STEP: f32 = 1.25;
+
+const MIN_ZOOM: f32 = 0.01;
+const MAX_ZOOM: f32 = 64.0;
+
+/// The squares on the long side of the checkerboard behind an image.
+const SQUARES: u32 = 64;
+
+/// The extensions of the files shown as images.
+const EXTENSIONS: [&str; 9] = [
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tif", "svg",
+];
+
+/// An image file, shown over a checkerboard so that transparent parts can
+/// be told apart, zoomed with the wheel and panned by dragging.
+pub struct Image {
+    /// The file shown, if any.
+    pub path: Option<PathBuf>,
+    loaded: Option<Loaded>,
+    zoom: Zoom,
+    scrollable: scrollable::Id,
+    /// Where the image was grabbed, in its own coordinates, while it is
+    /// dragged.
+    grab: Option<Point>,
+    /// The position of the mouse over the image.
+    cursor: Point,
+}
+
+#[derive(Debug, Clone)]
+pub struct Loaded {
+    pub path: PathBuf,
+    pub source: Source,
+    pub width: u32,
+    pub height: u32,
+    /// A checkerboard, a pixel per square, stretched behind the image.
+    checker: widget::image::Handle,
+}
+
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// A raster image. Only the first frame of animations is shown.
+    Raster(widget::image::Handle),
+    Vector(svg::Handle),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zoom {
+    /// Scaled to fit the pane whole.
+    Fit,
+    Scale(f32),
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Loaded(Result<Loaded, Error>),
+    Zoom(f32),
+    Fit,
+    /// The wheel was scrolled over the image, at the given scale.
+    Wheel(mouse::ScrollDelta, f32),
+    Moved(Point),
+    Pressed,
+    Released,
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl Image {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            loaded: None,
+            zoom: Zoom::Fit,
+            scrollable: scrollable::Id::unique(),
+            grab: None,
+            cursor: Point::ORIGIN,
+        }
+    }
+
+    /// Shows an image file, fitted to the pane.
+    pub fn open(&mut self, path: PathBuf) -> Task<Message> {
+        self.path = Some(path.clone());
+        self.zoom = Zoom::Fit;
+
+        Task::perform(load(path), Message::Loaded)
+    }
+
+    /// Loads the image again if its file changed.
+    pub fn changed(&self, paths: &[PathBuf]) -> Task<Message> {
+        match &self.path {
+            Some(path) if paths.contains(path) => {
+                Task::perform(load(path.clone()), Message::Loaded)
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::Loaded(Ok(loaded)) => {
+                if self.path.as_ref() == Some(&loaded.path) {
+                    self.loaded = Some(loaded);
+                }
+
+                Action::None
+            }
+            Message::Loaded(Err(error)) => {
+                if self.path.as_ref() == Some(&error.path) {
+                    self.loaded = None;
+                }
+
+                Action::Error(error.to_string())
+            }
+            Message::Zoom(scale) => {
+                self.zoom = Zoom::Scale(scale.clamp(MIN_ZOOM, MAX_ZOOM));
+
+                Action::None
+            }
+            Message::Fit => {
+                self.zoom = Zoom::Fit;
+
+                Action::None
+            }
+            Message::Wheel(delta, scale) => {
+                let y = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 50.0,
+                };
+
+                self.zoom = Zoom::Scale((scale * ZOOM_STEP.powf(y)).clamp(MIN_ZOOM, MAX_ZOOM));
+
+                Action::None
+            }
+            Message::Moved(position) => {
+                self.cursor = position;
+
+                let Some(grab) = self.grab else {
+                    return Action::None;
+                };
+
+                // Scrolling by the move keeps the grabbed point under the
+                // mouse.
+                Action::Run(scrollable::scroll_by(
+                    self.scrollable.clone(),
+                    scrollable::AbsoluteOffset {
+                        x: grab.x - position.x,
+                        y: grab.y - position.y,
+                    },
+                ))
+            }
+            Message::Pressed => {
+                self.grab = Some(self.cursor);
+
+                Action::None
+            }
+            Message::Released => {
+                self.grab = None;
+
+                Action::None
+            }
+        }
+    }
+
+    pub fn view<'a>(&'a self, appearance: &'a Appearance) -> Element<'a, Message> {
+        let Some(loaded) = &self.loaded else {
+            return text(if self.path.is_some() {
+                "Loading…"
+            } else {
+                "Select an image in the project to show it."
+            })
+            .size(12)
+            .into();
+        };
+
+        responsive(move |size| {
+            // The toolbar and status take some of the pane.
+            let viewport = Size::new(size.width, (size.height - 60.0).max(1.0));
+
+            let scale = match self.zoom {
+                Zoom::Fit => (viewport.width / loaded.width as f32)
+                    .min(viewport.height / loaded.height as f32),
+                Zoom::Scale(scale) => scale,
+            };
+
+            let width = loaded.width as f32 * scale;
+            let height = loaded.height as f32 * scale;
+
+            let picture: Element<'_, Message> = match &loaded.source {
+                Source::Raster(handle) => widget::image(handle.clone())
+                    .width(width)
+                    .height(height)
+                    .content_fit(ContentFit::Fill)
+                    .filter_method(if scale >= 2.0 {
+                        widget::image::FilterMethod::Nearest
+                    } else {
+                        widget::image::FilterMethod::Linear
+                    })
+                    .into(),
+                Source::Vector(handle) => svg(handle.clone())
+                    .width(width)
+                    .height(height)
+                    .content_fit(ContentFit::Fill)
+                    .into(),
+            };
+
+            let checker = widget::image(loaded.checker.clone())
+                .width(width)
+                .height(height)
+                .content_fit(ContentFit::Fill)
+                .filter_method(widget::image::FilterMethod::Nearest);
+
+            // Centered while smaller than the pane.
+            let canvas = container(stack![checker, picture])
+                .center_x(width.max(viewport.width))
+                .center_y(height.max(viewport.height));
+
+            let canvas = mouse_area(canvas)
+                .interaction(if self.grab.is_some() {
+                    mouse::Interaction::Grabbing
+                } else {
+                    mouse::Interaction::Grab
+                })
+                .on_move(Message::Moved)
+                .on_press(Message::Pressed)
+                .on_release(Message::Released)
+                // Released unseen once the mouse leaves.
+                .on_exit(Message::Released)
+                .on_scroll(move |delta| Message::Wheel(delta, scale));
+
+            let zoom = |label, message| {
+                button(text(label).size(12))
+                    .padding([1, 6])
+                    .style(|theme, status| style::button(theme, status, &appearance.panes))
+                    .on_press(message)
+            };
+
+            let toolbar = row![
+                zoom("−", Message::Zoom(scale / ZOOM_STEP)),
+                zoom("+", Message::Zoom(scale * ZOOM_STEP)),
+                zoom("100%", Message::Zoom(1.0)),
+                zoom("Fit", Message::Fit),
+            ]
+            .spacing(5);
+
+            let status = row![
+                text(loaded.path.display().to_string()).size(12),
+                horizontal_space(),
+                text(format!("{} × {} px", loaded.width, loaded.height)).size(12),
+                text(format!("{:.0}%", scale * 100.0)).size(12),
+            ]
+            .spacing(10);
+
+            column![
+                toolbar,
+                scrollable(canvas)
+                    .id(self.scrollable.clone())
+                    .direction(scrollable::Direction::Both {
+                        vertical: scrollable::Scrollbar::default(),
+                        horizontal: scrollable::Scrollbar::default(),
+                    })
+                    .width(Length::Fill)
+                    .height(Length::Fill),
+                status,
+            ]
+            .spacing(5)
+            .into()
+        })
+        .into()
+    }
+}
+
+pub fn title() -> &'static str {
+    "Image"
+}
+
+/// Whether a file is shown as an image, telling by its extension.
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Reads an image file and its size in pixels.
+async fn load(path: PathBuf) -> Result<Loaded, Error> {
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|error| self::error(&path, error))?;
+
+    let file = path.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let is_svg = file
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+
+        let (source, width, height) = if is_svg {
+            let tree = resvg::usvg::Tree::from_data(&bytes, &resvg::usvg::Options::default())
+                .map_err(|error| error.to_string())?;
+            let size = tree.size();
+
+            (
+                Source::Vector(svg::Handle::from_memory(bytes)),
+                size.width().ceil() as u32,
+                size.height().ceil() as u32,
+            )
+        } else {
+            let (width, height) = ::image::io::Reader::new(io::Cursor::new(&bytes))
+                .with_guessed_format()
+                .map_err(|error| error.to_string())?
+                .into_dimensions()
+                .map_err(|error| error.to_string())?;
+
+            (
+                Source::Raster(widget::image::Handle::from_bytes(bytes)),
+                width,
+                height,
+            )
+        };
+
+        if width == 0 || height == 0 {
+            return Err(String::from("the image is empty"));
+        }
+
+        Ok(Loaded {
+            path: file,
+            source,
+            width,
+            height,
+            checker: checker(width, height),
+        })
+    })
+    .await
+    .unwrap_or_else(|error| Err(error.to_string()))
+    .map_err(|message| Error { path, message })
+}
+
+/// A checkerboard with square squares for an image, a pixel per square.
+fn checker(width: u32, height: u32) -> widget::image::Handle {
+    let side = width.max(height).div_ceil(SQUARES).max(1);
+    let (columns, rows) = (width.div_ceil(side), height.div_ceil(side));
+
+    let pixels = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (row + column).is_multiple_of(2)))
+        .flat_map(|light| {
+            if light {
+                [204, 204, 204, 255]
+            } else {
+                [153, 153, 153, 255]
+            }
+        })
+        .collect::<Vec<u8>>();
+
+    widget::image::Handle::from_rgba(columns, rows, pixels)
+}
+
+fn error(path: &Path, error: io::Error) -> Error {
+    Error {
+        path: path.to_owned(),
+        message: error.to_string(),
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
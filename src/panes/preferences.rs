@@ -0,0 +1,249 @@
+use iced::widget;
+use iced::widget::{checkbox, column, pick_list, row, slider, text, text_input};
+
+use crate::settings::{Autosave, LineNumbers, Settings};
+
+/// Edits the user settings; every change is written back to `settings.toml`.
+pub struct Preferences {
+    pub user: Settings,
+    pub font: String,
+    /// The rulers being edited, as comma-separated columns.
+    pub rulers: String,
+    pub themes: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ThemeSelected(String),
+    FontEdited(String),
+    FontSubmitted,
+    TextSizeChanged(f32),
+    SpacingChanged(f32),
+    PaddingChanged(f32),
+    TabWidthChanged(u8),
+    LineNumbersSelected(LineNumbers),
+    RulersEdited(String),
+    RulersSubmitted,
+    WhitespaceToggled(bool),
+    IndentGuidesToggled(bool),
+    MinimapToggled(bool),
+    VimToggled(bool),
+    FormatOnSaveToggled(bool),
+    AutosaveSelected(Autosave),
+    RecoveryToggled(bool),
+}
+
+impl Preferences {
+    pub fn new(user: &Settings, themes: Vec<String>) -> Self {
+        Self {
+            user: user.clone(),
+            font: user.font.clone().unwrap_or_default(),
+            rulers: rulers(user),
+            themes,
+        }
+    }
+
+    /// Returns the edited settings, if the message changed them.
+    pub fn update(&mut self, message: Message) -> Option<Settings> {
+        let mut user = self.user.clone();
+
+        match message {
+            Message::ThemeSelected(theme) => user.theme = theme,
+            Message::FontEdited(font) => {
+                self.font = font;
+
+                return None;
+            }
+            Message::FontSubmitted => {
+                let font = self.font.trim();
+
+                user.font = (!font.is_empty()).then(|| font.to_owned());
+            }
+            Message::TextSizeChanged(size) => user.text_size = size,
+            Message::SpacingChanged(spacing) => user.spacing = spacing,
+            Message::PaddingChanged(padding) => user.padding = padding,
+            Message::TabWidthChanged(width) => user.tab_width = width as usize,
+            Message::LineNumbersSelected(line_numbers) => user.line_numbers = line_numbers,
+            Message::RulersEdited(rulers) => {
+                self.rulers = rulers;
+
+                return None;
+            }
+            Message::RulersSubmitted => {
+                user.rulers = self
+                    .rulers
+                    .split(',')
+                    .filter_map(|column| column.trim().parse().ok())
+                    .collect();
+
+                self.rulers = rulers(&user);
+            }
+            Message::WhitespaceToggled(show) => user.show_whitespace = show,
+            Message::IndentGuidesToggled(show) => user.indent_guides = show,
+            Message::MinimapToggled(show) => user.minimap = show,
+            Message::VimToggled(vim) => user.vim = vim,
+            Message::FormatOnSaveToggled(format) => user.format_on_save = format,
+            Message::AutosaveSelected(autosave) => user.autosave = autosave,
+            Message::RecoveryToggled(recovery) => user.recovery = recovery,
+        }
+
+        (user != self.user).then(|| {
+            self.user = user.clone();
+            user
+        })
+    }
+
+    /// Replaces the edited settings, e.g. after they were reloaded from disk.
+    pub fn reset(&mut self, user: &Settings, themes: Vec<String>) {
+        if self.user.font != user.font {
+            self.font = user.font.clone().unwrap_or_default();
+        }
+
+        if self.user.rulers != user.rulers {
+            self.rulers = rulers(user);
+        }
+
+        self.user = user.clone();
+        self.themes = themes;
+    }
+
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let field = |label, value: String, control: iced::Element<'static, Message>| {
+            row![
+                text(label).width(120),
+                control,
+                text(value)
+                    .width(40)
+                    .align_x(iced::alignment::Horizontal::Right),
+            ]
+            .spacing(10)
+            .align_y(iced::Center)
+        };
+
+        let theme = pick_list(
+            self.themes.as_slice(),
+            Some(&self.user.theme),
+            Message::ThemeSelected,
+        )
+        .width(iced::Length::Fill);
+
+        let font = text_input("Monospace", &self.font)
+            .on_input(Message::FontEdited)
+            .on_submit(Message::FontSubmitted);
+
+        let line_numbers = pick_list(
+            LineNumbers::ALL,
+            Some(self.user.line_numbers),
+            Message::LineNumbersSelected,
+        )
+        .width(iced::Length::Fill);
+
+        let autosave = pick_list(
+            Autosave::ALL,
+            Some(self.user.autosave),
+            Message::AutosaveSelected,
+        )
+        .width(iced::Length::Fill);
+
+        let rulers = text_input("e.g. 80, 100", &self.rulers)
+            .on_input(Message::RulersEdited)
+            .on_submit(Message::RulersSubmitted);
+
+        widget::scrollable(
+            column![
+                row![text("Theme").width(120), theme]
+                    .spacing(10)
+                    .align_y(iced::Center),
+                row![text("Font").width(120), font]
+                    .spacing(10)
+                    .align_y(iced::Center),
+                field(
+                    "Text size",
+                    format!("{}", self.user.text_size),
+                    slider(8.0..=32.0, self.user.text_size, Message::TextSizeChanged).into(),
+                ),
+                field(
+                    "Pane spacing",
+                    format!("{}", self.user.spacing),
+                    slider(0.0..=20.0, self.user.spacing, Message::SpacingChanged).into(),
+                ),
+                field(
+                    "Pane padding",
+                    format!("{}", self.user.padding),
+                    slider(0.0..=20.0, self.user.padding, Message::PaddingChanged).into(),
+                ),
+                field(
+                    "Tab width",
+                    format!("{}", self.user.tab_width),
+                    slider(
+                        1..=16,
+                        self.user.tab_width.min(16) as u8,
+                        Message::TabWidthChanged
+                    )
+                    .into(),
+                ),
+                row![text("Line numbers").width(120), line_numbers]
+                    .spacing(10)
+                    .align_y(iced::Center),
+                row![text("Rulers").width(120), rulers]
+                    .spacing(10)
+                    .align_y(iced::Center),
+                row![
+                    text("Whitespace").width(120),
+                    checkbox("", self.user.show_whitespace).on_toggle(Message::WhitespaceToggled),
+                ]
+                .spacing(10)
+                .align_y(iced::Center),
+                row![
+                    text("Indent guides").width(120),
+                    checkbox("", self.user.indent_guides).on_toggle(Message::IndentGuidesToggled),
+                ]
+                .spacing(10)
+                .align_y(iced::Center),
+                row![
+                    text("Minimap").width(120),
+                    checkbox("", self.user.minimap).on_toggle(Message::MinimapToggled),
+                ]
+                .spacing(10)
+                .align_y(iced::Center),
+                row![
+                    text("Vim mode").width(120),
+                    checkbox("", self.user.vim).on_toggle(Message::VimToggled),
+                ]
+                .spacing(10)
+                .align_y(iced::Center),
+                row![
+                    text("Format on save").width(120),
+                    checkbox("", self.user.format_on_save).on_toggle(Message::FormatOnSaveToggled),
+                ]
+                .spacing(10)
+                .align_y(iced::Center),
+                row![text("Autosave").width(120), autosave]
+                    .spacing(10)
+                    .align_y(iced::Center),
+                row![
+                    text("Crash recovery").width(120),
+                    checkbox("", self.user.recovery).on_toggle(Message::RecoveryToggled),
+                ]
+                .spacing(10)
+                .align_y(iced::Center),
+            ]
+            .spacing(10)
+            .padding(5),
+        )
+        .into()
+    }
+}
+
+pub fn title() -> &'static str {
+    "Preferences"
+}
+
+fn rulers(settings: &Settings) -> String {
+    settings
+        .rulers
+        .iter()
+        .map(usize::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
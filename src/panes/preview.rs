@@ -0,0 +1,392 @@
+use std::path::{Path, PathBuf};
+
+use iced::alignment::Horizontal;
+use iced::font::{self, Font};
+use iced::widget::{
+    self, column, container, horizontal_rule, image, rich_text, row, scrollable, span, svg, text,
+};
+use iced::{Element, Length, Task, highlighter};
+
+use crate::settings::{Appearance, Settings};
+
+use super::style;
+
+mod markdown;
+
+/// The sizes of headings, relative to the size of text, by level.
+const HEADINGS: [f32; 6] = [2.0, 1.6, 1.35, 1.15, 1.0, 0.9];
+
+const BOLD: Font = Font {
+    weight: font::Weight::Bold,
+    ..Font::DEFAULT
+};
+
+/// A rendering of the Markdown file focused in the editor, kept up to date
+/// as it is edited and scrolled along with it.
+pub struct Preview {
+    /// The file shown, if any.
+    pub path: Option<PathBuf>,
+    /// The text last parsed.
+    text: String,
+    dark: bool,
+    blocks: Vec<markdown::Block>,
+    scrollable: scrollable::Id,
+    /// The relative offset last scrolled to, following the editor.
+    offset: f32,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    LinkClicked(String),
+}
+
+pub enum Action {
+    None,
+    /// A file of the project was linked to and should be opened.
+    Open(PathBuf),
+    Error(String),
+}
+
+impl Preview {
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            text: String::new(),
+            dark: false,
+            blocks: Vec::new(),
+            scrollable: scrollable::Id::unique(),
+            offset: 0.0,
+        }
+    }
+
+    /// Shows a Markdown file, parsing its text again if it changed, and
+    /// scrolls to the same relative offset as its source.
+    ///
+    /// The last file shown is kept when the editor focuses another kind of
+    /// file.
+    pub fn show(
+        &mut self,
+        source: Option<(PathBuf, String)>,
+        offset: Option<f32>,
+        dark: bool,
+    ) -> Task<Message> {
+        let Some((path, text)) = source else {
+            return Task::none();
+        };
+
+        if self.path.as_ref() != Some(&path) {
+            self.offset = 0.0;
+        }
+
+        if self.path.as_ref() != Some(&path) || self.text != text || self.dark != dark {
+            self.blocks = markdown::parse(
+                &text,
+                if dark {
+                    highlighter::Theme::Base16Mocha
+                } else {
+                    highlighter::Theme::InspiredGitHub
+                },
+            );
+            self.path = Some(path);
+            self.text = text;
+            self.dark = dark;
+        }
+
+        match offset {
+            Some(offset) if offset != self.offset => {
+                self.offset = offset;
+
+                scrollable::snap_to(
+                    self.scrollable.clone(),
+                    scrollable::RelativeOffset { x: 0.0, y: offset },
+                )
+            }
+            _ => Task::none(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::LinkClicked(url) => {
+                if url.starts_with('#') {
+                    return Action::None;
+                }
+
+                if let Some(scheme) = scheme(&url) {
+                    if !EXTERNAL
+                        .iter()
+                        .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+                    {
+                        return Action::Error(format!("{url}: only web and mail links are opened"));
+                    }
+
+                    return match open_external(&url) {
+                        Ok(()) => Action::None,
+                        Err(error) => Action::Error(format!("{url}: {error}")),
+                    };
+                }
+
+                match self.resolve(url.split('#').next().unwrap_or_default()) {
+                    Some(path) if path.is_file() => Action::Open(path),
+                    _ => Action::Error(format!("{url}: no such file")),
+                }
+            }
+        }
+    }
+
+    pub fn view<'a>(
+        &'a self,
+        settings: &'a Settings,
+        appearance: &'a Appearance,
+    ) -> Element<'a, Message> {
+        if self.path.is_none() {
+            return text("Open a Markdown file in the editor to preview it.")
+                .size(12)
+                .into();
+        }
+
+        scrollable(
+            container(
+                column(
+                    self.blocks
+                        .iter()
+                        .map(|block| self.block(block, settings, appearance)),
+                )
+                .spacing(12),
+            )
+            .padding([0, 12]),
+        )
+        .id(self.scrollable.clone())
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn block<'a>(
+        &'a self,
+        block: &'a markdown::Block,
+        settings: &'a Settings,
+        appearance: &'a Appearance,
+    ) -> Element<'a, Message> {
+        match block {
+            markdown::Block::Heading(level, inlines) => {
+                let scale = HEADINGS[level.clamp(&1, &HEADINGS.len()) - 1];
+
+                self.inlines(inlines, BOLD, settings, appearance)
+                    .size(settings.text_size * scale)
+                    .into()
+            }
+            markdown::Block::Paragraph(inlines) => self
+                .inlines(inlines, Font::DEFAULT, settings, appearance)
+                .into(),
+            markdown::Block::Code(inlines) => container(
+                self.inlines(inlines, settings.font(), settings, appearance)
+                    .wrapping(text::Wrapping::None),
+            )
+            .width(Length::Fill)
+            .padding(8)
+            .style(style::code_block)
+            .into(),
+            markdown::Block::Quote(blocks) => container(
+                column(
+                    blocks
+                        .iter()
+                        .map(|block| self.block(block, settings, appearance)),
+                )
+                .spacing(8),
+            )
+            .width(Length::Fill)
+            .padding([6, 12])
+            .style(style::quote)
+            .into(),
+            markdown::Block::List { start, items } => {
+                column(items.iter().enumerate().map(|(index, blocks)| {
+                    let marker = match start {
+                        Some(start) => format!("{}.", start + index as u64),
+                        None => String::from("•"),
+                    };
+
+                    row![
+                        text(marker).size(settings.text_size),
+                        column(
+                            blocks
+                                .iter()
+                                .map(|block| self.block(block, settings, appearance))
+                        )
+                        .spacing(6),
+                    ]
+                    .spacing(8)
+                    .into()
+                }))
+                .spacing(4)
+                .into()
+            }
+            markdown::Block::Table { alignments, rows } => {
+                column(rows.iter().enumerate().map(|(index, cells)| {
+                    let header = index == 0;
+
+                    row(cells.iter().enumerate().map(|(column, inlines)| {
+                        let alignment = match alignments.get(column) {
+                            Some(pulldown_cmark::Alignment::Center) => Horizontal::Center,
+                            Some(pulldown_cmark::Alignment::Right) => Horizontal::Right,
+                            _ => Horizontal::Left,
+                        };
+
+                        let font = if header { BOLD } else { Font::DEFAULT };
+                        let cell = self.inlines(inlines, font, settings, appearance);
+
+                        container(cell)
+                            .width(Length::Fill)
+                            .align_x(alignment)
+                            .padding([4, 8])
+                            .style(move |theme| style::table_cell(theme, header))
+                            .into()
+                    }))
+                    .into()
+                }))
+                .into()
+            }
+            markdown::Block::Image { url, alt } => match self.resolve(url) {
+                Some(path) if !url.contains("://") => {
+                    let is_svg = path
+                        .extension()
+                        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"));
+
+                    if is_svg {
+                        svg(svg::Handle::from_path(path))
+                            .width(Length::Fill)
+                            .height(Length::Shrink)
+                            .content_fit(iced::ContentFit::ScaleDown)
+                            .into()
+                    } else {
+                        image(image::Handle::from_path(path))
+                            .width(Length::Fill)
+                            .content_fit(iced::ContentFit::ScaleDown)
+                            .into()
+                    }
+                }
+                // Remote images are not fetched.
+                _ => self
+                    .inlines(
+                        &[markdown::Inline {
+                            text: if alt.is_empty() {
+                                url.clone()
+                            } else {
+                                alt.clone()
+                            },
+                            link: Some(url.clone()),
+                            ..markdown::Inline::default()
+                        }],
+                        Font::DEFAULT,
+                        settings,
+                        appearance,
+                    )
+                    .into(),
+            },
+            markdown::Block::Rule => horizontal_rule(1).into(),
+        }
+    }
+
+    fn inlines<'a>(
+        &self,
+        inlines: &[markdown::Inline],
+        base: Font,
+        settings: &Settings,
+        appearance: &Appearance,
+    ) -> widget::text::Rich<'a, Message> {
+        let palette = appearance.theme.extended_palette();
+
+        rich_text(
+            inlines
+                .iter()
+                .map(|inline| {
+                    let font = Font {
+                        weight: if inline.strong {
+                            font::Weight::Bold
+                        } else {
+                            base.weight
+                        },
+                        style: if inline.emphasis {
+                            font::Style::Italic
+                        } else {
+                            base.style
+                        },
+                        ..if inline.code { settings.font() } else { base }
+                    };
+
+                    let span = span(inline.text.clone())
+                        .font(font)
+                        .strikethrough(inline.strikethrough)
+                        .color_maybe(inline.color);
+
+                    let span = if inline.code && inline.color.is_none() {
+                        span.background(palette.background.weak.color)
+                    } else {
+                        span
+                    };
+
+                    match &inline.link {
+                        Some(url) => span
+                            .color(palette.primary.base.color)
+                            .underline(true)
+                            .link(Message::LinkClicked(url.clone())),
+                        None => span,
+                    }
+                })
+                .collect::<Vec<_>>(),
+        )
+        .size(settings.text_size)
+    }
+
+    /// The path of a file linked to, relative to the file shown.
+    fn resolve(&self, link: &str) -> Option<PathBuf> {
+        let directory = self.path.as_deref()?.parent()?;
+
+        Some(directory.join(Path::new(link)))
+    }
+}
+
+pub fn title() -> &'static str {
+    "Preview"
+}
+
+/// The schemes of the links opened with the program of the system.
+const EXTERNAL: &[&str] = &["http", "https", "mailto"];
+
+/// The scheme of a link to something other than a file of the project.
+fn scheme(url: &str) -> Option<&str> {
+    let (scheme, rest) = url.split_once(':')?;
+
+    (rest.starts_with("//") || scheme.eq_ignore_ascii_case("mailto")).then_some(scheme)
+}
+
+/// Opens a web or mail link with the program of the system.
+fn open_external(url: &str) -> std::io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        std::process::Command::new("explorer")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+
+    let mut child = command.arg(url).spawn()?;
+
+    // Waited on aside, for the program not to linger once it exits.
+    std::thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_tell_their_scheme() {
+        assert_eq!(scheme("https://example.com"), Some("https"));
+        assert_eq!(scheme("MAILTO:someone@example.com"), Some("MAILTO"));
+        assert_eq!(scheme("file:///etc/passwd"), Some("file"));
+        assert_eq!(scheme("docs/guide.md#usage"), None);
+        assert_eq!(scheme("C:\\docs\\guide.md"), None);
+    }
+}
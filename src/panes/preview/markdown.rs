@@ -0,0 +1,300 @@
+use iced::Color;
+use iced::advanced::text::Highlighter as _;
+use iced::highlighter;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+
+/// A block of a Markdown document, ready to be shown.
+#[derive(Debug, Clone)]
+pub enum Block {
+    Heading(usize, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    /// Some code, highlighted.
+    Code(Vec<Inline>),
+    Quote(Vec<Block>),
+    List {
+        start: Option<u64>,
+        items: Vec<Vec<Block>>,
+    },
+    /// A table, its header being the first row.
+    Table {
+        alignments: Vec<Alignment>,
+        rows: Vec<Row>,
+    },
+    Image {
+        url: String,
+        alt: String,
+    },
+    Rule,
+}
+
+/// The cells of a row of a table.
+pub type Row = Vec<Vec<Inline>>;
+
+/// A piece of text within a block, styled the same way.
+#[derive(Debug, Clone, Default)]
+pub struct Inline {
+    pub text: String,
+    pub strong: bool,
+    pub emphasis: bool,
+    pub strikethrough: bool,
+    pub code: bool,
+    pub link: Option<String>,
+    /// The color of highlighted code.
+    pub color: Option<Color>,
+}
+
+/// The blocks some inlines or blocks are being added to.
+enum Container {
+    Root(Vec<Block>),
+    Quote(Vec<Block>),
+    List {
+        start: Option<u64>,
+        items: Vec<Vec<Block>>,
+    },
+}
+
+/// Parses a Markdown document, highlighting its code with a theme.
+pub fn parse(markdown: &str, theme: highlighter::Theme) -> Vec<Block> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+
+    let mut containers = vec![Container::Root(Vec::new())];
+    let mut inlines = Vec::new();
+    let mut style = Inline::default();
+    let mut code: Option<(String, String)> = None;
+    let mut image: Option<(String, String)> = None;
+    let mut table: Option<(Vec<Alignment>, Vec<Row>)> = None;
+    let mut metadata = false;
+
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::MetadataBlock(_)) => metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => metadata = false,
+            _ if metadata => {}
+            Event::Start(Tag::BlockQuote(_)) => {
+                flush(&mut containers, &mut inlines);
+
+                containers.push(Container::Quote(Vec::new()));
+            }
+            Event::End(TagEnd::BlockQuote) => {
+                flush(&mut containers, &mut inlines);
+
+                if let Some(Container::Quote(blocks)) = containers.pop() {
+                    push(&mut containers, Block::Quote(blocks));
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                flush(&mut containers, &mut inlines);
+
+                containers.push(Container::List {
+                    start,
+                    items: Vec::new(),
+                });
+            }
+            Event::End(TagEnd::List(_)) => {
+                if let Some(Container::List { start, items }) = containers.pop() {
+                    push(&mut containers, Block::List { start, items });
+                }
+            }
+            Event::Start(Tag::Item) => {
+                if let Some(Container::List { items, .. }) = containers.last_mut() {
+                    items.push(Vec::new());
+                }
+            }
+            // The items of tight lists hold their text without paragraphs.
+            Event::End(TagEnd::Item | TagEnd::Paragraph) => flush(&mut containers, &mut inlines),
+            Event::End(TagEnd::Heading(level)) => {
+                push(
+                    &mut containers,
+                    Block::Heading(level as usize, std::mem::take(&mut inlines)),
+                );
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                flush(&mut containers, &mut inlines);
+
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_owned()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+
+                code = Some((language, String::new()));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((language, code)) = code.take() {
+                    push(
+                        &mut containers,
+                        Block::Code(highlight(&code, &language, theme)),
+                    );
+                }
+            }
+            Event::Start(Tag::Table(alignments)) => table = Some((alignments, Vec::new())),
+            Event::End(TagEnd::Table) => {
+                if let Some((alignments, rows)) = table.take() {
+                    push(&mut containers, Block::Table { alignments, rows });
+                }
+            }
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                if let Some((_, rows)) = &mut table {
+                    rows.push(Vec::new());
+                }
+            }
+            Event::End(TagEnd::TableCell) => {
+                if let Some(row) = table.as_mut().and_then(|(_, rows)| rows.last_mut()) {
+                    row.push(std::mem::take(&mut inlines));
+                }
+            }
+            Event::Start(Tag::Emphasis) => style.emphasis = true,
+            Event::End(TagEnd::Emphasis) => style.emphasis = false,
+            Event::Start(Tag::Strong) => style.strong = true,
+            Event::End(TagEnd::Strong) => style.strong = false,
+            Event::Start(Tag::Strikethrough) => style.strikethrough = true,
+            Event::End(TagEnd::Strikethrough) => style.strikethrough = false,
+            Event::Start(Tag::Link { dest_url, .. }) => style.link = Some(dest_url.into_string()),
+            Event::End(TagEnd::Link) => style.link = None,
+            // Images are shown on their own, splitting their paragraph,
+            // except in tables where their description is shown instead.
+            Event::Start(Tag::Image { dest_url, .. }) if table.is_none() => {
+                flush(&mut containers, &mut inlines);
+
+                image = Some((dest_url.into_string(), String::new()));
+            }
+            Event::End(TagEnd::Image) => {
+                if let Some((url, alt)) = image.take() {
+                    push(&mut containers, Block::Image { url, alt });
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, code)) = &mut code {
+                    code.push_str(&text);
+                } else if let Some((_, alt)) = &mut image {
+                    alt.push_str(&text);
+                } else {
+                    inlines.push(Inline {
+                        text: text.into_string(),
+                        ..style.clone()
+                    });
+                }
+            }
+            Event::Code(text) | Event::InlineMath(text) | Event::DisplayMath(text) => {
+                inlines.push(Inline {
+                    text: text.into_string(),
+                    code: true,
+                    ..style.clone()
+                });
+            }
+            Event::SoftBreak => inlines.push(Inline {
+                text: String::from(" "),
+                ..style.clone()
+            }),
+            Event::HardBreak => inlines.push(Inline {
+                text: String::from("\n"),
+                ..style.clone()
+            }),
+            Event::TaskListMarker(checked) => inlines.push(Inline {
+                text: String::from(if checked { "[x] " } else { "[ ] " }),
+                code: true,
+                ..Inline::default()
+            }),
+            Event::FootnoteReference(name) => inlines.push(Inline {
+                text: format!("[{name}]"),
+                ..style.clone()
+            }),
+            Event::Rule => push(&mut containers, Block::Rule),
+            _ => {}
+        }
+    }
+
+    flush(&mut containers, &mut inlines);
+
+    // Containers left open by a document cut short are closed.
+    while containers.len() > 1 {
+        let block = match containers.pop() {
+            Some(Container::Quote(blocks)) => Block::Quote(blocks),
+            Some(Container::List { start, items }) => Block::List { start, items },
+            _ => continue,
+        };
+
+        push(&mut containers, block);
+    }
+
+    match containers.pop() {
+        Some(Container::Root(blocks)) => blocks,
+        _ => Vec::new(),
+    }
+}
+
+/// Adds a block to the innermost container.
+fn push(containers: &mut [Container], block: Block) {
+    match containers.last_mut() {
+        Some(Container::Root(blocks) | Container::Quote(blocks)) => blocks.push(block),
+        Some(Container::List { items, .. }) => {
+            if let Some(item) = items.last_mut() {
+                item.push(block);
+            }
+        }
+        None => {}
+    }
+}
+
+/// Adds the inlines gathered so far as a paragraph, if any.
+fn flush(containers: &mut [Container], inlines: &mut Vec<Inline>) {
+    if !inlines.is_empty() {
+        push(containers, Block::Paragraph(std::mem::take(inlines)));
+    }
+}
+
+/// Highlights some code in a language, named like in fenced code blocks.
+fn highlight(code: &str, language: &str, theme: highlighter::Theme) -> Vec<Inline> {
+    let mut highlighter = highlighter::Highlighter::new(&highlighter::Settings {
+        theme,
+        token: language.to_owned(),
+    });
+
+    let mut inlines = Vec::new();
+
+    for line in code.trim_end_matches('\n').split('\n') {
+        if !inlines.is_empty() {
+            inlines.push(Inline {
+                text: String::from("\n"),
+                code: true,
+                ..Inline::default()
+            });
+        }
+
+        let mut end = 0;
+
+        for (range, highlight) in highlighter.highlight_line(line) {
+            // Left unhighlighted between highlights.
+            if range.start > end {
+                inlines.push(Inline {
+                    text: line[end..range.start].to_owned(),
+                    code: true,
+                    ..Inline::default()
+                });
+            }
+
+            inlines.push(Inline {
+                text: line[range.clone()].to_owned(),
+                code: true,
+                color: highlight.color(),
+                ..Inline::default()
+            });
+
+            end = range.end;
+        }
+
+        if end < line.len() {
+            inlines.push(Inline {
+                text: line[end..].to_owned(),
+                code: true,
+                ..Inline::default()
+            });
+        }
+    }
+
+    inlines
+}
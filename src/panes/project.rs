@@ -1,28 +1,855 @@
-use iced::widget::text_editor::{self};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use iced::futures::StreamExt;
+use iced::widget::{
+    self, button, column, container, horizontal_space, mouse_area, row, text, text_input,
+};
+use iced::{Point, Subscription, Task, keyboard};
+use iced_aw::ContextMenu;
+
+use crate::{git, watcher};
+
+use super::style;
+
+mod files;
+
+/// How far the pointer moves, pressed on an entry, before the entry is
+/// dragged rather than clicked.
+const DRAG_DISTANCE: f32 = 4.0;
 
 pub struct Project {
-    pub content: text_editor::Content,
+    pub root: PathBuf,
+    pub excludes: Vec<String>,
+    pub expanded: BTreeSet<PathBuf>,
+    /// The listings of the expanded directories.
+    pub entries: BTreeMap<PathBuf, Vec<Entry>>,
+    pub selected: Option<PathBuf>,
+    pub statuses: git::Statuses,
+    refreshing: bool,
+    outdated: bool,
+    /// The file or directory being named, if any.
+    editing: Option<Editing>,
+    name: String,
+    input: text_input::Id,
+    /// The file or directory copied or cut, to be pasted.
+    clipboard: Option<Clipboard>,
+    /// The operations performed, most recent last, to be undone.
+    history: Vec<files::Done>,
+    hovered: Option<PathBuf>,
+    /// The entry pressed, and where the pointer first moved since.
+    pressed: Option<(PathBuf, Option<Point>)>,
+    dragging: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Editing {
+    Create { directory: PathBuf, is_dir: bool },
+    Rename(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+struct Clipboard {
+    path: PathBuf,
+    /// Whether the file is moved, rather than copied, when pasted.
+    cut: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_dir: bool,
 }
 
+#[derive(Debug, Clone)]
+pub enum Message {
+    Changed(Vec<PathBuf>),
+    StatusesLoaded(Result<git::Statuses, git::Error>),
+    Pressed(PathBuf),
+    Hovered(PathBuf),
+    Unhovered(PathBuf),
+    Moved(Point),
+    Released,
+    Left,
+    /// A command applied to the selected entry, or to the root.
+    Command(Command),
+    /// A command chosen in the context menu of an entry.
+    Menu(PathBuf, Command),
+    NameChanged(String),
+    NameSubmitted,
+    Performed(Result<files::Done, files::Error>),
+    Undone(Result<files::Done, files::Error>),
+}
+
+/// What can be done to the files of the project.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Message {}
+pub enum Command {
+    NewFile,
+    NewFolder,
+    Rename,
+    Duplicate,
+    Copy,
+    Cut,
+    Paste,
+    Delete,
+    Undo,
+    Cancel,
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    /// A file was selected and should be opened.
+    Open(PathBuf),
+    /// A file or directory was renamed or moved.
+    Renamed(PathBuf, PathBuf),
+    Error(String),
+}
 
 impl Project {
-    pub fn new() -> Self {
-        Self {
-            content: text_editor::Content::new(),
+    pub fn new(root: PathBuf, excludes: Vec<String>) -> (Self, Task<Message>) {
+        let mut project = Self {
+            root: root.clone(),
+            excludes,
+            expanded: BTreeSet::from([root.clone()]),
+            entries: BTreeMap::new(),
+            selected: None,
+            statuses: git::Statuses::default(),
+            refreshing: false,
+            outdated: false,
+            editing: None,
+            name: String::new(),
+            input: text_input::Id::unique(),
+            clipboard: None,
+            history: Vec::new(),
+            hovered: None,
+            pressed: None,
+            dragging: false,
+        };
+
+        project.list(&root);
+
+        let task = project.refresh();
+
+        (project, task)
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::Changed(paths) => {
+                let directories: BTreeSet<_> = paths
+                    .iter()
+                    .filter_map(|path| path.parent())
+                    .filter(|directory| self.entries.contains_key(*directory))
+                    .map(Path::to_owned)
+                    .collect();
+
+                for directory in directories {
+                    self.list(&directory);
+                }
+
+                self.entries.retain(|directory, _| directory.exists());
+                self.expanded.retain(|directory| directory.exists());
+
+                Action::Run(self.refresh())
+            }
+            Message::StatusesLoaded(result) => {
+                self.refreshing = false;
+
+                let action = match result {
+                    Ok(statuses) => {
+                        self.statuses = statuses;
+
+                        Action::None
+                    }
+                    Err(error) => Action::Error(error.to_string()),
+                };
+
+                if self.outdated {
+                    return Action::Run(self.refresh());
+                }
+
+                action
+            }
+            Message::Pressed(path) => {
+                self.pressed = Some((path, None));
+                self.dragging = false;
+
+                Action::None
+            }
+            Message::Hovered(path) => {
+                self.hovered = Some(path);
+
+                Action::None
+            }
+            Message::Unhovered(path) => {
+                if self.hovered.as_ref() == Some(&path) {
+                    self.hovered = None;
+                }
+
+                Action::None
+            }
+            Message::Moved(point) => {
+                if let Some((_, origin)) = &mut self.pressed {
+                    match origin {
+                        None => *origin = Some(point),
+                        Some(origin) => {
+                            self.dragging |= origin.distance(point) > DRAG_DISTANCE;
+                        }
+                    }
+                }
+
+                Action::None
+            }
+            Message::Released => {
+                let Some((path, _)) = self.pressed.take() else {
+                    return Action::None;
+                };
+
+                if std::mem::take(&mut self.dragging) {
+                    return self.drop(path);
+                }
+
+                self.selected = Some(path.clone());
+
+                if path.is_dir() {
+                    self.toggle(path);
+
+                    Action::None
+                } else {
+                    Action::Open(path)
+                }
+            }
+            Message::Left => {
+                self.hovered = None;
+                self.pressed = None;
+                self.dragging = false;
+
+                Action::None
+            }
+            Message::Command(command) => self.command(command),
+            Message::Menu(path, command) => {
+                self.selected = Some(path);
+
+                self.command(command)
+            }
+            Message::NameChanged(name) => {
+                self.name = name;
+
+                Action::None
+            }
+            Message::NameSubmitted => {
+                let Some(editing) = self.editing.take() else {
+                    return Action::None;
+                };
+
+                let name = self.name.trim();
+
+                if name.is_empty() {
+                    return Action::None;
+                }
+
+                if !files::is_valid(name) {
+                    return Action::Error(format!("\"{name}\" is not a valid file name"));
+                }
+
+                let operation = match editing {
+                    Editing::Create { directory, is_dir } => files::Operation::Create {
+                        path: directory.join(name),
+                        is_dir,
+                    },
+                    Editing::Rename(path) => {
+                        let to = path.with_file_name(name);
+
+                        if to == path {
+                            return Action::None;
+                        }
+
+                        files::Operation::Move { from: path, to }
+                    }
+                };
+
+                self.perform(operation)
+            }
+            Message::Performed(result) => match result {
+                Ok(done) => {
+                    let action = self.done(&done);
+
+                    self.history.push(done);
+
+                    action
+                }
+                Err(error) => Action::Error(error.to_string()),
+            },
+            Message::Undone(result) => match result {
+                Ok(done) => self.done(&done),
+                Err(error) => Action::Error(error.to_string()),
+            },
+        }
+    }
+
+    fn command(&mut self, command: Command) -> Action {
+        match command {
+            Command::NewFile | Command::NewFolder => {
+                let directory = self.target();
+
+                if !self.expanded.contains(&directory) {
+                    self.toggle(directory.clone());
+                }
+
+                self.editing = Some(Editing::Create {
+                    directory,
+                    is_dir: command == Command::NewFolder,
+                });
+                self.name.clear();
+
+                Action::Run(text_input::focus(self.input.clone()))
+            }
+            Command::Rename => {
+                let Some(path) = self.selected.clone() else {
+                    return Action::None;
+                };
+
+                self.name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                self.editing = Some(Editing::Rename(path));
+
+                Action::Run(Task::batch([
+                    text_input::focus(self.input.clone()),
+                    text_input::select_all(self.input.clone()),
+                ]))
+            }
+            Command::Duplicate => {
+                let Some(path) = self.selected.clone() else {
+                    return Action::None;
+                };
+
+                let directory = path.parent().unwrap_or(&self.root);
+
+                self.perform(files::Operation::Copy {
+                    to: files::free(directory, &path),
+                    from: path,
+                })
+            }
+            Command::Copy | Command::Cut => {
+                self.clipboard = self.selected.clone().map(|path| Clipboard {
+                    path,
+                    cut: command == Command::Cut,
+                });
+
+                Action::None
+            }
+            Command::Paste => {
+                let Some(clipboard) = self.clipboard.clone() else {
+                    return Action::None;
+                };
+
+                let directory = self.target();
+
+                if clipboard.cut {
+                    self.clipboard = None;
+
+                    self.relocate(clipboard.path, &directory)
+                } else {
+                    self.perform(files::Operation::Copy {
+                        to: files::free(&directory, &clipboard.path),
+                        from: clipboard.path,
+                    })
+                }
+            }
+            Command::Delete => match self.selected.clone() {
+                Some(path) if path != self.root => self.perform(files::Operation::Trash(path)),
+                _ => Action::None,
+            },
+            Command::Undo => match self.history.pop().and_then(|done| done.undo()) {
+                Some(operation) => {
+                    Action::Run(Task::perform(files::perform(operation), Message::Undone))
+                }
+                None => Action::None,
+            },
+            Command::Cancel => {
+                self.editing = None;
+
+                Action::None
+            }
+        }
+    }
+
+    fn perform(&self, operation: files::Operation) -> Action {
+        Action::Run(Task::perform(files::perform(operation), Message::Performed))
+    }
+
+    /// Moves a file or directory into a directory, unless it is already
+    /// there.
+    fn relocate(&mut self, path: PathBuf, directory: &Path) -> Action {
+        let Some(name) = path.file_name() else {
+            return Action::None;
+        };
+
+        if path.parent() == Some(directory) {
+            return Action::None;
+        }
+
+        self.perform(files::Operation::Move {
+            to: directory.join(name),
+            from: path,
+        })
+    }
+
+    /// Moves a dragged entry into the directory under the pointer.
+    fn drop(&mut self, path: PathBuf) -> Action {
+        let directory = match &self.hovered {
+            Some(hovered) if hovered.is_dir() => hovered.clone(),
+            Some(hovered) => hovered.parent().unwrap_or(&self.root).to_owned(),
+            None => self.root.clone(),
+        };
+
+        self.relocate(path, &directory)
+    }
+
+    /// Shows the result of an operation without waiting for the watcher.
+    fn done(&mut self, done: &files::Done) -> Action {
+        let mut directories: BTreeSet<PathBuf> = BTreeSet::new();
+
+        match done {
+            files::Done::Created(path)
+            | files::Done::Copied(path)
+            | files::Done::Restored(path) => {
+                directories.extend(path.parent().map(Path::to_owned));
+            }
+            files::Done::Moved { from, to } => {
+                directories.extend(from.parent().map(Path::to_owned));
+                directories.extend(to.parent().map(Path::to_owned));
+
+                // Expanded directories stay expanded where they moved.
+                self.expanded = std::mem::take(&mut self.expanded)
+                    .into_iter()
+                    .map(|directory| renamed(&directory, from, to).unwrap_or(directory))
+                    .collect();
+                self.entries
+                    .retain(|directory, _| !directory.starts_with(from));
+
+                for directory in self.expanded.clone() {
+                    if directory.starts_with(to) {
+                        self.list(&directory);
+                    }
+                }
+
+                if let Some(clipboard) = &mut self.clipboard
+                    && let Some(path) = renamed(&clipboard.path, from, to)
+                {
+                    clipboard.path = path;
+                }
+            }
+            files::Done::Trashed(trashed) => {
+                directories.extend(trashed.original.parent().map(Path::to_owned));
+
+                self.entries
+                    .retain(|directory, _| !directory.starts_with(&trashed.original));
+                self.expanded
+                    .retain(|directory| !directory.starts_with(&trashed.original));
+            }
+        }
+
+        for directory in directories {
+            if self.entries.contains_key(&directory) {
+                self.list(&directory);
+            }
+        }
+
+        self.selected = done.path().map(Path::to_owned);
+
+        match done {
+            files::Done::Moved { from, to } => Action::Renamed(from.clone(), to.clone()),
+            files::Done::Created(path) if path.is_file() => Action::Open(path.clone()),
+            _ => Action::None,
+        }
+    }
+
+    /// The directory of the selected entry, where new files go.
+    fn target(&self) -> PathBuf {
+        match &self.selected {
+            Some(selected) if selected.is_dir() => selected.clone(),
+            Some(selected) => selected.parent().unwrap_or(&self.root).to_owned(),
+            None => self.root.clone(),
+        }
+    }
+
+    fn toggle(&mut self, path: PathBuf) {
+        if self.expanded.remove(&path) {
+            self.entries
+                .retain(|directory, _| !directory.starts_with(&path));
+            self.expanded
+                .retain(|directory| !directory.starts_with(&path));
+        } else {
+            self.list(&path);
+            self.expanded.insert(path);
         }
     }
 
-    pub fn update(&mut self, message: Message) {
-        match message {}
+    /// Updates the excluded names and the listings depending on them.
+    pub fn exclude(&mut self, excludes: Vec<String>) {
+        if self.excludes == excludes {
+            return;
+        }
+
+        self.excludes = excludes;
+
+        for directory in self.entries.keys().cloned().collect::<Vec<_>>() {
+            self.list(&directory);
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        let root = self.root.clone();
+        let excludes = self.excludes.clone();
+
+        let watcher = Subscription::run_with_id(
+            (root.clone(), excludes.clone()),
+            watcher::watch(vec![(root.clone(), watcher::RecursiveMode::Recursive)]).filter_map(
+                move |paths| {
+                    let paths: Vec<_> = paths
+                        .into_iter()
+                        .filter(|path| is_relevant(&root, &excludes, path))
+                        .collect();
+
+                    async move { (!paths.is_empty()).then_some(Message::Changed(paths)) }
+                },
+            ),
+        );
+
+        // The name input takes the key presses, so Escape is listened to
+        // even when captured.
+        let escape = if self.editing.is_some() {
+            iced::event::listen_with(|event, _, _| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(keyboard::key::Named::Escape),
+                    ..
+                }) => Some(Message::Command(Command::Cancel)),
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([watcher, escape])
     }
 
-    pub fn view(&self) -> iced::Element<Message> {
-        iced::widget::text("Welcome to the project!").into()
+    pub fn view(&self) -> iced::Element<'_, Message> {
+        let mut rows = Vec::new();
+
+        self.rows(&self.root, 0, &mut rows);
+
+        let toolbar = row![
+            horizontal_space(),
+            button(text("New file").size(12)).on_press(Message::Command(Command::NewFile)),
+            button(text("New folder").size(12)).on_press(Message::Command(Command::NewFolder)),
+        ]
+        .spacing(4);
+
+        let tree = mouse_area(
+            widget::scrollable(widget::column(rows))
+                .width(iced::Length::Fill)
+                .height(iced::Length::Fill),
+        )
+        .on_move(Message::Moved)
+        .on_release(Message::Released)
+        .on_exit(Message::Left);
+
+        column![toolbar, tree].spacing(4).into()
+    }
+
+    fn rows<'a>(
+        &'a self,
+        directory: &Path,
+        depth: u16,
+        rows: &mut Vec<iced::Element<'a, Message>>,
+    ) {
+        let Some(entries) = self.entries.get(directory) else {
+            return;
+        };
+
+        if let Some(Editing::Create {
+            directory: parent,
+            is_dir,
+        }) = &self.editing
+            && parent == directory
+        {
+            rows.push(self.name_input(depth, if *is_dir { "▸" } else { " " }));
+        }
+
+        for entry in entries {
+            let expanded = self.expanded.contains(&entry.path);
+            let status = self.statuses.get(&entry.path);
+            let selected = self.selected.as_ref() == Some(&entry.path);
+            let hovered = self.hovered.as_ref() == Some(&entry.path);
+
+            // The directory a dragged entry would be dropped into is
+            // highlighted.
+            let target = self.dragging
+                && self.hovered.as_ref().is_some_and(|hovered| {
+                    hovered == &entry.path && entry.is_dir
+                        || !entry.is_dir && hovered.parent() == Some(&entry.path)
+                });
+
+            let icon = match (entry.is_dir, expanded) {
+                (true, true) => "▾",
+                (true, false) => "▸",
+                (false, _) => " ",
+            };
+
+            if self.editing == Some(Editing::Rename(entry.path.clone())) {
+                rows.push(self.name_input(depth, icon));
+            } else {
+                let cut = self
+                    .clipboard
+                    .as_ref()
+                    .is_some_and(|clipboard| clipboard.cut && clipboard.path == entry.path);
+
+                let label = row![
+                    horizontal_space().width(depth * 12),
+                    text(icon).size(12).width(12),
+                    text(&entry.name).size(12).style(move |theme| {
+                        let mut style = style::git_status(theme, status);
+
+                        if cut {
+                            style.color = style.color.map(|color| color.scale_alpha(0.5));
+                        }
+
+                        style
+                    }),
+                    horizontal_space(),
+                    text(status.map_or("", git::Status::badge))
+                        .size(12)
+                        .style(move |theme| style::git_status(theme, status)),
+                ]
+                .spacing(4);
+
+                // The button only draws the entry: the mouse area tells
+                // clicks from drags.
+                let entry_button = button(label)
+                    .width(iced::Length::Fill)
+                    .padding([1, 4])
+                    .style(move |theme, _| {
+                        style::entry(
+                            theme,
+                            if hovered {
+                                button::Status::Hovered
+                            } else {
+                                button::Status::Active
+                            },
+                            selected || target,
+                        )
+                    });
+
+                let path = entry.path.clone();
+                let pasting = self.clipboard.is_some();
+
+                rows.push(
+                    ContextMenu::new(
+                        mouse_area(entry_button)
+                            .on_press(Message::Pressed(entry.path.clone()))
+                            .on_enter(Message::Hovered(entry.path.clone()))
+                            .on_exit(Message::Unhovered(entry.path.clone())),
+                        move || menu(&path, pasting),
+                    )
+                    .into(),
+                );
+            }
+
+            if expanded {
+                self.rows(&entry.path, depth + 1, rows);
+            }
+        }
+    }
+
+    fn name_input<'a>(&'a self, depth: u16, icon: &'a str) -> iced::Element<'a, Message> {
+        row![
+            horizontal_space().width(depth * 12),
+            text(icon).size(12).width(12),
+            text_input("Name", &self.name)
+                .id(self.input.clone())
+                .size(12)
+                .padding([0, 2])
+                .on_input(Message::NameChanged)
+                .on_submit(Message::NameSubmitted),
+        ]
+        .spacing(4)
+        .padding([1, 4])
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+
+    /// Reads the contents of a directory, directories first.
+    fn list(&mut self, directory: &Path) {
+        let Ok(read) = std::fs::read_dir(directory) else {
+            self.entries.remove(directory);
+            self.expanded.remove(directory);
+            return;
+        };
+
+        let mut entries: Vec<_> = read
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+
+                if self.excludes.iter().any(|pattern| glob(pattern, &name)) {
+                    return None;
+                }
+
+                Some(Entry {
+                    is_dir: entry.path().is_dir(),
+                    path: entry.path(),
+                    name,
+                })
+            })
+            .collect();
+
+        entries.sort_by(|a, b| {
+            b.is_dir
+                .cmp(&a.is_dir)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+
+        self.entries.insert(directory.to_owned(), entries);
+    }
+
+    /// Recomputes the git statuses, once at a time.
+    fn refresh(&mut self) -> Task<Message> {
+        if self.refreshing {
+            self.outdated = true;
+
+            return Task::none();
+        }
+
+        self.refreshing = true;
+        self.outdated = false;
+
+        Task::perform(git::statuses(self.root.clone()), Message::StatusesLoaded)
     }
 }
 
 pub fn title() -> &'static str {
     "Project"
 }
+
+/// The context menu of an entry.
+fn menu<'a>(path: &Path, pasting: bool) -> iced::Element<'a, Message> {
+    let item = |label: &'a str, command: Command, enabled: bool| {
+        button(text(label).size(12))
+            .width(iced::Length::Fill)
+            .padding([2, 8])
+            .style(|theme, status| style::entry(theme, status, false))
+            .on_press_maybe(enabled.then(|| Message::Menu(path.to_owned(), command)))
+    };
+
+    container(
+        column![
+            item("New file", Command::NewFile, true),
+            item("New folder", Command::NewFolder, true),
+            item("Rename", Command::Rename, true),
+            item("Duplicate", Command::Duplicate, true),
+            item("Copy", Command::Copy, true),
+            item("Cut", Command::Cut, true),
+            item("Paste", Command::Paste, pasting),
+            item("Delete", Command::Delete, true),
+        ]
+        .width(140),
+    )
+    .padding(2)
+    .style(style::popup)
+    .into()
+}
+
+/// Where a path is after a file or directory moved, if it moved with it.
+pub fn renamed(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(from).ok()?;
+
+    Some(if relative.as_os_str().is_empty() {
+        to.to_owned()
+    } else {
+        to.join(relative)
+    })
+}
+
+/// Whether a change may affect the tree or the git statuses.
+fn is_relevant(root: &Path, excludes: &[String], path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+
+    if relative.starts_with(".git") {
+        return relative == Path::new(".git/index")
+            || relative == Path::new(".git/HEAD")
+            || relative.starts_with(".git/refs");
+    }
+
+    !relative.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+
+        excludes.iter().any(|pattern| glob(pattern, &name))
+    })
+}
+
+/// Matches a name against a pattern, where `*` matches any sequence of
+/// characters and `?` any single character.
+pub fn glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob;
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob("target", "target"));
+        assert!(!glob("target", "targets"));
+        assert!(glob("*.log", "build.log"));
+        assert!(glob("*.log", ".log"));
+        assert!(!glob("*.log", "build.log.old"));
+        assert!(glob("node_*", "node_modules"));
+        assert!(glob("?.txt", "a.txt"));
+        assert!(!glob("?.txt", "ab.txt"));
+        assert!(glob("*", ""));
+        assert!(!glob("", "a"));
+    }
+
+    #[test]
+    fn glob_backtracks() {
+        assert!(glob("*a*b", "xaxxab"));
+        assert!(glob("a*b*c", "abbbc"));
+        assert!(!glob("a*b*c", "acb"));
+        assert!(glob("**ö", "föö"));
+    }
+}
@@ -0,0 +1,202 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::trash;
+
+pub use trash::Error;
+
+/// A change to the files of the project.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Create {
+        path: PathBuf,
+        is_dir: bool,
+    },
+    /// Renames or moves a file or directory, never over another.
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Copy {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Trash(PathBuf),
+    Restore(trash::Trashed),
+}
+
+/// An operation performed, as needed to undo it.
+#[derive(Debug, Clone)]
+pub enum Done {
+    Created(PathBuf),
+    Moved { from: PathBuf, to: PathBuf },
+    Copied(PathBuf),
+    Trashed(trash::Trashed),
+    Restored(PathBuf),
+}
+
+impl Done {
+    /// The operation undoing this one, if any.
+    pub fn undo(&self) -> Option<Operation> {
+        match self {
+            Done::Created(path) | Done::Copied(path) => Some(Operation::Trash(path.clone())),
+            Done::Moved { from, to } => Some(Operation::Move {
+                from: to.clone(),
+                to: from.clone(),
+            }),
+            Done::Trashed(trashed) => Some(Operation::Restore(trashed.clone())),
+            Done::Restored(_) => None,
+        }
+    }
+
+    /// The file or directory the operation ended with, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Done::Created(path) | Done::Copied(path) | Done::Restored(path) => Some(path),
+            Done::Moved { to, .. } => Some(to),
+            Done::Trashed(_) => None,
+        }
+    }
+}
+
+pub async fn perform(operation: Operation) -> Result<Done, Error> {
+    match operation {
+        Operation::Trash(path) => trash::trash(path).await.map(Done::Trashed),
+        Operation::Restore(trashed) => trash::restore(trashed).await.map(Done::Restored),
+        operation => tokio::task::spawn_blocking(move || run(operation))
+            .await
+            .unwrap_or_else(|error| {
+                Err(Error {
+                    path: PathBuf::new(),
+                    message: error.to_string(),
+                })
+            }),
+    }
+}
+
+fn run(operation: Operation) -> Result<Done, Error> {
+    match operation {
+        Operation::Create { path, is_dir } => {
+            let result = if is_dir {
+                std::fs::create_dir(&path)
+            } else {
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(&path)
+                    .map(|_| ())
+            };
+
+            result
+                .map(|()| Done::Created(path.clone()))
+                .map_err(|error| self::error(&path, error))
+        }
+        Operation::Move { from, to } => {
+            if to == from {
+                return Ok(Done::Moved { from, to });
+            }
+
+            if to.starts_with(&from) {
+                return Err(Error {
+                    path: from,
+                    message: String::from("cannot be moved into itself"),
+                });
+            }
+
+            if to.exists() {
+                return Err(self::error(
+                    &to,
+                    io::Error::from(io::ErrorKind::AlreadyExists),
+                ));
+            }
+
+            trash::relocate(&from, &to)
+                .map(|()| Done::Moved {
+                    from: from.clone(),
+                    to,
+                })
+                .map_err(|error| self::error(&from, error))
+        }
+        Operation::Copy { from, to } => {
+            if to.starts_with(&from) {
+                return Err(Error {
+                    path: from,
+                    message: String::from("cannot be copied into itself"),
+                });
+            }
+
+            trash::copy(&from, &to)
+                .map(|()| Done::Copied(to))
+                .map_err(|error| self::error(&from, error))
+        }
+        Operation::Trash(_) | Operation::Restore(_) => unreachable!("performed asynchronously"),
+    }
+}
+
+/// A free path for a copy of a file in a directory: its name, or its name
+/// followed by `copy` and a number before its extension.
+pub fn free(directory: &Path, source: &Path) -> PathBuf {
+    let name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let path = directory.join(&name);
+
+    if !path.exists() {
+        return path;
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !source.is_dir() => {
+            (stem.to_owned(), format!(".{extension}"))
+        }
+        _ => (name.clone(), String::new()),
+    };
+
+    (1..)
+        .map(|attempt| {
+            directory.join(if attempt == 1 {
+                format!("{stem} copy{extension}")
+            } else {
+                format!("{stem} copy {attempt}{extension}")
+            })
+        })
+        .find(|path| !path.exists())
+        .unwrap_or(path)
+}
+
+/// Whether a name typed for a file is acceptable, rather than a path.
+pub fn is_valid(name: &str) -> bool {
+    let name = name.trim();
+
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+fn error(path: &Path, error: io::Error) -> Error {
+    Error {
+        path: path.to_owned(),
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_in_place_does_nothing() {
+        let path = std::env::temp_dir().join(format!("rine-files-{}", std::process::id()));
+        std::fs::write(&path, "contents").unwrap();
+
+        let done = run(Operation::Move {
+            from: path.clone(),
+            to: path.clone(),
+        });
+
+        assert!(matches!(done, Ok(Done::Moved { from, to }) if from == path && to == path));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "contents");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
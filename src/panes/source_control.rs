@@ -0,0 +1,567 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use iced::widget::{
+    self, button, checkbox, column, horizontal_space, pick_list, row, text, text_editor, text_input,
+};
+use iced::{Element, Task};
+
+use crate::settings::Settings;
+use crate::{diff, git};
+
+use super::style;
+
+pub struct SourceControl {
+    pub root: PathBuf,
+    pub changes: git::Changes,
+    /// The files whose hunks are shown.
+    pub expanded: BTreeSet<(Group, PathBuf)>,
+    pub message: text_editor::Content,
+    pub amend: bool,
+    /// The name of the branch to create.
+    pub branch: String,
+    /// Whether an operation is running.
+    pub busy: bool,
+    refreshing: bool,
+    outdated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Group {
+    Staged,
+    Unstaged,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Loaded(Result<git::Changes, git::Error>),
+    Toggle(Group, PathBuf),
+    Stage(Vec<PathBuf>),
+    Unstage(Vec<PathBuf>),
+    StageHunk(PathBuf, usize),
+    UnstageHunk(PathBuf, usize),
+    CommitEdited(text_editor::Action),
+    AmendToggled(bool),
+    Commit,
+    Checkout(String),
+    BranchEdited(String),
+    CreateBranch,
+    Stash,
+    Pop(usize),
+    Compare(PathBuf),
+    /// An operation finished, with something to report.
+    Done(Result<Option<String>, git::Error>),
+    /// A commit was made, or failed, keeping its message to try again.
+    Committed(Result<Option<String>, git::Error>),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    /// An operation succeeded and should be reported.
+    Info(String, Task<Message>),
+    /// A file should be compared to its version at `HEAD`.
+    Compare(PathBuf),
+    /// Something failed and should be reported.
+    Error(String, Task<Message>),
+}
+
+impl SourceControl {
+    pub fn new(root: PathBuf) -> (Self, Task<Message>) {
+        let mut source_control = Self {
+            root,
+            changes: git::Changes::default(),
+            expanded: BTreeSet::new(),
+            message: text_editor::Content::new(),
+            amend: false,
+            branch: String::new(),
+            busy: false,
+            refreshing: false,
+            outdated: false,
+        };
+
+        let task = source_control.refresh();
+
+        (source_control, task)
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::Loaded(result) => {
+                self.refreshing = false;
+
+                let refresh = if self.outdated {
+                    self.refresh()
+                } else {
+                    Task::none()
+                };
+
+                match result {
+                    Ok(changes) => {
+                        self.changes = changes;
+
+                        Action::Run(refresh)
+                    }
+                    Err(error) => Action::Error(error.to_string(), refresh),
+                }
+            }
+            Message::Toggle(group, path) => {
+                if !self.expanded.remove(&(group, path.clone())) {
+                    self.expanded.insert((group, path));
+                }
+
+                Action::None
+            }
+            Message::Stage(paths) => {
+                self.run(async move { git::stage(paths).await.map(|()| None) })
+            }
+            Message::Unstage(paths) => {
+                self.run(async move { git::unstage(paths).await.map(|()| None) })
+            }
+            Message::StageHunk(path, index) => {
+                let Some((change, hunk)) = self.hunk(Group::Unstaged, &path, index) else {
+                    return Action::None;
+                };
+
+                let contents = change.new.clone();
+                let format = change.format;
+
+                self.run(async move {
+                    git::stage_hunk(path, contents, format, hunk)
+                        .await
+                        .map(|()| None)
+                })
+            }
+            Message::UnstageHunk(path, index) => {
+                let Some((_, hunk)) = self.hunk(Group::Staged, &path, index) else {
+                    return Action::None;
+                };
+
+                self.run(async move { git::unstage_hunk(path, hunk).await.map(|()| None) })
+            }
+            Message::CommitEdited(action) => {
+                self.message.perform(action);
+
+                Action::None
+            }
+            Message::AmendToggled(amend) => {
+                self.amend = amend;
+
+                if amend
+                    && self.message.text().trim().is_empty()
+                    && let Some(message) = &self.changes.message
+                {
+                    self.message = text_editor::Content::with_text(message.trim_end());
+                }
+
+                Action::None
+            }
+            Message::Commit => {
+                let message = self.message.text().trim().to_owned();
+
+                if message.is_empty() {
+                    return Action::Error(
+                        String::from("git: the commit message is empty"),
+                        Task::none(),
+                    );
+                }
+
+                let (root, amend) = (self.root.clone(), self.amend);
+
+                self.run_then(
+                    async move {
+                        let id = git::commit(root, message, amend).await?;
+
+                        Ok(Some(if amend {
+                            format!("Amended commit {id}")
+                        } else {
+                            format!("Committed {id}")
+                        }))
+                    },
+                    Message::Committed,
+                )
+            }
+            Message::Checkout(branch) => {
+                let root = self.root.clone();
+
+                self.run(async move {
+                    git::checkout(root, branch.clone()).await?;
+
+                    Ok(Some(format!("Switched to branch {branch}")))
+                })
+            }
+            Message::BranchEdited(branch) => {
+                self.branch = branch;
+
+                Action::None
+            }
+            Message::CreateBranch => {
+                let branch = std::mem::take(&mut self.branch).trim().to_owned();
+
+                if branch.is_empty() {
+                    return Action::None;
+                }
+
+                let root = self.root.clone();
+
+                self.run(async move {
+                    git::create_branch(root, branch.clone()).await?;
+
+                    Ok(Some(format!("Switched to a new branch {branch}")))
+                })
+            }
+            Message::Stash => {
+                let root = self.root.clone();
+
+                self.run(async move { git::stash(root).await.map(|()| None) })
+            }
+            Message::Pop(stash) => {
+                let root = self.root.clone();
+
+                self.run(async move { git::pop(root, stash).await.map(|()| None) })
+            }
+            Message::Compare(path) => Action::Compare(path),
+            Message::Committed(result) => {
+                if result.is_ok() {
+                    self.message = text_editor::Content::new();
+                    self.amend = false;
+                }
+
+                self.update(Message::Done(result))
+            }
+            Message::Done(result) => {
+                self.busy = false;
+
+                let refresh = self.refresh();
+
+                match result {
+                    Ok(None) => Action::Run(refresh),
+                    Ok(Some(info)) => Action::Info(info, refresh),
+                    Err(error) => Action::Error(error.to_string(), refresh),
+                }
+            }
+        }
+    }
+
+    /// Reloads the changes, once at a time.
+    pub fn refresh(&mut self) -> Task<Message> {
+        if self.refreshing {
+            self.outdated = true;
+
+            return Task::none();
+        }
+
+        self.refreshing = true;
+        self.outdated = false;
+
+        Task::perform(git::changes(self.root.clone()), Message::Loaded)
+    }
+
+    pub fn view<'a>(&'a self, settings: &'a Settings) -> Element<'a, Message> {
+        let branch = row![
+            pick_list(
+                self.changes.branches.as_slice(),
+                self.changes.branch.as_ref(),
+                Message::Checkout,
+            )
+            .placeholder("No branch")
+            .text_size(12),
+            text_input("New branch", &self.branch)
+                .size(12)
+                .on_input(Message::BranchEdited)
+                .on_submit(Message::CreateBranch),
+            button(text("Create").size(12)).on_press_maybe(
+                (!self.busy && !self.branch.trim().is_empty()).then_some(Message::CreateBranch),
+            ),
+        ]
+        .spacing(5);
+
+        let can_commit = !self.busy && (self.amend || !self.changes.staged.is_empty());
+
+        let commit = column![
+            text_editor(&self.message)
+                .placeholder("Commit message")
+                .size(12)
+                .height(80)
+                .on_action(Message::CommitEdited),
+            row![
+                checkbox("Amend", self.amend)
+                    .size(14)
+                    .text_size(12)
+                    .on_toggle_maybe(
+                        self.changes
+                            .message
+                            .is_some()
+                            .then_some(Message::AmendToggled)
+                    ),
+                horizontal_space(),
+                button(text("Commit").size(12))
+                    .on_press_maybe(can_commit.then_some(Message::Commit)),
+            ]
+            .spacing(5),
+        ]
+        .spacing(5);
+
+        let mut content = column![branch, commit].spacing(10);
+
+        content = content.push(self.group(Group::Staged, settings));
+        content = content.push(self.group(Group::Unstaged, settings));
+
+        let stashes = column![row![
+            text(format!("Stashes ({})", self.changes.stashes.len())).size(12),
+            horizontal_space(),
+            button(text("Stash").size(12)).on_press_maybe(
+                (!self.busy && !self.changes.unstaged.is_empty()).then_some(Message::Stash)
+            ),
+        ]]
+        .extend(
+            self.changes
+                .stashes
+                .iter()
+                .enumerate()
+                .map(|(index, stash)| {
+                    row![
+                        text(stash).size(12),
+                        horizontal_space(),
+                        button(text("Pop").size(12))
+                            .padding([1, 6])
+                            .on_press_maybe((!self.busy).then_some(Message::Pop(index))),
+                    ]
+                    .spacing(5)
+                    .into()
+                }),
+        )
+        .spacing(2);
+
+        widget::scrollable(content.push(stashes))
+            .width(iced::Length::Fill)
+            .height(iced::Length::Fill)
+            .into()
+    }
+
+    fn group<'a>(&'a self, group: Group, settings: &'a Settings) -> Element<'a, Message> {
+        let (title, changes, action, verb) = match group {
+            Group::Staged => (
+                "Staged",
+                &self.changes.staged,
+                Message::Unstage as fn(_) -> _,
+                "Unstage",
+            ),
+            Group::Unstaged => (
+                "Changes",
+                &self.changes.unstaged,
+                Message::Stage as fn(_) -> _,
+                "Stage",
+            ),
+        };
+
+        let all = changes.iter().map(|change| change.path.clone()).collect();
+
+        let header = row![
+            text(format!("{title} ({})", changes.len())).size(12),
+            horizontal_space(),
+            button(text(format!("{verb} all")).size(12))
+                .on_press_maybe((!self.busy && !changes.is_empty()).then(|| action(all))),
+        ];
+
+        let mut rows = vec![header.into()];
+
+        for change in changes {
+            let expanded = self.expanded.contains(&(group, change.path.clone()));
+            let status = Some(change.kind.status());
+
+            let label = row![
+                text(if expanded { "▾" } else { "▸" }).size(12).width(12),
+                text(relative(&self.root, &change.path))
+                    .size(12)
+                    .style(move |theme| style::git_status(theme, status)),
+                horizontal_space(),
+                text(change.kind.badge())
+                    .size(12)
+                    .style(move |theme| style::git_status(theme, status)),
+            ]
+            .spacing(4);
+
+            rows.push(
+                row![
+                    button(label)
+                        .width(iced::Length::Fill)
+                        .padding([1, 4])
+                        .style(|theme, status| style::entry(theme, status, false))
+                        .on_press(Message::Toggle(group, change.path.clone())),
+                    button(text("Diff").size(12))
+                        .padding([1, 6])
+                        .on_press(Message::Compare(change.path.clone())),
+                    button(text(verb).size(12))
+                        .padding([1, 6])
+                        .on_press_maybe((!self.busy).then(|| action(vec![change.path.clone()]))),
+                ]
+                .spacing(5)
+                .into(),
+            );
+
+            if expanded {
+                rows.extend(
+                    change
+                        .hunks
+                        .iter()
+                        .enumerate()
+                        .map(|(index, hunk)| self.hunk_view(group, change, index, hunk, settings)),
+                );
+            }
+        }
+
+        column(rows).spacing(2).into()
+    }
+
+    fn hunk_view<'a>(
+        &'a self,
+        group: Group,
+        change: &'a git::Change,
+        index: usize,
+        hunk: &diff::Hunk,
+        settings: &'a Settings,
+    ) -> Element<'a, Message> {
+        let (verb, message) = match group {
+            Group::Staged => (
+                "Unstage hunk",
+                Message::UnstageHunk(change.path.clone(), index),
+            ),
+            Group::Unstaged => ("Stage hunk", Message::StageHunk(change.path.clone(), index)),
+        };
+
+        let header = row![
+            text(format!(
+                "@@ -{},{} +{},{} @@",
+                hunk.old.start + 1,
+                hunk.old.len(),
+                hunk.new.start + 1,
+                hunk.new.len()
+            ))
+            .font(settings.font())
+            .size(12),
+            horizontal_space(),
+            button(text(verb).size(12))
+                .padding([1, 6])
+                .on_press_maybe((!self.busy).then_some(message)),
+        ];
+
+        let removed = diff::lines(&change.old, hunk.old.clone())
+            .lines()
+            .map(|line| {
+                text(format!("-{line}"))
+                    .font(settings.font())
+                    .size(12)
+                    .style(text::danger)
+                    .into()
+            });
+
+        let added = diff::lines(&change.new, hunk.new.clone())
+            .lines()
+            .map(|line| {
+                text(format!("+{line}"))
+                    .font(settings.font())
+                    .size(12)
+                    .style(text::success)
+                    .into()
+            });
+
+        widget::container(column![header].extend(removed).extend(added))
+            .padding(iced::Padding::default().left(16))
+            .into()
+    }
+
+    /// Starts an operation, unless one is running.
+    fn run(
+        &mut self,
+        operation: impl Future<Output = Result<Option<String>, git::Error>> + Send + 'static,
+    ) -> Action {
+        self.run_then(operation, Message::Done)
+    }
+
+    /// Runs an operation, reporting its result with a message other than
+    /// [`Message::Done`].
+    fn run_then(
+        &mut self,
+        operation: impl Future<Output = Result<Option<String>, git::Error>> + Send + 'static,
+        done: fn(Result<Option<String>, git::Error>) -> Message,
+    ) -> Action {
+        if self.busy {
+            return Action::None;
+        }
+
+        self.busy = true;
+
+        Action::Run(Task::perform(operation, done))
+    }
+
+    fn hunk(&self, group: Group, path: &Path, index: usize) -> Option<(&git::Change, diff::Hunk)> {
+        let changes = match group {
+            Group::Staged => &self.changes.staged,
+            Group::Unstaged => &self.changes.unstaged,
+        };
+
+        let change = changes.iter().find(|change| change.path == path)?;
+
+        Some((change, change.hunks.get(index)?.clone()))
+    }
+}
+
+pub fn title() -> &'static str {
+    "Source Control"
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed() -> git::Error {
+        git::Error {
+            message: String::from("git: failed"),
+        }
+    }
+
+    #[test]
+    fn failed_operations_still_refresh() {
+        let (mut source_control, _) = SourceControl::new(PathBuf::from("."));
+        source_control.update(Message::Loaded(Ok(git::Changes::default())));
+
+        // The error comes with the refresh it started, which is run and
+        // reloads the changes.
+        let Action::Error(_, _refresh) = source_control.update(Message::Done(Err(failed()))) else {
+            panic!("a failed operation is reported");
+        };
+
+        assert!(source_control.refreshing);
+
+        source_control.update(Message::Loaded(Ok(git::Changes::default())));
+
+        assert!(!source_control.refreshing);
+
+        let _ = source_control.refresh();
+
+        assert!(source_control.refreshing);
+        assert!(!source_control.outdated);
+    }
+
+    #[test]
+    fn failed_loads_refresh_when_outdated() {
+        let (mut source_control, _) = SourceControl::new(PathBuf::from("."));
+        let _ = source_control.refresh();
+
+        assert!(source_control.outdated);
+
+        let action = source_control.update(Message::Loaded(Err(failed())));
+
+        assert!(matches!(action, Action::Error(..)));
+        assert!(source_control.refreshing);
+        assert!(!source_control.outdated);
+    }
+}
@@ -1,48 +1,66 @@
 use iced::{self, *};
 
-pub fn title_bar(theme: &Theme) -> widget::container::Style {
+use crate::settings::theme;
+use crate::{diff, git};
+
+pub fn title_bar(theme: &Theme, panes: &theme::Panes) -> widget::container::Style {
     let palette = theme.extended_palette();
 
     widget::container::Style {
         background: None,
-        text_color: Some(palette.primary.base.color),
+        text_color: Some(panes.title.unwrap_or(palette.primary.base.color)),
         ..Default::default()
     }
 }
 
-pub fn full_pane(theme: &Theme) -> widget::container::Style {
+pub fn full_pane(theme: &Theme, panes: &theme::Panes) -> widget::container::Style {
     let palette = theme.extended_palette();
 
     widget::container::Style {
-        background: Some(palette.background.weak.color.into()),
+        background: Some(
+            panes
+                .background
+                .unwrap_or(palette.background.weak.color)
+                .into(),
+        ),
         border: Border {
             width: 2.0,
-            color: palette.background.strong.color,
+            color: panes.border.unwrap_or(palette.background.strong.color),
             radius: border::Radius::new(10.0),
         },
         ..Default::default()
     }
 }
 
-pub fn full_pane_focus(theme: &Theme) -> widget::container::Style {
+pub fn full_pane_focus(theme: &Theme, panes: &theme::Panes) -> widget::container::Style {
     let palette = theme.extended_palette();
 
     widget::container::Style {
-        background: Some(palette.background.weak.color.into()),
+        background: Some(
+            panes
+                .background
+                .unwrap_or(palette.background.weak.color)
+                .into(),
+        ),
         border: Border {
             width: 2.0,
-            color: palette.primary.strong.color,
+            color: panes.focus.unwrap_or(palette.primary.strong.color),
             radius: border::Radius::new(10.0),
         },
         ..Default::default()
     }
 }
 
-pub fn pane_content(theme: &Theme) -> widget::container::Style {
+pub fn pane_content(theme: &Theme, panes: &theme::Panes) -> widget::container::Style {
     let palette = theme.extended_palette();
 
     widget::container::Style {
-        background: Some(palette.background.weak.color.into()),
+        background: Some(
+            panes
+                .background
+                .unwrap_or(palette.background.weak.color)
+                .into(),
+        ),
         ..Default::default()
     }
 }
@@ -56,17 +74,367 @@ pub fn icon<'a, Message>(codepoint: char) -> Element<'a, Message> {
         .into()
 }
 
-pub fn button(theme: &Theme, status: widget::button::Status) -> widget::button::Style {
+pub fn button(
+    theme: &Theme,
+    status: widget::button::Status,
+    panes: &theme::Panes,
+) -> widget::button::Style {
     let palette = theme.extended_palette();
     let mut style = widget::button::primary(theme, status);
 
+    let active = panes.button.unwrap_or(palette.primary.weak.color);
+    let hovered = panes.button_hovered.unwrap_or(palette.primary.strong.color);
+
     style.background = None;
     style.text_color = match status {
-        widget::button::Status::Active => palette.primary.weak.color,
-        widget::button::Status::Hovered => palette.primary.strong.color,
-        widget::button::Status::Pressed => palette.primary.strong.color,
-        widget::button::Status::Disabled => palette.primary.weak.color,
+        widget::button::Status::Active => active,
+        widget::button::Status::Hovered => hovered,
+        widget::button::Status::Pressed => hovered,
+        widget::button::Status::Disabled => active,
     };
 
     style
 }
+
+pub fn entry(
+    theme: &Theme,
+    status: widget::button::Status,
+    selected: bool,
+) -> widget::button::Style {
+    let palette = theme.extended_palette();
+
+    let background = match status {
+        _ if selected => Some(palette.background.strong.color.into()),
+        widget::button::Status::Hovered | widget::button::Status::Pressed => {
+            Some(palette.background.base.color.into())
+        }
+        widget::button::Status::Active | widget::button::Status::Disabled => None,
+    };
+
+    widget::button::Style {
+        background,
+        text_color: palette.background.base.text,
+        border: Border {
+            radius: border::Radius::new(4.0),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+pub fn git_status(theme: &Theme, status: Option<git::Status>) -> widget::text::Style {
+    let palette = theme.extended_palette();
+
+    widget::text::Style {
+        color: status.map(|status| match status {
+            git::Status::Ignored => palette.background.strong.color,
+            git::Status::Untracked => palette.success.base.color,
+            git::Status::Added => palette.success.strong.color,
+            git::Status::Modified => palette.primary.base.color,
+            git::Status::Conflicted => palette.danger.base.color,
+        }),
+    }
+}
+
+pub fn hunk(
+    theme: &Theme,
+    status: widget::button::Status,
+    kind: diff::Kind,
+) -> widget::button::Style {
+    let palette = theme.extended_palette();
+
+    let (base, strong) = match kind {
+        diff::Kind::Added => (palette.success.base, palette.success.strong),
+        diff::Kind::Modified => (palette.primary.base, palette.primary.strong),
+        diff::Kind::Deleted => (palette.danger.base, palette.danger.strong),
+    };
+
+    let color = match status {
+        widget::button::Status::Hovered | widget::button::Status::Pressed => strong.color,
+        widget::button::Status::Active | widget::button::Status::Disabled => base.color,
+    };
+
+    widget::button::Style {
+        background: Some(color.into()),
+        border: Border {
+            radius: border::Radius::new(1.0),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+pub fn popup(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(palette.background.base.color.into()),
+        text_color: Some(palette.background.base.text),
+        border: Border {
+            width: 1.0,
+            color: palette.background.strong.color,
+            radius: border::Radius::new(4.0),
+        },
+        ..Default::default()
+    }
+}
+
+pub fn merge_side(theme: &Theme, selected: bool) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(palette.background.base.color.into()),
+        border: Border {
+            width: 1.0,
+            color: if selected {
+                palette.success.base.color
+            } else {
+                palette.background.strong.color
+            },
+            radius: border::Radius::new(4.0),
+        },
+        ..Default::default()
+    }
+}
+
+pub fn annotation(theme: &Theme, status: widget::button::Status) -> widget::button::Style {
+    let palette = theme.extended_palette();
+
+    widget::button::Style {
+        background: match status {
+            widget::button::Status::Hovered | widget::button::Status::Pressed => {
+                Some(palette.background.strong.color.into())
+            }
+            widget::button::Status::Active | widget::button::Status::Disabled => None,
+        },
+        text_color: palette.background.strong.text,
+        border: Border {
+            width: 1.0,
+            color: palette.background.base.color,
+            radius: border::Radius::new(0.0),
+        },
+        ..Default::default()
+    }
+}
+
+pub fn fold_marker(
+    theme: &Theme,
+    status: widget::button::Status,
+    folded: bool,
+) -> widget::button::Style {
+    let palette = theme.extended_palette();
+
+    widget::button::Style {
+        text_color: match status {
+            widget::button::Status::Hovered | widget::button::Status::Pressed => {
+                palette.background.base.text
+            }
+            widget::button::Status::Active | widget::button::Status::Disabled if folded => {
+                palette.primary.base.color
+            }
+            widget::button::Status::Active | widget::button::Status::Disabled => {
+                palette.background.strong.color
+            }
+        },
+        ..Default::default()
+    }
+}
+
+pub fn line_number(theme: &Theme, current: bool) -> widget::text::Style {
+    let palette = theme.extended_palette();
+
+    widget::text::Style {
+        color: Some(if current {
+            palette.background.base.text
+        } else {
+            palette.background.strong.color
+        }),
+    }
+}
+
+pub fn current_line(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(
+            Color {
+                a: 0.08,
+                ..palette.background.base.text
+            }
+            .into(),
+        ),
+        ..Default::default()
+    }
+}
+
+pub fn matching_bracket(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(
+            Color {
+                a: 0.15,
+                ..palette.primary.base.color
+            }
+            .into(),
+        ),
+        border: Border {
+            color: palette.primary.base.color,
+            width: 1.0,
+            radius: border::Radius::new(2.0),
+        },
+        ..Default::default()
+    }
+}
+
+pub fn ruler(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(palette.background.strong.color.into()),
+        ..Default::default()
+    }
+}
+
+pub fn indent_guide(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(
+            Color {
+                a: 0.4,
+                ..palette.background.strong.color
+            }
+            .into(),
+        ),
+        ..Default::default()
+    }
+}
+
+pub fn whitespace(theme: &Theme) -> widget::text::Style {
+    let palette = theme.extended_palette();
+
+    widget::text::Style {
+        color: Some(palette.background.strong.color),
+    }
+}
+
+pub fn minimap(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(
+            Color {
+                a: 0.35,
+                ..palette.background.base.text
+            }
+            .into(),
+        ),
+        ..Default::default()
+    }
+}
+
+pub fn minimap_viewport(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(
+            Color {
+                a: 0.1,
+                ..palette.background.base.text
+            }
+            .into(),
+        ),
+        ..Default::default()
+    }
+}
+
+pub fn conflict_mark(theme: &Theme, status: widget::button::Status) -> widget::button::Style {
+    let palette = theme.extended_palette();
+
+    mark(
+        status,
+        palette.danger.base.color,
+        palette.danger.strong.color,
+    )
+}
+
+pub fn match_mark(theme: &Theme, status: widget::button::Status) -> widget::button::Style {
+    let palette = theme.extended_palette();
+
+    mark(
+        status,
+        palette.secondary.strong.color,
+        palette.background.base.text,
+    )
+}
+
+pub fn cursor_mark(theme: &Theme, status: widget::button::Status) -> widget::button::Style {
+    let palette = theme.extended_palette();
+
+    mark(
+        status,
+        palette.primary.strong.color,
+        palette.background.base.text,
+    )
+}
+
+fn mark(status: widget::button::Status, base: Color, strong: Color) -> widget::button::Style {
+    let color = match status {
+        widget::button::Status::Hovered | widget::button::Status::Pressed => strong,
+        widget::button::Status::Active | widget::button::Status::Disabled => base,
+    };
+
+    widget::button::Style {
+        background: Some(color.into()),
+        ..Default::default()
+    }
+}
+
+pub fn code_block(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: Some(palette.background.weak.color.into()),
+        border: Border {
+            radius: 4.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+pub fn quote(theme: &Theme) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        text_color: Some(palette.background.strong.text),
+        background: Some(
+            Color {
+                a: 0.05,
+                ..palette.background.base.text
+            }
+            .into(),
+        ),
+        border: Border {
+            color: palette.background.strong.color,
+            width: 1.0,
+            radius: 2.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+pub fn table_cell(theme: &Theme, header: bool) -> widget::container::Style {
+    let palette = theme.extended_palette();
+
+    widget::container::Style {
+        background: header.then(|| palette.background.weak.color.into()),
+        border: Border {
+            color: palette.background.strong.color,
+            width: 1.0,
+            radius: 0.0.into(),
+        },
+        ..Default::default()
+    }
+}
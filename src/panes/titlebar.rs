@@ -1,20 +1,32 @@
 use iced::widget::{horizontal_space, pane_grid::*};
 use iced::{self, *};
 
+use crate::settings::theme;
+
 pub fn title_bar<'a>(
     id: Pane,
     pane: super::Pane,
     only_one: bool,
     maximized: bool,
+    // A status shown after the title, e.g. the Vim mode of the editor.
+    status: Option<String>,
+    panes: &'a theme::Panes,
 ) -> TitleBar<'a, super::Message> {
     TitleBar::new(
-        iced::widget::container(iced::widget::text(format!("#{}", pane.title())).size(12)).padding(
-            Padding {
-                top: 5.0,
-                left: 10.0,
-                ..Default::default()
-            },
-        ),
+        iced::widget::container(
+            iced::widget::row![iced::widget::text(format!("#{}", pane.title())).size(12)]
+                .push_maybe(status.map(|status| {
+                    iced::widget::text(status)
+                        .size(12)
+                        .style(iced::widget::text::primary)
+                }))
+                .spacing(10),
+        )
+        .padding(Padding {
+            top: 5.0,
+            left: 10.0,
+            ..Default::default()
+        }),
     )
     .controls(Controls::new({
         let close = iced::widget::button(
@@ -23,7 +35,7 @@ pub fn title_bar<'a>(
         )
         .width(24)
         .height(24)
-        .style(super::style::button)
+        .style(|theme, status| super::style::button(theme, status, panes))
         .on_press(super::Message::Close(id))
         .into();
 
@@ -33,7 +45,7 @@ pub fn title_bar<'a>(
         )
         .width(24)
         .height(24)
-        .style(super::style::button)
+        .style(|theme, status| super::style::button(theme, status, panes))
         .on_press(super::Message::Maximize(id))
         .into();
 
@@ -43,7 +55,7 @@ pub fn title_bar<'a>(
         )
         .width(24)
         .height(24)
-        .style(super::style::button)
+        .style(|theme, status| super::style::button(theme, status, panes))
         .on_press(super::Message::Restore)
         .into();
 
@@ -55,5 +67,5 @@ pub fn title_bar<'a>(
             iced::widget::row([maximize, close, horizontal_space().width(5).into()])
         }
     }))
-    .style(super::style::title_bar)
+    .style(|theme| super::style::title_bar(theme, panes))
 }
@@ -0,0 +1,126 @@
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::encoding;
+
+/// The unsaved contents of a file, kept in a journal in the cache directory
+/// until it is saved, so that it can be restored if the editor crashes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub contents: String,
+    /// How the file is stored, kept when it is restored.
+    #[serde(default)]
+    pub format: encoding::Format,
+}
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub message: String,
+}
+
+pub fn directory() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("rine").join("recovery"))
+}
+
+/// Reads the entries of the journal for the files of a project, skipping
+/// the ones that cannot be read.
+pub fn read(root: &Path) -> Vec<Entry> {
+    let Some(entries) = directory().and_then(|directory| std::fs::read_dir(directory).ok()) else {
+        return Vec::new();
+    };
+
+    let mut entries: Vec<Entry> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|journal| {
+            journal
+                .extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .filter_map(|journal| {
+            let json = std::fs::read_to_string(journal).ok()?;
+
+            serde_json::from_str::<Entry>(&json).ok()
+        })
+        .filter(|entry| entry.path.starts_with(root))
+        .collect();
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+/// Writes the contents of a file to the journal, replacing the previous ones.
+pub async fn write(entry: Entry) -> Result<(), Error> {
+    let file = file(&entry.path)?;
+    let json = serde_json::to_string(&entry).map_err(|error| Error {
+        message: error.to_string(),
+    })?;
+
+    if let Some(directory) = file.parent() {
+        tokio::fs::create_dir_all(directory)
+            .await
+            .map_err(|error| self::error(&entry.path, error))?;
+    }
+
+    // Written aside first, so that a crash while writing leaves the
+    // previous contents whole.
+    let partial = file.with_extension("partial");
+
+    tokio::fs::write(&partial, json)
+        .await
+        .map_err(|error| self::error(&entry.path, error))?;
+    tokio::fs::rename(&partial, &file)
+        .await
+        .map_err(|error| self::error(&entry.path, error))
+}
+
+/// Removes a file from the journal, once saved or discarded.
+pub async fn remove(path: PathBuf) -> Result<(), Error> {
+    match tokio::fs::remove_file(file(&path)?).await {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(self::error(&path, error)),
+        _ => Ok(()),
+    }
+}
+
+/// A digest of some contents, to tell whether they changed.
+pub fn hash(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The journal file of a file, named after a digest of its path that stays
+/// the same across versions of the editor and of Rust.
+fn file(path: &Path) -> Result<PathBuf, Error> {
+    let digest = fnv1a(path.to_string_lossy().as_bytes());
+
+    directory()
+        .map(|directory| directory.join(format!("{digest:016x}.json")))
+        .ok_or_else(|| Error {
+            message: String::from("no cache directory"),
+        })
+}
+
+/// The 64-bit FNV-1a digest of some bytes.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |digest, &byte| {
+        (digest ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn error(path: &Path, error: io::Error) -> Error {
+    Error {
+        message: format!("{}: {error}", path.display()),
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "recovery: {}", self.message)
+    }
+}
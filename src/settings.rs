@@ -0,0 +1,515 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use iced::futures::StreamExt;
+use iced::{Font, Subscription, Theme, font};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{panes, watcher};
+
+mod project;
+mod snippets;
+pub mod theme;
+
+pub use project::ProjectSettings;
+pub use snippets::{Snippet, Snippets};
+pub use theme::{Appearance, Themes};
+
+/// User-level settings, read from `settings.toml` in the XDG config directory.
+///
+/// The effective settings of a session are resolved with the following
+/// precedence, highest first: the project's `.rine.toml`, the user's
+/// `settings.toml`, then the built-in defaults below.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// The name of a built-in theme, as listed by [`Theme::ALL`], or of a
+    /// custom theme from the `themes` config directory.
+    pub theme: String,
+    /// A font family name, or `None` for the default monospace font.
+    pub font: Option<String>,
+    pub text_size: f32,
+    /// Space between panes, in pixels.
+    pub spacing: f32,
+    /// Space between the pane borders and their contents, in pixels.
+    pub padding: f32,
+    /// Glob-like names hidden from the project tree.
+    pub excludes: Vec<String>,
+    pub tab_width: usize,
+    pub line_numbers: LineNumbers,
+    /// Columns at which vertical rulers are drawn in the editor, e.g. `[100]`.
+    pub rulers: Vec<usize>,
+    pub show_whitespace: bool,
+    pub indent_guides: bool,
+    /// Whether a zoomed-out view of the file is shown beside the editor.
+    pub minimap: bool,
+    /// Size above which files are opened read-only, in megabytes.
+    pub large_file_size: u64,
+    /// Whether the editor uses Vim keybindings.
+    pub vim: bool,
+    /// Whether files are formatted before they are saved.
+    pub format_on_save: bool,
+    /// When changed files are saved without being asked to, unformatted.
+    pub autosave: Autosave,
+    /// Seconds without edits after which files are saved, with
+    /// `autosave = "delay"`.
+    pub autosave_delay: u64,
+    /// Whether unsaved changes are kept aside, to be restored after a crash.
+    pub recovery: bool,
+    /// Formatter commands, keyed by file extension (e.g. `rs`). The text of
+    /// a file is piped through them, and `{file}` in their arguments stands
+    /// for its path.
+    pub formatters: BTreeMap<String, Command>,
+    /// Language server commands, keyed by file extension (e.g. `rs`).
+    pub language_servers: BTreeMap<String, Command>,
+    pub tasks: Vec<Task>,
+    pub layout: Layout,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Command {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// How the editor numbers lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineNumbers {
+    #[default]
+    Absolute,
+    /// Distances from the current line, which keeps its number.
+    Relative,
+    Off,
+}
+
+impl LineNumbers {
+    pub const ALL: [LineNumbers; 3] = [
+        LineNumbers::Absolute,
+        LineNumbers::Relative,
+        LineNumbers::Off,
+    ];
+}
+
+impl fmt::Display for LineNumbers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LineNumbers::Absolute => "Absolute",
+            LineNumbers::Relative => "Relative",
+            LineNumbers::Off => "Off",
+        })
+    }
+}
+
+/// When the editor saves changed files by itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Autosave {
+    #[default]
+    Off,
+    /// When the window loses focus, or another file is selected.
+    FocusChange,
+    /// Once edits stop for a while.
+    Delay,
+}
+
+impl Autosave {
+    pub const ALL: [Autosave; 3] = [Autosave::Off, Autosave::FocusChange, Autosave::Delay];
+}
+
+impl fmt::Display for Autosave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Autosave::Off => "Off",
+            Autosave::FocusChange => "On focus change",
+            Autosave::Delay => "After a delay",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Task {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Working directory, relative to the project root.
+    pub cwd: Option<PathBuf>,
+}
+
+/// The initial arrangement of the panes, e.g.
+///
+/// ```toml
+/// [layout]
+/// split = "horizontal"
+/// ratio = 0.7
+/// a = { split = "vertical", ratio = 0.2, a = "project", b = "editor" }
+/// b = "hub"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Layout {
+    Pane(panes::Pane),
+    Split {
+        split: Axis,
+        ratio: f32,
+        a: Box<Layout>,
+        b: Box<Layout>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        kind: io::ErrorKind,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Serialize {
+        message: String,
+    },
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rine").join("settings.toml"))
+    }
+
+    /// Loads the user settings, falling back to the defaults when the file
+    /// does not exist.
+    pub fn load() -> Result<Self, Error> {
+        match Self::path() {
+            Some(path) => read(&path).map(Option::unwrap_or_default),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Writes the user settings, creating the config directory if needed.
+    pub fn save(&self) -> Result<(), Error> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+
+        let contents = toml::to_string_pretty(self).map_err(|error| Error::Serialize {
+            message: error.to_string(),
+        })?;
+
+        let io_error = |error: io::Error| Error::Io {
+            path: path.clone(),
+            kind: error.kind(),
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+
+        std::fs::write(&path, contents).map_err(io_error)
+    }
+
+    pub fn font(&self) -> Font {
+        match &self.font {
+            Some(name) => Font {
+                family: font::Family::Name(intern(name)),
+                ..Font::MONOSPACE
+            },
+            None => Font::MONOSPACE,
+        }
+    }
+
+    /// The height of a line of text, in pixels, so that lines can be
+    /// aligned across widgets.
+    pub fn line_height(&self) -> f32 {
+        (self.text_size * 1.3).round()
+    }
+
+    /// Applies the project settings on top of these ones.
+    ///
+    /// Scalars, rulers and the layout are replaced, `excludes` are added to
+    /// the user ones, formatters and language servers are overridden per
+    /// extension, and tasks are overridden by name.
+    pub fn merge(mut self, project: ProjectSettings) -> Self {
+        for exclude in project.excludes {
+            if !self.excludes.contains(&exclude) {
+                self.excludes.push(exclude);
+            }
+        }
+
+        self.tab_width = project.tab_width.unwrap_or(self.tab_width);
+        self.rulers = project.rulers.unwrap_or(self.rulers);
+        self.formatters.extend(project.formatters);
+        self.language_servers.extend(project.language_servers);
+
+        self.tasks
+            .retain(|task| !project.tasks.iter().any(|other| other.name == task.name));
+        self.tasks.extend(project.tasks);
+
+        self.layout = project.layout.unwrap_or(self.layout);
+
+        self
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::Ferra.to_string(),
+            font: None,
+            text_size: 14.0,
+            spacing: 5.0,
+            padding: 5.0,
+            excludes: vec![String::from(".git"), String::from("target")],
+            tab_width: 4,
+            line_numbers: LineNumbers::Absolute,
+            rulers: Vec::new(),
+            show_whitespace: false,
+            indent_guides: true,
+            minimap: true,
+            large_file_size: 16,
+            vim: false,
+            format_on_save: true,
+            autosave: Autosave::default(),
+            autosave_delay: 1,
+            recovery: true,
+            formatters: BTreeMap::new(),
+            language_servers: BTreeMap::new(),
+            tasks: Vec::new(),
+            layout: Layout::default(),
+        }
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::Split {
+            split: Axis::Horizontal,
+            ratio: 0.5,
+            a: Box::new(Layout::Split {
+                split: Axis::Vertical,
+                ratio: 0.5,
+                a: Box::new(Layout::Pane(panes::Pane::Project)),
+                b: Box::new(Layout::Pane(panes::Pane::Editor)),
+            }),
+            b: Box::new(Layout::Pane(panes::Pane::Hub)),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, kind } => write!(f, "{}: {kind}", path.display()),
+            Error::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{line}:{column}: {message}", path.display()),
+            Error::Serialize { message } => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Notifies whenever the user or project settings, a custom theme or the
+/// snippets change on disk.
+pub fn watch(root: PathBuf) -> Subscription<()> {
+    let files: Vec<_> = [Settings::path(), Some(ProjectSettings::path(&root))]
+        .into_iter()
+        .flatten()
+        .collect();
+    let directories: Vec<_> = [Themes::directory(), Snippets::directory()]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    // Editors often save by renaming over the file, so the parent directories
+    // are watched rather than the files themselves.
+    let paths = files
+        .iter()
+        .filter_map(|file| file.parent())
+        .chain(directories.iter().map(PathBuf::as_path))
+        .map(|path| (path.to_owned(), watcher::RecursiveMode::NonRecursive))
+        .collect();
+
+    Subscription::run_with_id(
+        root,
+        watcher::watch(paths).filter_map(move |paths| {
+            let changed = paths.iter().any(|path| {
+                files.contains(path)
+                    || directories
+                        .iter()
+                        .any(|directory| path.starts_with(directory))
+            });
+
+            async move { changed.then_some(()) }
+        }),
+    )
+}
+
+/// Returns a `'static` copy of a font family name, as required by [`Font`].
+fn intern(name: &str) -> &'static str {
+    static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+    let mut names = NAMES.lock().unwrap_or_else(|error| error.into_inner());
+
+    match names.get(name) {
+        Some(name) => name,
+        None => {
+            let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+            names.insert(name);
+            name
+        }
+    }
+}
+
+/// Reads and parses a TOML file, returning `None` if it does not exist.
+fn read<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, Error> {
+    match read_source(path)? {
+        Some(source) => from_toml(path, &source).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Reads a file to a string, returning `None` if it does not exist.
+fn read_source(path: &Path) -> Result<Option<String>, Error> {
+    match std::fs::read_to_string(path) {
+        Ok(source) => Ok(Some(source)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(Error::Io {
+            path: path.to_owned(),
+            kind: error.kind(),
+        }),
+    }
+}
+
+fn from_toml<T: DeserializeOwned>(path: &Path, source: &str) -> Result<T, Error> {
+    toml::from_str(source).map_err(|error| {
+        let offset = error.span().map_or(0, |span| span.start);
+        let (line, column) = location(source, offset);
+
+        Error::Parse {
+            path: path.to_owned(),
+            line,
+            column,
+            message: error.message().to_owned(),
+        }
+    })
+}
+
+/// Converts a byte offset into a 1-based line and column.
+fn location(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before, |start| &before[start + 1..])
+        .chars()
+        .count()
+        + 1;
+
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(command: &str) -> Command {
+        Command {
+            command: command.to_owned(),
+            args: Vec::new(),
+        }
+    }
+
+    fn task(name: &str, command: &str) -> Task {
+        Task {
+            name: name.to_owned(),
+            command: command.to_owned(),
+            args: Vec::new(),
+            cwd: None,
+        }
+    }
+
+    #[test]
+    fn project_settings_override_the_user_ones() {
+        let user = Settings {
+            excludes: vec![String::from("target")],
+            rulers: vec![80],
+            formatters: BTreeMap::from([
+                (String::from("rs"), command("rustfmt")),
+                (String::from("js"), command("prettier")),
+            ]),
+            tasks: vec![task("build", "make"), task("test", "make")],
+            ..Settings::default()
+        };
+
+        let project: ProjectSettings = from_toml(
+            Path::new(ProjectSettings::FILE),
+            r#"
+            excludes = ["target", "dist"]
+            tab_width = 2
+            formatters.rs = { command = "rustfmt", args = ["--edition", "2024"] }
+
+            [[tasks]]
+            name = "test"
+            command = "cargo"
+            "#,
+        )
+        .unwrap();
+
+        let merged = user.clone().merge(project);
+
+        assert_eq!(merged.excludes, ["target", "dist"]);
+        assert_eq!(merged.tab_width, 2);
+        assert_eq!(merged.rulers, [80]);
+        assert_eq!(merged.formatters["rs"].args, ["--edition", "2024"]);
+        assert_eq!(merged.formatters["js"], user.formatters["js"]);
+        assert_eq!(merged.tasks, [task("build", "make"), task("test", "cargo")]);
+        assert_eq!(merged.layout, user.layout);
+    }
+
+    #[test]
+    fn empty_project_settings_change_nothing() {
+        let user = Settings::default();
+
+        assert_eq!(user.clone().merge(ProjectSettings::default()), user);
+    }
+
+    #[test]
+    fn parse_errors_tell_where() {
+        let source = "tab_width = 4\nvim = \"yes\"\n";
+
+        match from_toml::<Settings>(Path::new("settings.toml"), source) {
+            Err(Error::Parse { line, column, .. }) => assert_eq!((line, column), (2, 7)),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn locations_count_characters() {
+        let source = "é = 1\nab";
+
+        assert_eq!(location(source, 0), (1, 1));
+        assert_eq!(location(source, 2), (1, 2));
+        assert_eq!(location(source, 8), (2, 2));
+        assert_eq!(location(source, 100), (2, 3));
+    }
+}
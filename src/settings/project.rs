@@ -0,0 +1,35 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{Command, Error, Layout, Task};
+
+/// Per-project settings, read from `.rine.toml` at the project root.
+///
+/// Every field is optional and only overrides the user settings when present,
+/// see [`Settings::merge`](super::Settings::merge).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectSettings {
+    pub excludes: Vec<String>,
+    pub tab_width: Option<usize>,
+    pub rulers: Option<Vec<usize>>,
+    pub formatters: BTreeMap<String, Command>,
+    pub language_servers: BTreeMap<String, Command>,
+    pub tasks: Vec<Task>,
+    pub layout: Option<Layout>,
+}
+
+impl ProjectSettings {
+    pub const FILE: &'static str = ".rine.toml";
+
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(Self::FILE)
+    }
+
+    /// Loads the settings of the project at `root`, if any.
+    pub fn load(root: &Path) -> Result<Self, Error> {
+        super::read(&Self::path(root)).map(Option::unwrap_or_default)
+    }
+}
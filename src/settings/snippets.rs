@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::Error;
+
+/// A snippet, read from a file in the `snippets` config directory.
+///
+/// The files use the syntax of VS Code: `rust.json` holds the snippets of
+/// Rust, while `*.code-snippets` files hold snippets for any language, or
+/// for the languages listed in their `scope`, e.g.
+///
+/// ```json
+/// {
+///     "Print": {
+///         "prefix": ["pr", "println"],
+///         "body": "println!(\"${1:{}}\", $2);$0",
+///         "description": "Prints a line"
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    pub name: String,
+    pub prefixes: Vec<String>,
+    pub body: String,
+    pub description: String,
+    /// The extensions of the files the snippet applies to, or none if it
+    /// applies to every file.
+    pub extensions: Vec<String>,
+}
+
+/// The snippets found in the config directory.
+#[derive(Debug, Clone, Default)]
+pub struct Snippets {
+    pub snippets: Vec<Snippet>,
+}
+
+#[derive(Deserialize)]
+struct Definition {
+    #[serde(default)]
+    prefix: Strings,
+    body: Strings,
+    #[serde(default)]
+    description: Option<String>,
+    /// The comma-separated languages of a global snippet.
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+/// A string, or strings joined by lines.
+#[derive(Default, Deserialize)]
+#[serde(untagged)]
+enum Strings {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Snippets {
+    pub fn directory() -> Option<PathBuf> {
+        super::Settings::path()
+            .as_deref()
+            .and_then(Path::parent)
+            .map(|dir| dir.join("snippets"))
+    }
+
+    /// Loads every snippet, skipping and reporting invalid files.
+    pub fn load() -> (Self, Vec<Error>) {
+        let mut snippets = Self::default();
+        let mut errors = Vec::new();
+
+        let Some(entries) = Self::directory().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+            return (snippets, errors);
+        };
+
+        let mut paths: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json" || extension == "code-snippets")
+            })
+            .collect();
+
+        paths.sort();
+
+        for path in paths {
+            match read(&path) {
+                Ok(read) => snippets.snippets.extend(read),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        (snippets, errors)
+    }
+
+    /// The snippets applying to files with an extension.
+    pub fn of<'a>(&'a self, extension: &'a str) -> impl Iterator<Item = &'a Snippet> + 'a {
+        self.snippets
+            .iter()
+            .filter(move |snippet| snippet.applies_to(extension))
+    }
+}
+
+impl Snippet {
+    pub fn applies_to(&self, extension: &str) -> bool {
+        self.extensions.is_empty()
+            || self
+                .extensions
+                .iter()
+                .any(|candidate| candidate.eq_ignore_ascii_case(extension))
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<Snippet>, Error> {
+    let Some(source) = super::read_source(path)? else {
+        return Ok(Vec::new());
+    };
+
+    let definitions: BTreeMap<String, Definition> = serde_json::from_str(&strip_comments(&source))
+        .map_err(|error| {
+            // The location is reported separately.
+            let message = error.to_string();
+            let message = message.split(" at line ").next().unwrap_or_default();
+
+            Error::Parse {
+                path: path.to_owned(),
+                line: error.line(),
+                column: error.column(),
+                message: message.to_owned(),
+            }
+        })?;
+
+    let is_global = path
+        .extension()
+        .is_some_and(|extension| extension == "code-snippets");
+
+    let language = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    Ok(definitions
+        .into_iter()
+        .map(|(name, definition)| {
+            let extensions = match (is_global, &definition.scope) {
+                (false, _) => extensions(&language),
+                (true, Some(scope)) => scope
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|language| !language.is_empty())
+                    .flat_map(extensions)
+                    .collect(),
+                (true, None) => Vec::new(),
+            };
+
+            Snippet {
+                name,
+                prefixes: match definition.prefix {
+                    Strings::None => Vec::new(),
+                    Strings::One(prefix) => vec![prefix],
+                    Strings::Many(prefixes) => prefixes,
+                },
+                body: match definition.body {
+                    Strings::None => String::new(),
+                    Strings::One(body) => body,
+                    Strings::Many(lines) => lines.join("\n"),
+                },
+                description: definition.description.unwrap_or_default(),
+                extensions,
+            }
+        })
+        .collect())
+}
+
+/// The extensions of the files of a language, named like in VS Code, or the
+/// name itself taken as an extension.
+fn extensions(language: &str) -> Vec<String> {
+    let extensions: &[&str] = match language {
+        "rust" => &["rs"],
+        "python" => &["py", "pyi"],
+        "javascript" => &["js", "mjs", "cjs"],
+        "javascriptreact" => &["jsx"],
+        "typescript" => &["ts", "mts", "cts"],
+        "typescriptreact" => &["tsx"],
+        "markdown" => &["md", "markdown"],
+        "shellscript" => &["sh", "bash", "zsh"],
+        "c" => &["c", "h"],
+        "cpp" => &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        "csharp" => &["cs"],
+        "ruby" => &["rb"],
+        "yaml" => &["yaml", "yml"],
+        "html" => &["html", "htm"],
+        "plaintext" => &["txt"],
+        "haskell" => &["hs"],
+        "kotlin" => &["kt", "kts"],
+        "elixir" => &["ex", "exs"],
+        "erlang" => &["erl", "hrl"],
+        "ocaml" => &["ml", "mli"],
+        "perl" => &["pl", "pm"],
+        "powershell" => &["ps1"],
+        "dockerfile" => &["dockerfile"],
+        language => return vec![language.to_owned()],
+    };
+
+    extensions
+        .iter()
+        .map(|extension| (*extension).to_owned())
+        .collect()
+}
+
+/// Blanks the comments VS Code allows in snippet files, outside of strings,
+/// keeping the locations of errors.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut characters = source.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(character) = characters.next() {
+        if in_string {
+            stripped.push(character);
+
+            match character {
+                '\\' => stripped.extend(characters.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        match (character, characters.peek()) {
+            ('"', _) => {
+                in_string = true;
+                stripped.push(character);
+            }
+            ('/', Some('/')) => {
+                while let Some(&next) = characters.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+
+                    stripped.push(' ');
+                    characters.next();
+                }
+
+                stripped.push(' ');
+            }
+            ('/', Some('*')) => {
+                characters.next();
+                stripped.push_str("  ");
+
+                let mut previous = ' ';
+
+                for next in characters.by_ref() {
+                    stripped.push(if next == '\n' { '\n' } else { ' ' });
+
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+
+                    previous = next;
+                }
+            }
+            _ => stripped.push(character),
+        }
+    }
+
+    stripped
+}
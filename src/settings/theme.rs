@@ -0,0 +1,227 @@
+use std::path::{Path, PathBuf};
+
+use iced::Color;
+use iced::theme::Palette;
+use serde::{Deserialize, Deserializer};
+
+use super::Error;
+
+/// A custom theme, read from a TOML or JSON file in the `themes` config
+/// directory, e.g.
+///
+/// ```toml
+/// name = "Midnight"
+/// base = "Nord"
+///
+/// [palette]
+/// primary = "#88c0d0"
+///
+/// [panes]
+/// focus = "#ebcb8b"
+///
+/// [syntax]
+/// keyword = "#81a1c1"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Custom {
+    pub name: String,
+    /// The built-in theme providing anything left unspecified.
+    #[serde(default = "default_base")]
+    pub base: String,
+    #[serde(default)]
+    pub palette: Colors,
+    #[serde(default)]
+    pub panes: Panes,
+    #[serde(default)]
+    pub syntax: Syntax,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Colors {
+    #[serde(default, deserialize_with = "color")]
+    pub background: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub text: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub primary: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub success: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub danger: Option<Color>,
+}
+
+/// Overrides for the pane chrome, derived from the palette when unset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Panes {
+    #[serde(default, deserialize_with = "color")]
+    pub background: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub border: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub focus: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub title: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub button: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub button_hovered: Option<Color>,
+}
+
+/// Overrides for the syntax highlighting colors of the editor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Syntax {
+    #[serde(default, deserialize_with = "color")]
+    pub comment: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub string: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub number: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub keyword: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub function: Option<Color>,
+    #[serde(default, deserialize_with = "color")]
+    pub r#type: Option<Color>,
+}
+
+/// The theme in use: an iced theme and the overrides applied on top of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Appearance {
+    pub theme: iced::Theme,
+    pub panes: Panes,
+    pub syntax: Syntax,
+}
+
+/// The built-in themes and the custom ones found in the config directory.
+#[derive(Debug, Clone, Default)]
+pub struct Themes {
+    pub custom: Vec<Custom>,
+}
+
+impl Themes {
+    pub fn directory() -> Option<PathBuf> {
+        super::Settings::path()
+            .as_deref()
+            .and_then(Path::parent)
+            .map(|dir| dir.join("themes"))
+    }
+
+    /// Loads every custom theme, skipping and reporting invalid files.
+    pub fn load() -> (Self, Vec<Error>) {
+        let mut themes = Self::default();
+        let mut errors = Vec::new();
+
+        let Some(entries) = Self::directory().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+            return (themes, errors);
+        };
+
+        let mut paths: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "toml" || extension == "json")
+            })
+            .collect();
+
+        paths.sort();
+
+        for path in paths {
+            match read(&path) {
+                Ok(Some(custom)) => themes.custom.push(custom),
+                Ok(None) => {}
+                Err(error) => errors.push(error),
+            }
+        }
+
+        (themes, errors)
+    }
+
+    /// The names of all the available themes, built-in ones first.
+    pub fn names(&self) -> Vec<String> {
+        iced::Theme::ALL
+            .iter()
+            .map(ToString::to_string)
+            .chain(self.custom.iter().map(|custom| custom.name.clone()))
+            .collect()
+    }
+
+    /// Resolves a theme by name, falling back to the default theme.
+    pub fn appearance(&self, name: &str) -> Appearance {
+        if let Some(custom) = self.custom.iter().rev().find(|custom| custom.name == name) {
+            let base = builtin(&custom.base).unwrap_or(iced::Theme::Ferra);
+            let palette = base.palette();
+            let colors = &custom.palette;
+
+            let palette = Palette {
+                background: colors.background.unwrap_or(palette.background),
+                text: colors.text.unwrap_or(palette.text),
+                primary: colors.primary.unwrap_or(palette.primary),
+                success: colors.success.unwrap_or(palette.success),
+                danger: colors.danger.unwrap_or(palette.danger),
+            };
+
+            return Appearance {
+                theme: iced::Theme::custom(custom.name.clone(), palette),
+                panes: custom.panes,
+                syntax: custom.syntax,
+            };
+        }
+
+        Appearance {
+            theme: builtin(name).unwrap_or(iced::Theme::Ferra),
+            panes: Panes::default(),
+            syntax: Syntax::default(),
+        }
+    }
+}
+
+fn builtin(name: &str) -> Option<iced::Theme> {
+    iced::Theme::ALL
+        .iter()
+        .find(|theme| theme.to_string() == name)
+        .cloned()
+}
+
+fn default_base() -> String {
+    iced::Theme::Ferra.to_string()
+}
+
+fn read(path: &Path) -> Result<Option<Custom>, Error> {
+    let Some(source) = super::read_source(path)? else {
+        return Ok(None);
+    };
+
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::from_str(&source).map(Some).map_err(|error| {
+            // The location is reported separately.
+            let message = error.to_string();
+            let message = message.split(" at line ").next().unwrap_or_default();
+
+            Error::Parse {
+                path: path.to_owned(),
+                line: error.line(),
+                column: error.column(),
+                message: message.to_owned(),
+            }
+        })
+    } else {
+        super::from_toml(path, &source).map(Some)
+    }
+}
+
+/// Deserializes a `#rrggbb` or `#rrggbbaa` color.
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+
+    Color::parse(&hex)
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid color `{hex}`")))
+}
@@ -0,0 +1,234 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A file or directory moved to the trash, to be restored on undo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trashed {
+    /// Where it was.
+    pub original: PathBuf,
+    /// Where it is in the trash.
+    pub file: PathBuf,
+    /// The file describing it in the trash, if the trash has one.
+    pub info: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Moves a file or directory to the trash of the user.
+///
+/// On Linux and other Unix systems, the trash follows the freedesktop.org
+/// specification, in the home trash. On macOS, the file is moved to
+/// `~/.Trash`.
+pub async fn trash(path: PathBuf) -> Result<Trashed, Error> {
+    let original = path.clone();
+
+    tokio::task::spawn_blocking(move || put(&path))
+        .await
+        .unwrap_or_else(|error| Err(io::Error::other(error)))
+        .map_err(|error| self::error(&original, error))
+}
+
+/// Moves a file back from the trash to where it was, unless something took
+/// its place.
+pub async fn restore(trashed: Trashed) -> Result<PathBuf, Error> {
+    let original = trashed.original.clone();
+
+    tokio::task::spawn_blocking(move || {
+        if trashed.original.exists() {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+
+        relocate(&trashed.file, &trashed.original)?;
+
+        if let Some(info) = &trashed.info {
+            std::fs::remove_file(info)?;
+        }
+
+        Ok(trashed.original)
+    })
+    .await
+    .unwrap_or_else(|error| Err(io::Error::other(error)))
+    .map_err(|error| self::error(&original, error))
+}
+
+/// Moves a file or directory, copying it over if it is on another file
+/// system.
+pub fn relocate(from: &Path, to: &Path) -> io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(error) if error.kind() == io::ErrorKind::CrossesDevices => {
+            copy(from, to)?;
+
+            if from.is_dir() {
+                std::fs::remove_dir_all(from)
+            } else {
+                std::fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+/// Copies a file, or a directory and its contents.
+pub fn copy(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(from)?;
+
+    if metadata.is_dir() {
+        std::fs::create_dir(to)?;
+
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+
+            copy(&entry.path(), &to.join(entry.file_name()))?;
+        }
+
+        Ok(())
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn put(path: &Path) -> io::Result<Trashed> {
+    use std::io::Write;
+
+    use crate::date::DateTime;
+
+    let original = std::path::absolute(path)?;
+    let name = original
+        .file_name()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?
+        .to_string_lossy()
+        .into_owned();
+
+    let trash = dirs::data_dir()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?
+        .join("Trash");
+    let files = trash.join("files");
+    let infos = trash.join("info");
+
+    std::fs::create_dir_all(&files)?;
+    std::fs::create_dir_all(&infos)?;
+
+    // The info file is created first, atomically, to claim the name.
+    for attempt in 1.. {
+        let candidate = if attempt == 1 {
+            name.clone()
+        } else {
+            format!("{name}.{attempt}")
+        };
+
+        let info = infos.join(format!("{candidate}.trashinfo"));
+        let file = files.join(&candidate);
+
+        if file.exists() {
+            continue;
+        }
+
+        let mut handle = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info)
+        {
+            Ok(handle) => handle,
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        };
+
+        write!(
+            handle,
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            encode(&original),
+            DateTime::now().iso8601(),
+        )?;
+
+        if let Err(error) = relocate(&original, &file) {
+            let _ = std::fs::remove_file(&info);
+
+            return Err(error);
+        }
+
+        return Ok(Trashed {
+            original,
+            file,
+            info: Some(info),
+        });
+    }
+
+    unreachable!("names are tried until one is free")
+}
+
+#[cfg(target_os = "macos")]
+fn put(path: &Path) -> io::Result<Trashed> {
+    let original = std::path::absolute(path)?;
+    let name = original
+        .file_name()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?
+        .to_string_lossy()
+        .into_owned();
+
+    let trash = dirs::home_dir()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?
+        .join(".Trash");
+
+    let file = (1..)
+        .map(|attempt| {
+            trash.join(if attempt == 1 {
+                name.clone()
+            } else {
+                format!("{name} {attempt}")
+            })
+        })
+        .find(|file| !file.exists())
+        .unwrap_or_else(|| trash.join(&name));
+
+    relocate(&original, &file)?;
+
+    Ok(Trashed {
+        original,
+        file,
+        info: None,
+    })
+}
+
+#[cfg(not(unix))]
+fn put(_path: &Path) -> io::Result<Trashed> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "there is no trash on this system",
+    ))
+}
+
+/// Percent-encodes a path, as in the `Path` of info files.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn encode(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str()
+        .as_bytes()
+        .iter()
+        .map(|&byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn error(path: &Path, error: io::Error) -> Error {
+    Error {
+        path: path.to_owned(),
+        message: error.to_string(),
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
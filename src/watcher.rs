@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use iced::futures::{SinkExt, Stream, StreamExt};
+
+pub use notify::RecursiveMode;
+
+/// Streams the paths affected by every change under the watched paths.
+///
+/// Paths that cannot be watched, e.g. because they do not exist yet, are
+/// skipped.
+pub fn watch(paths: Vec<(PathBuf, RecursiveMode)>) -> impl Stream<Item = Vec<PathBuf>> {
+    iced::stream::channel(10, move |mut output| async move {
+        use notify::Watcher;
+
+        let (sender, mut receiver) = iced::futures::channel::mpsc::unbounded();
+
+        let Ok(mut watcher) = notify::recommended_watcher(move |event| {
+            let _ = sender.unbounded_send(event);
+        }) else {
+            return;
+        };
+
+        for (path, mode) in &paths {
+            let _ = watcher.watch(path, *mode);
+        }
+
+        while let Some(event) = receiver.next().await {
+            let Ok(event): Result<notify::Event, _> = event else {
+                continue;
+            };
+
+            if event.kind.is_access() {
+                continue;
+            }
+
+            let _ = output.send(event.paths).await;
+        }
+    })
+}